@@ -0,0 +1,171 @@
+//! Criterion benchmarks for the query and snapshot paths, run against a synthetic large repo.
+//! Run them with `cargo bench`, or `cargo bench -- snapshot` for a single group.
+
+#[allow(dead_code)]
+#[path = "../src/test_repo.rs"]
+mod test_repo;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Result;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use tempfile::TempDir;
+
+use gg_lib::{
+    gui_util::WorkerSession,
+    worker::queries::{LogQuery, LogQueryState},
+};
+
+use test_repo::mkrepo;
+
+const BENCH_COMMITS: usize = 2000;
+const BENCH_FILES: usize = 5000;
+const BENCH_PAGE_SIZE: usize = 100;
+
+/// extends the test repo with `files` files in a nested layout and `commits` commits on top of them;
+/// every tenth commit also gets a side branch, so that the log graph has more than one lane
+fn mklargerepo(commits: usize, files: usize) -> Result<TempDir> {
+    let repo = mkrepo();
+
+    for i in 0..files {
+        let path = synthetic_path(repo.path(), i);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, format!("synthetic file {i}\n"))?;
+    }
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+    ws.import_and_snapshot(true)?;
+
+    let mut tx = ws.start_transaction()?;
+    let base = ws.get_commit(ws.wc_id())?;
+    let tree_id = base.tree_id().clone();
+
+    let mut tip = base;
+    for i in 0..commits {
+        tip = tx
+            .mut_repo()
+            .new_commit(&ws.settings, vec![tip.id().clone()], tree_id.clone())
+            .set_description(format!("synthetic commit {i}"))
+            .write()?;
+
+        if i % 10 == 0 {
+            tx.mut_repo()
+                .new_commit(&ws.settings, vec![tip.id().clone()], tree_id.clone())
+                .set_description(format!("synthetic side branch {i}"))
+                .write()?;
+        }
+    }
+
+    tx.mut_repo().edit(ws.id().clone(), &tip)?;
+    ws.finish_transaction(tx, "generate synthetic history")?;
+
+    Ok(repo)
+}
+
+fn synthetic_path(root: &Path, i: usize) -> PathBuf {
+    root.join("synthetic")
+        .join(format!("dir{}", i % 32))
+        .join(format!("sub{}", i % 7))
+        .join(format!("file{i}.txt"))
+}
+
+fn query_log_pagination(c: &mut Criterion) {
+    let repo = mklargerepo(BENCH_COMMITS, 0).unwrap();
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path()).unwrap();
+
+    c.bench_function("query_log/first_page", |b| {
+        b.iter(|| {
+            ws.reset_operation_cache();
+            let revset = ws.evaluate_revset_str("all()").unwrap();
+            let state = LogQueryState::new("bench", BENCH_PAGE_SIZE);
+            let mut query = LogQuery::new(&ws, "all()", &*revset, state);
+            query.get_page().unwrap()
+        })
+    });
+
+    c.bench_function("query_log/all_pages", |b| {
+        b.iter(|| {
+            ws.reset_operation_cache();
+            let revset = ws.evaluate_revset_str("all()").unwrap();
+            let state = LogQueryState::new("bench", BENCH_PAGE_SIZE);
+            let mut query = LogQuery::new(&ws, "all()", &*revset, state);
+            let mut rows = 0;
+            loop {
+                let page = query.get_page().unwrap();
+                rows += page.rows.len();
+                if !page.has_more {
+                    break rows;
+                }
+            }
+        })
+    });
+}
+
+fn format_header(c: &mut Criterion) {
+    let repo = mklargerepo(BENCH_COMMITS, 0).unwrap();
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path()).unwrap();
+
+    let commits = ws
+        .resolve_multiple(ws.evaluate_revset_str("all()").unwrap())
+        .unwrap();
+
+    c.bench_function("format_header/uncached", |b| {
+        b.iter(|| {
+            ws.reset_operation_cache();
+            for commit in &commits {
+                ws.format_header(commit, None).unwrap();
+            }
+        })
+    });
+
+    c.bench_function("format_header/cached", |b| {
+        b.iter(|| {
+            for commit in &commits {
+                ws.format_header(commit, None).unwrap();
+            }
+        })
+    });
+}
+
+fn snapshot(c: &mut Criterion) {
+    let repo = mklargerepo(0, BENCH_FILES).unwrap();
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path()).unwrap();
+
+    c.bench_function("snapshot/unchanged", |b| {
+        b.iter(|| ws.import_and_snapshot(true).unwrap())
+    });
+
+    let mut edits = 0;
+    c.bench_function("snapshot/one_file_changed", |b| {
+        b.iter_batched(
+            || {
+                edits += 1;
+                fs::write(
+                    synthetic_path(repo.path(), edits % BENCH_FILES),
+                    format!("edit {edits}\n"),
+                )
+                .unwrap();
+            },
+            |_| ws.import_and_snapshot(true).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .sample_size(10)
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(5));
+    targets = query_log_pagination, format_header, snapshot
+}
+criterion_main!(benches);
@@ -0,0 +1,693 @@
+//! JSON-RPC access to the workers, so that editors and scripts can drive the same backend as the GUI
+//!
+//! Each instance listens on a Unix socket (TCP loopback on Windows) and writes a discovery file,
+//! `<pid>.json`, to an `automation` directory in the user's runtime or data directory. The file
+//! holds the address and a token; it and the socket are only accessible to the current user.
+//! Requests and responses are JSON-RPC 2.0, one per line, and a connection's first call must be
+//! `authenticate` with the token.
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Sender},
+        Arc,
+    },
+    thread,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    messages::{
+        AbandonRevisions, AddGitRemote, AddWorkspace, BackoutRevision, BreakStaleLock,
+        CheckoutRevision, CommitId, CopyChanges, CopyWorkspaceChanges, CreateCheckpoint,
+        CreateRevision, DeleteNamedQuery, DescribeRevision, DescribeRevisions, DiffCursor,
+        DiffStyle, DragPayload, DuplicateRevisions, EditRevisionMetadata, FetchRemote,
+        ForgetWorkspace, ImportPatches, InsertRevision, LogCount, LogFilter, MoveBranch,
+        MoveChanges, MoveRevision, MoveRevisions, MoveSource, MutationResult, PrefixResolution,
+        PushRemote, RedoOperation, RemoveGitRemote, RenameGitRemote, ResolveConflict,
+        RestoreCheckpoint, RestoreFileFromOperation, RestoreOperation, RevId, RevResult,
+        SaveNamedQuery, SetAnnotation, SetDefaultQuery, SetReviewState, SetTrunk, SignRevision,
+        SplitRevision, SyncReviewStates, TrackBranch, TreePath, UndoOperation, UntrackBranch,
+        WriteResolvedFile,
+    },
+    worker::{Mutation, SessionEvent},
+    AppState,
+};
+
+// gg has a single window, whose workers serve every connection
+const WINDOW_LABEL: &str = "main";
+// log queries are paged statefully, so scripts get their own pane rather than moving the GUI's
+const AUTOMATION_PANE: &str = "automation";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+pub const UNAUTHENTICATED: i64 = -32001;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct Discovery {
+    address: String,
+    token: String,
+    pid: u32,
+}
+
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(err: anyhow::Error) -> Self {
+        RpcError {
+            code: SERVER_ERROR,
+            message: format!("{err:#}"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AuthenticateParams {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct QueryLogParams {
+    revset: String,
+    #[serde(default)]
+    filter: LogFilter,
+    page_size: Option<usize>,
+    count: Option<LogCount>,
+}
+
+#[derive(Deserialize)]
+struct RevisionParams {
+    id: RevId,
+}
+
+#[derive(Deserialize)]
+struct SelectionParams {
+    #[serde(default)]
+    ids: Vec<RevId>,
+}
+
+#[derive(Deserialize)]
+struct ChangeHistoryParams {
+    id: RevId,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct PrefixParams {
+    prefix: String,
+}
+
+#[derive(Deserialize)]
+struct DragPayloadParams {
+    op_id: String,
+    payloads: Vec<DragPayload>,
+}
+
+#[derive(Deserialize)]
+struct RevisionDiffParams {
+    id: RevId,
+    style: DiffStyle,
+    after: Option<DiffCursor>,
+    page_size: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct RevisionTreeParams {
+    id: RevId,
+    dir: Option<TreePath>,
+}
+
+#[derive(Deserialize)]
+struct FileParams {
+    id: RevId,
+    path: TreePath,
+}
+
+#[derive(Deserialize)]
+struct FileHistoryParams {
+    id: RevId,
+    path: TreePath,
+    after: Option<CommitId>,
+    page_size: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct FileDiffParams {
+    id: RevId,
+    path: TreePath,
+    style: DiffStyle,
+    after: Option<DiffCursor>,
+    page_size: Option<usize>,
+}
+
+/// listens in the background until the app exits; connections are served on their own threads
+pub fn start(app: AppHandle) -> Result<()> {
+    let dir = automation_dir()?;
+    fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+    restrict_to_user(&dir, 0o700)?;
+    remove_stale_instances(&dir);
+
+    let token = Arc::new(generate_token()?);
+    let address = spawn_listener(&dir, app, token.clone())?;
+
+    let discovery_path = dir.join(format!("{}.json", std::process::id()));
+    let discovery = Discovery {
+        address,
+        token: token.to_string(),
+        pid: std::process::id(),
+    };
+    // created empty and restricted before the token is written
+    fs::write(&discovery_path, "")
+        .with_context(|| format!("write {}", discovery_path.display()))?;
+    restrict_to_user(&discovery_path, 0o600)?;
+    fs::write(&discovery_path, serde_json::to_string_pretty(&discovery)?)
+        .with_context(|| format!("write {}", discovery_path.display()))?;
+
+    log::info!("automation endpoint at {}", discovery.address);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn spawn_listener(dir: &Path, app: AppHandle, token: Arc<String>) -> Result<String> {
+    use std::os::unix::net::UnixListener;
+
+    let socket_path = dir.join(format!("{}.sock", std::process::id()));
+    _ = fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path)
+        .with_context(|| format!("bind {}", socket_path.display()))?;
+    restrict_to_user(&socket_path, 0o600)?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream.and_then(|stream| Ok((stream.try_clone()?, stream))) {
+                Ok((reader, writer)) => {
+                    let app = app.clone();
+                    let token = token.clone();
+                    thread::spawn(move || {
+                        serve_connection(&token, reader, writer, |method, params| {
+                            dispatch(&app, method, params)
+                        })
+                    });
+                }
+                Err(err) => log::warn!("accept automation connection: {err}"),
+            }
+        }
+    });
+
+    Ok(socket_path.to_string_lossy().into_owned())
+}
+
+// named pipes aren't in std, so windows uses loopback TCP; the token is what keeps other users out
+#[cfg(not(unix))]
+fn spawn_listener(_dir: &Path, app: AppHandle, token: Arc<String>) -> Result<String> {
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").context("bind loopback port")?;
+    let address = listener.local_addr()?.to_string();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream.and_then(|stream| Ok((stream.try_clone()?, stream))) {
+                Ok((reader, writer)) => {
+                    let app = app.clone();
+                    let token = token.clone();
+                    thread::spawn(move || {
+                        serve_connection(&token, reader, writer, |method, params| {
+                            dispatch(&app, method, params)
+                        })
+                    });
+                }
+                Err(err) => log::warn!("accept automation connection: {err}"),
+            }
+        }
+    });
+
+    Ok(address)
+}
+
+/// answers a connection's requests, passing them to `dispatch` once it has authenticated
+pub fn serve_connection(
+    token: &str,
+    reader: impl Read,
+    mut writer: impl Write,
+    mut dispatch: impl FnMut(&str, Value) -> Result<Value, RpcError>,
+) {
+    let mut authenticated = false;
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (id, result) = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let result = if request.method == "authenticate" {
+                    authenticate(token, request.params).map(|()| {
+                        authenticated = true;
+                        Value::Bool(true)
+                    })
+                } else if authenticated {
+                    dispatch(&request.method, request.params)
+                } else {
+                    Err(RpcError {
+                        code: UNAUTHENTICATED,
+                        message: "call authenticate first".to_owned(),
+                    })
+                };
+                (request.id, result)
+            }
+            Err(err) => (
+                Value::Null,
+                Err(RpcError {
+                    code: PARSE_ERROR,
+                    message: err.to_string(),
+                }),
+            ),
+        };
+
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(err) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": err.code, "message": err.message }
+            }),
+        };
+        if writeln!(writer, "{response}").is_err() {
+            break;
+        }
+    }
+}
+
+pub fn authenticate(token: &str, params: Value) -> Result<(), RpcError> {
+    let params: AuthenticateParams = parse_params(params)?;
+
+    // compared in constant time, so that the token can't be guessed a byte at a time
+    let matches = params.token.len() == token.len()
+        && params
+            .token
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0;
+
+    if matches {
+        Ok(())
+    } else {
+        Err(RpcError {
+            code: UNAUTHENTICATED,
+            message: "invalid token".to_owned(),
+        })
+    }
+}
+
+// mutations are named after the GUI's commands, and session recordings refer to them the same way
+macro_rules! mutations {
+    ($($name:literal => $mutation:ty),* $(,)?) => {
+        fn parse_mutation(
+            method: &str,
+            params: Value,
+        ) -> Option<Result<Box<dyn Mutation + Send + Sync>, RpcError>> {
+            match method {
+                $($name => Some(
+                    parse_params::<$mutation>(params)
+                        .map(|mutation| Box::new(mutation) as Box<dyn Mutation + Send + Sync>),
+                ),)*
+                _ => None,
+            }
+        }
+
+        /// the name of the command which sends a mutation
+        pub fn mutation_method(mutation: &dyn Mutation) -> Option<&'static str> {
+            let type_name = mutation.describe();
+            $(if type_name == std::any::type_name::<$mutation>() {
+                return Some($name);
+            })*
+            None
+        }
+    };
+}
+
+mutations! {
+    "checkout_revision" => CheckoutRevision,
+    "create_revision" => CreateRevision,
+    "insert_revision" => InsertRevision,
+    "describe_revision" => DescribeRevision,
+    "describe_revisions" => DescribeRevisions,
+    "edit_revision_metadata" => EditRevisionMetadata,
+    "sign_revision" => SignRevision,
+    "break_stale_lock" => BreakStaleLock,
+    "add_workspace" => AddWorkspace,
+    "forget_workspace" => ForgetWorkspace,
+    "duplicate_revisions" => DuplicateRevisions,
+    "abandon_revisions" => AbandonRevisions,
+    "move_revision" => MoveRevision,
+    "move_source" => MoveSource,
+    "move_revisions" => MoveRevisions,
+    "move_changes" => MoveChanges,
+    "split_revision" => SplitRevision,
+    "backout_revision" => BackoutRevision,
+    "import_patches" => ImportPatches,
+    "copy_changes" => CopyChanges,
+    "copy_workspace_changes" => CopyWorkspaceChanges,
+    "write_resolved_file" => WriteResolvedFile,
+    "resolve_conflict" => ResolveConflict,
+    "track_branch" => TrackBranch,
+    "untrack_branch" => UntrackBranch,
+    "move_branch" => MoveBranch,
+    "push_remote" => PushRemote,
+    "fetch_remote" => FetchRemote,
+    "add_git_remote" => AddGitRemote,
+    "remove_git_remote" => RemoveGitRemote,
+    "rename_git_remote" => RenameGitRemote,
+    "undo_operation" => UndoOperation,
+    "redo_operation" => RedoOperation,
+    "restore_operation" => RestoreOperation,
+    "restore_file_from_operation" => RestoreFileFromOperation,
+    "create_checkpoint" => CreateCheckpoint,
+    "restore_checkpoint" => RestoreCheckpoint,
+    "set_default_query" => SetDefaultQuery,
+    "save_named_query" => SaveNamedQuery,
+    "delete_named_query" => DeleteNamedQuery,
+    "set_annotation" => SetAnnotation,
+    "set_review_state" => SetReviewState,
+    "sync_review_states" => SyncReviewStates,
+    "set_trunk" => SetTrunk,
+}
+
+/// parses a mutation from a session recording
+pub fn parse_recorded_mutation(
+    method: &str,
+    params: Value,
+) -> Result<Box<dyn Mutation + Send + Sync>> {
+    match parse_mutation(method, params) {
+        Some(result) => result.map_err(|err| anyhow!("{method}: {}", err.message)),
+        None => Err(anyhow!("unknown mutation {method}")),
+    }
+}
+
+// methods are named after the GUI's commands and take the same arguments
+fn dispatch(app: &AppHandle, method: &str, params: Value) -> Result<Value, RpcError> {
+    if let Some(mutation) = parse_mutation(method, params.clone()) {
+        return mutate(app, mutation?);
+    }
+
+    match method {
+        "query_log" => query(app, params, |tx, params: QueryLogParams| {
+            SessionEvent::QueryLog {
+                tx,
+                pane: AUTOMATION_PANE.to_owned(),
+                query: params.revset,
+                filter: params.filter,
+                page_size: params.page_size,
+                count: params.count,
+            }
+        }),
+        "query_log_next_page" => query(app, params, |tx, ()| SessionEvent::QueryLogNextPage {
+            tx,
+            pane: AUTOMATION_PANE.to_owned(),
+        }),
+        "query_revision" => query(app, params, |tx, params: RevisionParams| {
+            SessionEvent::QueryRevision { tx, id: params.id }
+        }),
+        "query_children" => query(app, params, |tx, params: RevisionParams| {
+            SessionEvent::QueryChildren { tx, id: params.id }
+        }),
+        "query_workspaces" => query(app, params, |tx, ()| SessionEvent::QueryWorkspaces { tx }),
+        "query_annotations" => query(app, params, |tx, ()| SessionEvent::QueryAnnotations { tx }),
+        "query_review_states" => {
+            query(app, params, |tx, ()| SessionEvent::QueryReviewStates { tx })
+        }
+        "query_named_queries" => {
+            query(app, params, |tx, ()| SessionEvent::QueryNamedQueries { tx })
+        }
+        "list_available_commands" => query(app, params, |tx, params: SelectionParams| {
+            SessionEvent::ListAvailableCommands {
+                tx,
+                ids: params.ids,
+            }
+        }),
+        "query_change_history" => query(app, params, |tx, params: ChangeHistoryParams| {
+            SessionEvent::QueryChangeHistory {
+                tx,
+                id: params.id,
+                limit: params.limit,
+            }
+        }),
+        "query_revision_diff" => query(app, params, |tx, params: RevisionDiffParams| {
+            SessionEvent::QueryRevisionDiff {
+                tx,
+                id: params.id,
+                style: params.style,
+                after: params.after,
+                page_size: params.page_size,
+            }
+        }),
+        "query_revision_tree" => query(app, params, |tx, params: RevisionTreeParams| {
+            SessionEvent::QueryRevisionTree {
+                tx,
+                id: params.id,
+                dir: params.dir,
+            }
+        }),
+        "query_file_contents" => query(app, params, |tx, params: FileParams| {
+            SessionEvent::QueryFileContents {
+                tx,
+                id: params.id,
+                path: params.path,
+            }
+        }),
+        "query_blame" => query(app, params, |tx, params: FileParams| {
+            SessionEvent::QueryBlame {
+                tx,
+                id: params.id,
+                path: params.path,
+            }
+        }),
+        "query_file_history" => query(app, params, |tx, params: FileHistoryParams| {
+            SessionEvent::QueryFileHistory {
+                tx,
+                id: params.id,
+                path: params.path,
+                after: params.after,
+                page_size: params.page_size,
+            }
+        }),
+        "query_file_diff" => query(app, params, |tx, params: FileDiffParams| {
+            SessionEvent::QueryFileDiff {
+                tx,
+                id: params.id,
+                path: params.path,
+                style: params.style,
+                after: params.after,
+                page_size: params.page_size,
+            }
+        }),
+        "resolve_id_prefix" => query(app, params, |tx, params: PrefixParams| {
+            SessionEvent::ResolveIdPrefix {
+                tx,
+                prefix: params.prefix,
+            }
+        }),
+        "resolve_drag_payload" => query(app, params, |tx, params: DragPayloadParams| {
+            SessionEvent::ResolveDragPayload {
+                tx,
+                pane: AUTOMATION_PANE.to_owned(),
+                op_id: params.op_id,
+                payloads: params.payloads,
+            }
+        }),
+        "select_revision" => select_revision(app, params),
+        _ => Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: format!("unknown method {method}"),
+        }),
+    }
+}
+
+fn query<P: DeserializeOwned, T: Serialize>(
+    app: &AppHandle,
+    params: Value,
+    create_event: impl FnOnce(Sender<Result<T>>, P) -> SessionEvent,
+) -> Result<Value, RpcError> {
+    let params = parse_params(params)?;
+    let result = call(&app.state::<AppState>().get_reader(WINDOW_LABEL), |tx| {
+        create_event(tx, params)
+    })?;
+    Ok(serde_json::to_value(result).map_err(anyhow::Error::from)?)
+}
+
+/// the GUI is updated as if it had run the command itself
+fn mutate(app: &AppHandle, mutation: Box<dyn Mutation + Send + Sync>) -> Result<Value, RpcError> {
+    let session_tx = app.state::<AppState>().get_sender(WINDOW_LABEL);
+    let (call_tx, call_rx) = channel();
+    session_tx
+        .send(SessionEvent::ExecuteMutation {
+            tx: call_tx,
+            mutation,
+        })
+        .map_err(|_| anyhow!("worker stopped"))?;
+    let result = call_rx.recv().context("worker stopped")?;
+
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let emitted = match &result {
+            MutationResult::Updated { new_status }
+            | MutationResult::Rebased { new_status, .. }
+            | MutationResult::Fetched { new_status, .. } => {
+                window.emit("gg://repo/status", new_status)
+            }
+            MutationResult::UpdatedSelection {
+                new_status,
+                new_selection,
+            } => window
+                .emit("gg://repo/status", new_status)
+                .and_then(|()| window.emit("gg://revision/select", new_selection)),
+            _ => Ok(()),
+        };
+        if let Err(err) = emitted {
+            log::warn!("emit automation result: {err:#}");
+        }
+    }
+
+    Ok(serde_json::to_value(result).map_err(anyhow::Error::from)?)
+}
+
+/// takes any id prefix, such as a full commit hash from an editor, and brings the window to front
+fn select_revision(app: &AppHandle, params: Value) -> Result<Value, RpcError> {
+    let params: PrefixParams = parse_params(params)?;
+    let reader = app.state::<AppState>().get_reader(WINDOW_LABEL);
+
+    let resolution = call(&reader, |tx| SessionEvent::ResolveIdPrefix {
+        tx,
+        prefix: params.prefix,
+    })?;
+    if let PrefixResolution::Resolved { id } = &resolution {
+        let revision = call(&reader, |tx| SessionEvent::QueryRevision {
+            tx,
+            id: id.clone(),
+        })?;
+        if let (RevResult::Detail { header, .. }, Some(window)) =
+            (revision, app.get_webview_window(WINDOW_LABEL))
+        {
+            window
+                .emit("gg://revision/select", header)
+                .context("emit selection")?;
+            if let Err(err) = window.set_focus() {
+                log::warn!("focus window: {err:#}");
+            }
+        }
+    }
+
+    Ok(serde_json::to_value(resolution).map_err(anyhow::Error::from)?)
+}
+
+fn call<T>(
+    session_tx: &Sender<SessionEvent>,
+    create_event: impl FnOnce(Sender<Result<T>>) -> SessionEvent,
+) -> Result<T> {
+    let (call_tx, call_rx) = channel();
+    session_tx
+        .send(create_event(call_tx))
+        .map_err(|_| anyhow!("worker stopped"))?;
+    call_rx.recv().context("worker stopped")?
+}
+
+// methods without arguments accept null or {}, as do those whose arguments all have defaults
+pub fn parse_params<P: DeserializeOwned>(params: Value) -> Result<P, RpcError> {
+    let alternative = match &params {
+        Value::Null => Some(json!({})),
+        Value::Object(map) if map.is_empty() => Some(Value::Null),
+        _ => None,
+    };
+
+    serde_json::from_value(params)
+        .or_else(|err| {
+            alternative
+                .and_then(|alternative| serde_json::from_value(alternative).ok())
+                .ok_or(err)
+        })
+        .map_err(|err| RpcError {
+            code: INVALID_PARAMS,
+            message: err.to_string(),
+        })
+}
+
+// XDG_RUNTIME_DIR is cleared on logout, so it's preferred where it exists
+fn automation_dir() -> Result<PathBuf> {
+    let base = dirs::runtime_dir()
+        .or_else(dirs::data_local_dir)
+        .ok_or(anyhow!("no runtime or data directory"))?;
+    Ok(base.join("gg").join("automation"))
+}
+
+// instances which exited without cleaning up leave files behind; their pids may since have been reused
+fn remove_stale_instances(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") && !is_listening(&path) {
+            _ = fs::remove_file(&path);
+            _ = fs::remove_file(path.with_extension("sock"));
+        }
+    }
+}
+
+fn is_listening(discovery_path: &Path) -> bool {
+    let Some(address) = fs::read_to_string(discovery_path)
+        .ok()
+        .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+        .and_then(|discovery| discovery["address"].as_str().map(str::to_owned))
+    else {
+        return false;
+    };
+
+    #[cfg(unix)]
+    let connection = std::os::unix::net::UnixStream::connect(address).map(|_| ());
+    #[cfg(not(unix))]
+    let connection = std::net::TcpStream::connect(address).map(|_| ());
+
+    connection.is_ok()
+}
+
+fn generate_token() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|err| anyhow!("generate token: {err}"))?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+#[cfg(unix)]
+fn restrict_to_user(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .with_context(|| format!("set permissions of {}", path.display()))
+}
+
+// the per-user data directory is already private on windows
+#[cfg(not(unix))]
+fn restrict_to_user(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
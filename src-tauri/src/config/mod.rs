@@ -1,10 +1,56 @@
-use jj_lib::settings::UserSettings;
+use std::{collections::HashMap, fs, io, path::Path, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, FixedOffset,
+};
+use jj_cli::config::LayeredConfigs;
+use jj_lib::{
+    settings::{HumanByteSize, UserSettings},
+    signing::Signer,
+};
+use toml_edit::DocumentMut;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+// jj's own default, which it uses when snapshot.max-new-file-size is unset
+const DEFAULT_MAX_NEW_FILE_SIZE: &str = "1MiB";
+
+const DEFAULT_AGE_BUCKETS: [(&str, &str); 4] = [
+    ("today", "1d"),
+    ("week", "1w"),
+    ("month", "30d"),
+    ("year", "1y"),
+];
 
 pub trait GGSettings {
     fn query_large_repo_heuristic(&self) -> i64;
     fn query_auto_snapshot(&self) -> Option<bool>;
     fn query_check_immutable(&self) -> Option<bool>;
+    fn query_warm_up(&self) -> Option<bool>;
+    fn operations_snapshots(&self) -> String;
+    fn watcher_enabled(&self) -> Option<bool>;
+    fn watcher_ignore(&self) -> Vec<String>;
+    fn autosave_interval(&self) -> Option<Duration>;
+    fn autosave_keep(&self) -> usize;
+    fn template_id_length(&self) -> usize;
+    fn template_age_buckets(&self) -> Vec<(String, Duration)>;
     fn ui_theme_override(&self) -> Option<String>;
+    fn ui_time_zone(&self) -> String;
+    fn ui_relative_dates(&self) -> bool;
+    fn ui_date_format(&self) -> String;
+    fn push_max_file_size(&self) -> Option<u64>;
+    fn push_scan_secrets(&self) -> bool;
+    fn git_run_hooks(&self) -> bool;
+    fn rebase_skip_empty(&self) -> bool;
+    fn signing_backend(&self) -> Option<String>;
+    fn automation_enabled(&self) -> bool;
+    fn crash_reports_enabled(&self) -> bool;
+    fn tool_timeout(&self) -> Option<Duration>;
+    fn describe_suggest_command(&self) -> Option<Vec<String>>;
+    fn editor_command(&self) -> Option<Vec<String>>;
+    fn web_template(&self, key: &str) -> Option<String>;
 }
 
 impl GGSettings for UserSettings {
@@ -22,7 +68,371 @@ impl GGSettings for UserSettings {
         self.config().get_bool("gg.queries.check-immutable").ok()
     }
 
+    fn query_warm_up(&self) -> Option<bool> {
+        self.config().get_bool("gg.queries.warm-up").ok()
+    }
+
+    fn operations_snapshots(&self) -> String {
+        self.config()
+            .get_string("gg.operations.snapshots")
+            .ok()
+            .filter(|policy| ["show", "collapse", "hide"].contains(&policy.as_str()))
+            .unwrap_or_else(|| "collapse".to_owned())
+    }
+
+    fn watcher_enabled(&self) -> Option<bool> {
+        self.config().get_bool("gg.watcher.enabled").ok()
+    }
+
+    fn watcher_ignore(&self) -> Vec<String> {
+        self.config()
+            .get::<Vec<String>>("gg.watcher.ignore")
+            .unwrap_or_default()
+    }
+
+    fn autosave_interval(&self) -> Option<Duration> {
+        self.config()
+            .get_int("gg.autosave.interval")
+            .ok()
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes as u64 * 60))
+    }
+
+    fn autosave_keep(&self) -> usize {
+        self.config()
+            .get_int("gg.autosave.keep")
+            .ok()
+            .filter(|keep| *keep >= 0)
+            .map_or(24, |keep| keep as usize)
+    }
+
+    fn template_id_length(&self) -> usize {
+        self.config()
+            .get_int("gg.template.id-length")
+            .ok()
+            .filter(|length| *length > 0)
+            .map_or(8, |length| length as usize)
+    }
+
+    fn template_age_buckets(&self) -> Vec<(String, Duration)> {
+        let buckets = match self
+            .config()
+            .get::<HashMap<String, String>>("gg.template.age-buckets")
+        {
+            Ok(buckets) => buckets,
+            Err(_) => DEFAULT_AGE_BUCKETS
+                .iter()
+                .map(|(name, limit)| (name.to_string(), limit.to_string()))
+                .collect(),
+        };
+
+        let mut buckets: Vec<_> = buckets
+            .into_iter()
+            .filter_map(|(name, limit)| Some((name, parse_age_limit(&limit)?)))
+            .collect();
+        buckets.sort_by_key(|(_, limit)| *limit);
+        buckets
+    }
+
     fn ui_theme_override(&self) -> Option<String> {
         self.config().get_string("gg.ui.theme-override").ok()
     }
+
+    fn ui_time_zone(&self) -> String {
+        self.config()
+            .get_string("gg.ui.time-zone")
+            .unwrap_or_else(|_| "local".to_owned())
+    }
+
+    fn ui_relative_dates(&self) -> bool {
+        self.config()
+            .get_bool("gg.ui.relative-dates")
+            .unwrap_or(false)
+    }
+
+    // an invalid format would make chrono fail at display time
+    fn ui_date_format(&self) -> String {
+        self.config()
+            .get_string("gg.ui.date-format")
+            .ok()
+            .filter(|format| !StrftimeItems::new(format).any(|item| item == Item::Error))
+            .unwrap_or_else(|| DEFAULT_DATE_FORMAT.to_owned())
+    }
+
+    fn push_max_file_size(&self) -> Option<u64> {
+        self.config()
+            .get::<HumanByteSize>("gg.push.max-file-size")
+            .ok()
+            .map(|size| size.0)
+    }
+
+    fn push_scan_secrets(&self) -> bool {
+        self.config()
+            .get_bool("gg.push.scan-secrets")
+            .unwrap_or(false)
+    }
+
+    fn git_run_hooks(&self) -> bool {
+        self.config().get_bool("gg.git.run-hooks").unwrap_or(false)
+    }
+
+    fn rebase_skip_empty(&self) -> bool {
+        self.config()
+            .get_bool("gg.rebase.skip-empty")
+            .unwrap_or(false)
+    }
+
+    fn signing_backend(&self) -> Option<String> {
+        self.config()
+            .get_string("signing.backend")
+            .ok()
+            .filter(|backend| backend != "none")
+    }
+
+    fn automation_enabled(&self) -> bool {
+        self.config()
+            .get_bool("gg.automation.enabled")
+            .unwrap_or(false)
+    }
+
+    fn crash_reports_enabled(&self) -> bool {
+        self.config()
+            .get_bool("gg.crash-reports.enabled")
+            .unwrap_or(false)
+    }
+
+    fn tool_timeout(&self) -> Option<Duration> {
+        self.config()
+            .get_int("gg.tools.timeout")
+            .ok()
+            .filter(|seconds| *seconds > 0)
+            .map(|seconds| Duration::from_secs(seconds as u64))
+    }
+
+    fn describe_suggest_command(&self) -> Option<Vec<String>> {
+        self.config()
+            .get::<Vec<String>>("gg.describe.suggest-command")
+            .ok()
+            .filter(|command| !command.is_empty())
+    }
+
+    fn editor_command(&self) -> Option<Vec<String>> {
+        self.config()
+            .get::<Vec<String>>("gg.editor.command")
+            .ok()
+            .filter(|command| !command.is_empty())
+    }
+
+    fn web_template(&self, key: &str) -> Option<String> {
+        self.config().get_string(&format!("gg.web.{key}")).ok()
+    }
+}
+
+/// jj's defaults, overridden by gg's
+pub fn default_config() -> Result<::config::Config> {
+    Ok(::config::Config::builder()
+        .add_source(jj_cli::config::default_config())
+        .add_source(::config::File::from_str(
+            include_str!("gg.toml"),
+            ::config::FileFormat::Toml,
+        ))
+        .build()?)
+}
+
+/// replaces values which gg or jj can't read with defaults, so that a config shared with another version of jj
+/// doesn't stop workspaces from loading; returns a message describing each value replaced
+pub fn repair_config(config: ::config::Config) -> Result<(::config::Config, Vec<String>)> {
+    let mut repairs = Vec::new();
+
+    // every gg setting with a default is in gg.toml, so configured values must have the same types
+    let defaults = ::config::Config::builder()
+        .add_source(::config::File::from_str(
+            include_str!("gg.toml"),
+            ::config::FileFormat::Toml,
+        ))
+        .build()?;
+    check_type(
+        &config,
+        "gg",
+        &defaults.get::<::config::Value>("gg")?,
+        &mut repairs,
+    );
+
+    // jj's settings which are read while loading a workspace, and which would fail it
+    let settings = UserSettings::from_config(config.clone());
+    if let Err(err) = Signer::from_settings(&settings) {
+        repairs.push((
+            "signing.backend".to_owned(),
+            ::config::Value::from("none"),
+            format!("{err}; commits won't be signed"),
+        ));
+    }
+    if let Err(err) = settings.fsmonitor_kind() {
+        repairs.push((
+            "core.fsmonitor".to_owned(),
+            ::config::Value::from("none"),
+            err.to_string(),
+        ));
+    }
+    if let Err(err) = settings.max_new_file_size() {
+        repairs.push((
+            "snapshot.max-new-file-size".to_owned(),
+            ::config::Value::from(DEFAULT_MAX_NEW_FILE_SIZE),
+            err.to_string(),
+        ));
+    }
+
+    if repairs.is_empty() {
+        return Ok((config, Vec::new()));
+    }
+
+    let mut builder = ::config::Config::builder().add_source(config);
+    let mut messages = Vec::new();
+    for (key, default, problem) in repairs {
+        messages.push(format!(
+            "Ignoring {key}, using the default instead: {problem}"
+        ));
+        builder = builder.set_override(key, default)?;
+    }
+    Ok((builder.build()?, messages))
+}
+
+// tables are checked value by value, since the configured ones are merged into the defaults
+fn check_type(
+    config: &::config::Config,
+    key: &str,
+    default: &::config::Value,
+    repairs: &mut Vec<(String, ::config::Value, String)>,
+) {
+    let result = match &default.kind {
+        ::config::ValueKind::Boolean(_) => config.get_bool(key).map(|_| ()),
+        ::config::ValueKind::I64(_) => config.get_int(key).map(|_| ()),
+        ::config::ValueKind::String(_) => config.get_string(key).map(|_| ()),
+        ::config::ValueKind::Array(_) => config.get_array(key).map(|_| ()),
+        ::config::ValueKind::Table(table) => config.get_table(key).map(|_| {
+            for (name, value) in table {
+                check_type(config, &format!("{key}.{name}"), value, repairs);
+            }
+        }),
+        _ => Ok(()),
+    };
+
+    if let Err(err) = result {
+        repairs.push((key.to_owned(), default.clone(), err.to_string()));
+    }
+}
+
+/// settings which apply before any workspace is open, so repo config is not read
+pub fn read_user_settings() -> Result<UserSettings> {
+    let mut configs = LayeredConfigs::from_environment(default_config()?);
+    configs.read_user_config()?;
+    Ok(UserSettings::from_config(configs.merge()))
+}
+
+/// commits written by gg are signed whenever jj has a signing backend, unless sign-all is explicitly false
+pub fn sign_by_default(config: ::config::Config) -> Result<::config::Config> {
+    let has_backend = config
+        .get_string("signing.backend")
+        .is_ok_and(|backend| backend != "none");
+    if !has_backend || config.get_bool("signing.sign-all").is_ok() {
+        return Ok(config);
+    }
+
+    Ok(::config::Config::builder()
+        .add_source(config)
+        .set_override("signing.sign-all", true)?
+        .build()?)
+}
+
+/// stands in for the clock, the user and the machine, so that tests and demos write identical commits and operations every run
+#[derive(Clone, Debug)]
+pub struct FakeEnvironment {
+    pub start: DateTime<FixedOffset>,
+    pub user_name: String,
+    pub user_email: String,
+    pub hostname: String,
+    pub username: String,
+    /// transactions started so far, each of which moves the clock forward by a second
+    pub ticks: u64,
+}
+
+impl Default for FakeEnvironment {
+    fn default() -> Self {
+        FakeEnvironment {
+            start: DateTime::parse_from_rfc3339("2001-02-03T04:05:06+07:00")
+                .expect("parse fake start time"),
+            user_name: "Test User".to_owned(),
+            user_email: "test.user@example.com".to_owned(),
+            hostname: "host.example.com".to_owned(),
+            username: "test-username".to_owned(),
+            ticks: 0,
+        }
+    }
+}
+
+impl FakeEnvironment {
+    /// jj reads its timestamps and random ids from debug settings when they're present
+    pub fn apply(&self, config: ::config::Config) -> Result<::config::Config> {
+        let now = (self.start + chrono::Duration::seconds(self.ticks as i64)).to_rfc3339();
+        Ok(::config::Config::builder()
+            .add_source(config)
+            .set_override("user.name", self.user_name.as_str())?
+            .set_override("user.email", self.user_email.as_str())?
+            .set_override("operation.hostname", self.hostname.as_str())?
+            .set_override("operation.username", self.username.as_str())?
+            .set_override("debug.commit-timestamp", now.as_str())?
+            .set_override("debug.operation-timestamp", now.as_str())?
+            // settings are rebuilt for each tick, which would restart a fixed seed's sequence
+            .set_override("debug.randomness-seed", self.ticks as i64)?
+            .build()?)
+    }
+
+    /// advances the clock, returning settings for the next transaction
+    pub fn tick(&mut self, settings: &UserSettings) -> Result<UserSettings> {
+        self.ticks += 1;
+        Ok(UserSettings::from_config(
+            self.apply(settings.config().clone())?,
+        ))
+    }
+}
+
+// "30m", "12h", "7d", "2w" or "1y"
+fn parse_age_limit(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let unit = text.chars().last()?;
+    let count: u64 = text[..text.len() - unit.len_utf8()].trim().parse().ok()?;
+    let seconds = match unit {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        'y' => 365 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(count * seconds))
+}
+
+/// sets a value in a jj config file, keeping the rest of the file's formatting and comments
+pub fn write_config_value(path: &Path, key: &[&str], value: &str) -> Result<()> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+    };
+    let mut document: DocumentMut = text
+        .parse()
+        .with_context(|| format!("parse {}", path.display()))?;
+
+    let (name, table_names) = key.split_last().ok_or(anyhow!("empty config key"))?;
+    let mut table = document.as_table_mut();
+    for table_name in table_names {
+        table = table
+            .entry(table_name)
+            .or_insert(toml_edit::table())
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("{table_name} in {} is not a table", path.display()))?;
+    }
+    table[name] = toml_edit::value(value);
+
+    fs::write(path, document.to_string()).with_context(|| format!("write {}", path.display()))
 }
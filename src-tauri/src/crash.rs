@@ -0,0 +1,354 @@
+//! Local crash reports for panics and internal errors, written only if the user opts in
+//! Reports are scrubbed of paths, identities and ids which could identify the repo, so that they can be attached to issues
+
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    cell::RefCell,
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf, MAIN_SEPARATOR_STR},
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use jj_lib::settings::UserSettings;
+use regex::{Captures, Regex};
+use serde::Serialize;
+
+use crate::{config::GGSettings, gui_util::WorkspaceSession};
+
+thread_local! {
+    /// details of the last panic on this thread, which catch_unwind can't provide
+    static LAST_PANIC: RefCell<Option<PanicDetails>> = RefCell::new(None);
+}
+
+struct PanicDetails {
+    message: String,
+    location: Option<String>,
+    backtrace: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CrashKind {
+    Panic,
+    Error,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CrashReport {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    pub time: String,
+    /// the command which was running
+    pub command: String,
+    pub kind: CrashKind,
+    pub message: String,
+    /// causes of an error, outermost first
+    pub chain: Vec<String>,
+    pub location: Option<String>,
+    pub backtrace: Option<String>,
+}
+
+impl CrashReport {
+    pub fn from_error(command: &str, err: &anyhow::Error) -> CrashReport {
+        CrashReport {
+            kind: CrashKind::Error,
+            message: err.to_string(),
+            chain: err.chain().skip(1).map(|cause| cause.to_string()).collect(),
+            backtrace: Some(err.backtrace().to_string()),
+            ..CrashReport::new(command)
+        }
+    }
+
+    /// uses the details recorded by the panic hook, if it saw this panic
+    pub fn from_panic(command: &str, payload: &(dyn Any + Send)) -> CrashReport {
+        match LAST_PANIC.with(|last_panic| last_panic.take()) {
+            Some(details) => CrashReport {
+                kind: CrashKind::Panic,
+                message: details.message,
+                location: details.location,
+                backtrace: Some(details.backtrace),
+                ..CrashReport::new(command)
+            },
+            None => CrashReport {
+                kind: CrashKind::Panic,
+                message: panic_message(payload),
+                ..CrashReport::new(command)
+            },
+        }
+    }
+
+    fn new(command: &str) -> CrashReport {
+        CrashReport {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            os: std::env::consts::OS.to_owned(),
+            arch: std::env::consts::ARCH.to_owned(),
+            time: Utc::now().to_rfc3339(),
+            command: command.to_owned(),
+            kind: CrashKind::Error,
+            message: String::new(),
+            chain: Vec::new(),
+            location: None,
+            backtrace: None,
+        }
+    }
+
+    pub fn scrub(self, scrubber: &Scrubber) -> CrashReport {
+        CrashReport {
+            command: scrubber.scrub(&self.command),
+            message: scrubber.scrub(&self.message),
+            chain: self
+                .chain
+                .iter()
+                .map(|cause| scrubber.scrub(cause))
+                .collect(),
+            location: self.location.map(|location| scrubber.scrub(&location)),
+            backtrace: self.backtrace.map(|backtrace| scrubber.scrub(&backtrace)),
+            ..self
+        }
+    }
+
+    /// the message as the user saw it
+    pub fn summary(&self) -> String {
+        format!("{}: {}", self.command, self.message)
+    }
+}
+
+/// shorter hex or k-z runs are too likely to be ordinary words
+const MIN_SCRUBBED_ID_LENGTH: usize = 6;
+
+/// names so common that they say nothing about a repo, and which would otherwise mangle ordinary text
+const GENERIC_NAMES: [&str; 6] = ["main", "master", "trunk", "develop", "origin", "upstream"];
+
+/// replaces text which could identify a repo or its owner with placeholders
+pub struct Scrubber {
+    literals: Vec<(String, &'static str)>,
+    patterns: Vec<(Regex, &'static str)>,
+    /// branch, remote and file names, which are replaced only as whole words
+    names: HashMap<String, &'static str>,
+    words: Regex,
+}
+
+impl Scrubber {
+    pub fn new(settings: &UserSettings, paths: &[(&Path, &'static str)]) -> Scrubber {
+        let mut literals: Vec<(String, &'static str)> = paths
+            .iter()
+            .map(|(path, placeholder)| (path.to_string_lossy().into_owned(), *placeholder))
+            .collect();
+        if let Some(home_dir) = dirs::home_dir() {
+            literals.push((home_dir.to_string_lossy().into_owned(), "~"));
+        }
+        literals.push((settings.user_email(), "<email>"));
+        literals.push((settings.user_name(), "<user>"));
+        literals.retain(|(text, _)| !text.is_empty());
+        // a path's parent would otherwise be replaced first, leaving the rest of it behind
+        literals.sort_by_key(|(text, _)| std::cmp::Reverse(text.len()));
+
+        // ids are usually shown abbreviated, so prefixes as short as the templates use must go too
+        let id_length = settings.template_id_length().max(MIN_SCRUBBED_ID_LENGTH);
+        let patterns = [
+            (r"[a-zA-Z][a-zA-Z0-9+.-]*://\S+".to_owned(), "<url>"),
+            (r"[\w.+-]+@[\w-]+(\.[\w-]+)+".to_owned(), "<email>"),
+            (format!(r"\b[0-9a-f]{{{id_length},40}}\b"), "<commit-id>"),
+            (format!(r"\b[k-z]{{{id_length},32}}\b"), "<change-id>"),
+        ]
+        .into_iter()
+        .map(|(pattern, placeholder)| (Regex::new(&pattern).expect("scrub pattern"), placeholder))
+        .collect();
+
+        Scrubber {
+            literals,
+            patterns,
+            names: HashMap::new(),
+            words: Regex::new(r"[\w./\\-]+").expect("scrub pattern"),
+        }
+    }
+
+    /// also replaces these names wherever they appear as a whole word
+    pub fn with_names(
+        mut self,
+        names: impl IntoIterator<Item = (String, &'static str)>,
+    ) -> Scrubber {
+        for (name, placeholder) in names {
+            if !name.is_empty() && !GENERIC_NAMES.contains(&name.as_str()) {
+                self.names.entry(name).or_insert(placeholder);
+            }
+        }
+        self
+    }
+
+    pub fn scrub(&self, text: &str) -> String {
+        let mut text = text.to_owned();
+        for (literal, placeholder) in &self.literals {
+            text = text.replace(literal, placeholder);
+        }
+        for (pattern, placeholder) in &self.patterns {
+            text = pattern
+                .replace_all(&text, |caps: &Captures| {
+                    // sizes, pids and line numbers can be as long as an id prefix
+                    if caps[0].bytes().all(|b| b.is_ascii_digit()) {
+                        caps[0].to_owned()
+                    } else {
+                        (*placeholder).to_owned()
+                    }
+                })
+                .into_owned();
+        }
+        if !self.names.is_empty() {
+            text = self
+                .words
+                .replace_all(&text, |caps: &Captures| {
+                    let word = &caps[0];
+                    let name = word.trim_matches(|c| matches!(c, '.' | '/' | '\\'));
+                    match self.names.get(name) {
+                        Some(placeholder) => word.replacen(name, placeholder, 1),
+                        None => word.to_owned(),
+                    }
+                })
+                .into_owned();
+        }
+        text
+    }
+}
+
+/// records each panic's details for CrashReport::from_panic, then reports it as usual
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let details = PanicDetails {
+            message: panic_message(info.payload()),
+            location: info.location().map(|location| location.to_string()),
+            backtrace: Backtrace::force_capture().to_string(),
+        };
+        LAST_PANIC.with(|last_panic| *last_panic.borrow_mut() = Some(details));
+
+        default_hook(info);
+    }));
+}
+
+// panic!() with a format string has a String payload, and with a literal a &str
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic!()".to_owned())
+}
+
+/// writes a scrubbed report if gg.crash-reports.enabled is set, returning its path
+pub fn report(ws: &WorkspaceSession, report: CrashReport) -> Option<PathBuf> {
+    if !ws.settings.crash_reports_enabled() {
+        return None;
+    }
+
+    let mut scrubber = Scrubber::new(
+        &ws.settings,
+        &[
+            (ws.workspace_root(), "<workspace>"),
+            (ws.repo_dir(), "<repo>"),
+        ],
+    );
+    match repo_names(ws) {
+        Ok(names) => scrubber = scrubber.with_names(names),
+        Err(err) => log::warn!("list names to scrub: {err:#}"),
+    }
+    let result = report_dir().and_then(|dir| write_report(&dir, &report.scrub(&scrubber)));
+    match result {
+        Ok(path) => Some(path),
+        Err(err) => {
+            log::error!("write crash report: {err:#}");
+            None
+        }
+    }
+}
+
+/// names chosen by the user: branches, remotes, and the files and directories in the working copy
+fn repo_names(ws: &WorkspaceSession) -> Result<Vec<(String, &'static str)>> {
+    let mut names = Vec::new();
+
+    for (branch_name, _) in ws.view().local_branches() {
+        names.push((branch_name.to_owned(), "<branch>"));
+    }
+
+    if let Some(git_repo) = ws.git_repo()? {
+        for remote_name in git_repo.remotes()?.iter().flatten() {
+            names.push((remote_name.to_owned(), "<remote>"));
+            for (branch_name, _) in ws.view().remote_branches(remote_name) {
+                names.push((branch_name.to_owned(), "<branch>"));
+            }
+        }
+    }
+
+    let mut push_path = |path: &str| {
+        names.push((path.to_owned(), "<path>"));
+        if MAIN_SEPARATOR_STR != "/" {
+            names.push((path.replace('/', MAIN_SEPARATOR_STR), "<path>"));
+        }
+    };
+    for (repo_path, _) in ws.get_commit(ws.wc_id())?.tree()?.entries() {
+        let path = repo_path.as_internal_file_string();
+        for (index, _) in path.match_indices('/') {
+            push_path(&path[..index]);
+        }
+        push_path(path);
+    }
+
+    Ok(names)
+}
+
+/// an error often follows the panic which caused it, so reports from the same moment get a suffix
+/// rather than replacing each other
+pub fn write_report(dir: &Path, report: &CrashReport) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+
+    let contents = serde_json::to_string_pretty(report)?;
+    let stem = format!(
+        "crash-{}-{}",
+        Utc::now().format("%Y%m%d-%H%M%S-%3f"),
+        std::process::id()
+    );
+    let mut attempt = 0;
+    loop {
+        let path = match attempt {
+            0 => dir.join(format!("{stem}.json")),
+            n => dir.join(format!("{stem}-{n}.json")),
+        };
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())
+                    .with_context(|| format!("write {}", path.display()))?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(err) => return Err(err).with_context(|| format!("create {}", path.display())),
+        }
+    }
+}
+
+pub fn report_dir() -> Result<PathBuf> {
+    let base = dirs::data_local_dir().ok_or(anyhow!("no data directory"))?;
+    Ok(base.join("gg").join("crash-reports"))
+}
+
+/// shows a report in the system's file manager; only files gg wrote can be revealed
+pub fn reveal(ws: &WorkspaceSession, path: &Path) -> Result<u32> {
+    let report_dir = report_dir()?;
+    if path.parent() != Some(report_dir.as_path()) || !path.is_file() {
+        return Err(anyhow!("{} is not a crash report", path.display()));
+    }
+
+    let (program, args) = if cfg!(windows) {
+        ("explorer", vec![format!("/select,{}", path.display())])
+    } else if cfg!(target_os = "macos") {
+        ("open", vec!["-R".to_owned(), path.display().to_string()])
+    } else {
+        // file managers differ in how to select a file, but all of them can open its directory
+        ("xdg-open", vec![report_dir.display().to_string()])
+    };
+
+    ws.spawn_tool("file manager", Path::new(program), args, Vec::new(), None)
+}
@@ -0,0 +1,35 @@
+//! Forge API tokens, kept in the OS keychain rather than in gg's own state or jj's config
+
+use anyhow::{Context, Result};
+use keyring::Entry;
+
+use crate::messages::Forge;
+
+const SERVICE: &str = "gg";
+
+fn entry(forge: Forge, host: &str) -> Result<Entry> {
+    let account = format!("{forge:?}/{}", host.to_lowercase());
+    Entry::new(SERVICE, &account).with_context(|| format!("keychain entry for {account}"))
+}
+
+/// a missing token is not an error; integrations should fall back to anonymous access
+pub fn get_token(forge: Forge, host: &str) -> Result<Option<String>> {
+    match entry(forge, host)?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err).context("read token from keychain"),
+    }
+}
+
+pub fn set_token(forge: Forge, host: &str, token: &str) -> Result<()> {
+    entry(forge, host)?
+        .set_password(token.trim())
+        .context("write token to keychain")
+}
+
+pub fn clear_token(forge: Forge, host: &str) -> Result<()> {
+    match entry(forge, host)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(err) => Err(err).context("remove token from keychain"),
+    }
+}
@@ -0,0 +1,649 @@
+//! Shared async HTTP client for forge APIs. Responses are cached by ETag, concurrent requests for
+//! the same URL are coalesced, and rate limits are respected, so integrations which make a
+//! request per visible commit don't exhaust the user's quota.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use futures_util::lock::Mutex as AsyncMutex;
+use serde::{de::DeserializeOwned, Deserialize};
+use tauri::async_runtime;
+
+use jj_lib::settings::UserSettings;
+
+use crate::{
+    config::GGSettings,
+    credentials,
+    messages::{Forge, ForgeProject, GitRemote, WebTarget},
+};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+// responses this recent are reused without even a conditional request
+const FRESH_FOR: Duration = Duration::from_secs(10);
+// after this long, a response isn't worth keeping just for its etag
+const EVICT_AFTER: Duration = Duration::from_secs(60 * 60);
+// used when a forge signals a limit without saying when it resets
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(60);
+
+struct CachedResponse {
+    etag: Option<String>,
+    body: String,
+    fetched: Instant,
+}
+
+/// what's needed from a response once it has been read, off the async runtime
+struct Fetched {
+    status: u16,
+    etag: Option<String>,
+    body: Option<String>,
+    retry_after: Option<u64>,
+    remaining: Option<u64>,
+    reset: Option<u64>,
+}
+
+pub struct ForgeClient {
+    agent: ureq::Agent,
+    cache: Mutex<HashMap<String, CachedResponse>>,
+    in_flight: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+    /// limits are per user and host, so one forge being limited doesn't stop requests to another
+    backoff_until: Mutex<HashMap<String, Instant>>,
+}
+
+/// the client is shared by all windows, since rate limits apply per user rather than per repo
+pub fn client() -> &'static ForgeClient {
+    static CLIENT: OnceLock<ForgeClient> = OnceLock::new();
+    CLIENT.get_or_init(|| ForgeClient {
+        agent: ureq::AgentBuilder::new()
+            .timeout(TIMEOUT)
+            .user_agent(concat!("gg/", env!("CARGO_PKG_VERSION")))
+            .build(),
+        cache: Mutex::new(HashMap::new()),
+        in_flight: Mutex::new(HashMap::new()),
+        backoff_until: Mutex::new(HashMap::new()),
+    })
+}
+
+impl ForgeClient {
+    /// stale data is returned while rate-limited
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        forge: Forge,
+        host: &str,
+        url: &str,
+    ) -> Result<T> {
+        let body = self.get(forge, host, url).await?;
+        serde_json::from_str(&body).with_context(|| format!("parse response from {url}"))
+    }
+
+    async fn get(&self, forge: Forge, host: &str, url: &str) -> Result<String> {
+        // a second request for the same url waits for the first, then finds its response cached
+        let url_lock = self
+            .in_flight
+            .lock()
+            .expect("in-flight table poisoned")
+            .entry(url.to_owned())
+            .or_default()
+            .clone();
+        let guard = url_lock.lock().await;
+        let result = self.get_uncoalesced(forge, host, url).await;
+        drop(guard);
+
+        // the table holds one reference and this request another; any more are still waiting
+        let mut in_flight = self.in_flight.lock().expect("in-flight table poisoned");
+        if Arc::strong_count(&url_lock) == 2 {
+            in_flight.remove(url);
+        }
+
+        result
+    }
+
+    async fn get_uncoalesced(&self, forge: Forge, host: &str, url: &str) -> Result<String> {
+        let (etag, stale_body) = match self.cache.lock().expect("cache poisoned").get(url) {
+            Some(cached) if cached.fetched.elapsed() < FRESH_FOR => {
+                return Ok(cached.body.clone());
+            }
+            Some(cached) => (cached.etag.clone(), Some(cached.body.clone())),
+            None => (None, None),
+        };
+
+        if self.is_backing_off(host) {
+            return stale_body.ok_or(anyhow!("{host} rate limit exceeded; try again later"));
+        }
+
+        let mut request = self.agent.get(url);
+        if let Some(etag) = &etag {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(token) = credentials::get_token(forge, host)? {
+            match forge {
+                Forge::GitHub | Forge::GitLab => {
+                    request = request.set("Authorization", &format!("Bearer {token}"));
+                }
+                // gerrit's read APIs are anonymous unless the /a/ prefix is used
+                Forge::Gerrit => (),
+            }
+        }
+
+        // ureq blocks, so requests run on the runtime's blocking pool rather than a worker
+        let fetched = async_runtime::spawn_blocking(move || fetch(request))
+            .await
+            .context("join request")?
+            .with_context(|| format!("GET {url}"))?;
+        self.observe_limits(host, &fetched);
+
+        match fetched.status {
+            304 => {
+                if let Some(cached) = self.cache.lock().expect("cache poisoned").get_mut(url) {
+                    cached.fetched = Instant::now();
+                    return Ok(cached.body.clone());
+                }
+                Err(anyhow!("GET {url}: not modified, but nothing was cached"))
+            }
+            429 => {
+                if !self.is_backing_off(host) {
+                    self.back_off(host, DEFAULT_BACKOFF);
+                }
+                stale_body.ok_or(anyhow!("{host} rate limit exceeded (HTTP 429)"))
+            }
+            // github also uses 403 for exhausted quotas, which observe_limits has already backed off from
+            403 if fetched.retry_after.is_some() || fetched.remaining == Some(0) => {
+                stale_body.ok_or(anyhow!("{host} rate limit exceeded (HTTP 403)"))
+            }
+            403 => Err(anyhow!(
+                "{host} denied access (HTTP 403); the token may be invalid, lack a scope or need SSO authorization"
+            )),
+            _ => {
+                let body = strip_xssi_prefix(forge, fetched.body.unwrap_or_default());
+                let mut cache = self.cache.lock().expect("cache poisoned");
+                cache.retain(|_, cached| cached.fetched.elapsed() < EVICT_AFTER);
+                cache.insert(
+                    url.to_owned(),
+                    CachedResponse {
+                        etag: fetched.etag,
+                        body: body.clone(),
+                        fetched: Instant::now(),
+                    },
+                );
+                Ok(body)
+            }
+        }
+    }
+
+    fn is_backing_off(&self, host: &str) -> bool {
+        self.backoff_until
+            .lock()
+            .expect("backoff poisoned")
+            .get(host)
+            .is_some_and(|until| Instant::now() < *until)
+    }
+
+    fn back_off(&self, host: &str, duration: Duration) {
+        log::warn!("{host} rate limit reached; backing off for {duration:?}");
+        let mut backoff_until = self.backoff_until.lock().expect("backoff poisoned");
+        let now = Instant::now();
+        backoff_until.retain(|_, until| now < *until);
+        backoff_until.insert(host.to_owned(), now + duration);
+    }
+
+    // github and gitlab both report quota in headers; retry-after is used for secondary limits
+    fn observe_limits(&self, host: &str, fetched: &Fetched) {
+        if let Some(seconds) = fetched.retry_after {
+            self.back_off(host, Duration::from_secs(seconds));
+            return;
+        }
+
+        if fetched.remaining != Some(0) {
+            return;
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        match fetched.reset {
+            Some(reset) if reset > now => self.back_off(host, Duration::from_secs(reset - now)),
+            _ => self.back_off(host, DEFAULT_BACKOFF),
+        }
+    }
+}
+
+// rate-limited and forbidden responses are read like any other, since their headers say which they are
+fn fetch(request: ureq::Request) -> Result<Fetched> {
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, response)) if status == 403 || status == 429 => response,
+        Err(err) => return Err(err.into()),
+    };
+
+    let status = response.status();
+    let etag = response.header("ETag").map(|etag| etag.to_owned());
+    let retry_after = header_number(&response, "Retry-After");
+    let remaining = header_number(&response, "X-RateLimit-Remaining")
+        .or_else(|| header_number(&response, "RateLimit-Remaining"));
+    let reset = header_number(&response, "X-RateLimit-Reset")
+        .or_else(|| header_number(&response, "RateLimit-Reset"));
+    let body = match status {
+        200..=299 => Some(response.into_string().context("read response")?),
+        _ => None,
+    };
+
+    Ok(Fetched {
+        status,
+        etag,
+        body,
+        retry_after,
+        remaining,
+        reset,
+    })
+}
+
+fn header_number(response: &ureq::Response, name: &str) -> Option<u64> {
+    response.header(name)?.trim().parse().ok()
+}
+
+// gerrit prefixes JSON responses to prevent them being executed as scripts
+fn strip_xssi_prefix(forge: Forge, body: String) -> String {
+    match forge {
+        Forge::Gerrit => body
+            .strip_prefix(")]}'")
+            .map(|body| body.trim_start().to_owned())
+            .unwrap_or(body),
+        _ => body,
+    }
+}
+
+fn github_api(host: &str) -> String {
+    if host == "github.com" {
+        "https://api.github.com".to_owned()
+    } else {
+        format!("https://{host}/api/v3")
+    }
+}
+
+#[derive(Deserialize)]
+struct GitHubUser {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabUser {
+    username: String,
+}
+
+/// checks a stored token by asking the forge who it belongs to
+pub async fn query_token_user(forge: Forge, host: &str) -> Result<String> {
+    let client = client();
+    match forge {
+        Forge::GitHub => Ok(client
+            .get_json::<GitHubUser>(forge, host, &format!("{}/user", github_api(host)))
+            .await?
+            .login),
+        Forge::GitLab => Ok(client
+            .get_json::<GitLabUser>(forge, host, &format!("https://{host}/api/v4/user"))
+            .await?
+            .username),
+        Forge::Gerrit => Err(anyhow!("Gerrit tokens can't be checked")),
+    }
+}
+
+/// recognises remotes on the public forges, and on self-hosted instances with conventional names
+pub fn parse_remote_url(url: &str) -> Option<ForgeProject> {
+    // scp-like urls have no scheme, e.g. git@github.com:owner/repo.git
+    let (authority, path) = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        None => url.split_once(':')?,
+    };
+    let authority = authority.rsplit('@').next()?;
+    let (host, port) = match authority.split_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    };
+    let host = host.to_lowercase();
+    let project = path.trim_matches('/').trim_end_matches(".git");
+
+    let forge = if host == "github.com" || host.starts_with("github.") {
+        Forge::GitHub
+    } else if host == "gitlab.com" || host.starts_with("gitlab.") {
+        Forge::GitLab
+    } else if port == Some("29418") || host.starts_with("gerrit.") || host.starts_with("review.") {
+        Forge::Gerrit
+    } else {
+        return None;
+    };
+
+    let project = match forge {
+        Forge::GitHub if project.split('/').count() != 2 => return None,
+        // authenticated http access to gerrit uses an /a/ prefix
+        Forge::Gerrit => project.strip_prefix("a/").unwrap_or(project),
+        _ => project,
+    };
+    if project.is_empty() {
+        return None;
+    }
+
+    Some(ForgeProject {
+        forge,
+        host,
+        project: project.to_owned(),
+    })
+}
+
+/// integrations are configured for the upstream of a fork, if there is one
+pub fn select_upstream(remotes: &[GitRemote]) -> Option<ForgeProject> {
+    ["upstream", "origin"]
+        .into_iter()
+        .find_map(|name| {
+            remotes
+                .iter()
+                .find(|remote| remote.name == name)?
+                .project
+                .clone()
+        })
+        .or_else(|| remotes.iter().find_map(|remote| remote.project.clone()))
+}
+
+/// builds a link to a forge's web UI from the configured or default templates; placeholders
+/// are {host}, {project}, {commit}, {branch}, {path} and {line}
+pub fn format_web_url(
+    settings: &UserSettings,
+    upstream: Option<&ForgeProject>,
+    target: &WebTarget,
+) -> Option<String> {
+    let template = |key: &str| {
+        settings
+            .web_template(key)
+            .or_else(|| upstream.map(|upstream| default_web_template(upstream.forge, key)))
+    };
+
+    let mut url = match target {
+        WebTarget::Commit { id } => template("commit-url")?.replace("{commit}", &id.hex),
+        WebTarget::Branch { name } => template("branch-url")?.replace("{branch}", name),
+        WebTarget::File { id, path, line } => {
+            let mut url = template("file-url")?
+                .replace("{commit}", &id.hex)
+                .replace("{path}", &encode_path(&path.repo_path));
+            if let Some(line) = line {
+                url.push_str(&template("line-anchor")?.replace("{line}", &line.to_string()));
+            }
+            url
+        }
+    };
+
+    if let Some(upstream) = upstream {
+        url = url
+            .replace("{host}", &upstream.host)
+            .replace("{project}", &upstream.project);
+    }
+
+    Some(url)
+}
+
+fn default_web_template(forge: Forge, key: &str) -> String {
+    let template = match (forge, key) {
+        (Forge::GitHub, "commit-url") => "https://{host}/{project}/commit/{commit}",
+        (Forge::GitHub, "branch-url") => "https://{host}/{project}/tree/{branch}",
+        (Forge::GitHub, "file-url") => "https://{host}/{project}/blob/{commit}/{path}",
+        (Forge::GitHub, _) => "#L{line}",
+        (Forge::GitLab, "commit-url") => "https://{host}/{project}/-/commit/{commit}",
+        (Forge::GitLab, "branch-url") => "https://{host}/{project}/-/tree/{branch}",
+        (Forge::GitLab, "file-url") => "https://{host}/{project}/-/blob/{commit}/{path}",
+        (Forge::GitLab, _) => "#L{line}",
+        // gerrit hosts its repository browser, gitiles, as a plugin
+        (Forge::Gerrit, "commit-url") => "https://{host}/plugins/gitiles/{project}/+/{commit}",
+        (Forge::Gerrit, "branch-url") => {
+            "https://{host}/plugins/gitiles/{project}/+/refs/heads/{branch}"
+        }
+        (Forge::Gerrit, "file-url") => "https://{host}/plugins/gitiles/{project}/+/{commit}/{path}",
+        (Forge::Gerrit, _) => "#{line}",
+    };
+    template.to_owned()
+}
+
+// repo paths are already /-separated, but may contain characters with meaning in urls
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '%' => encoded.push_str("%25"),
+            ' ' => encoded.push_str("%20"),
+            '#' => encoded.push_str("%23"),
+            '?' => encoded.push_str("%3F"),
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// a pull request, merge request or change, as linked from a forge's web UI
+#[derive(Debug, PartialEq)]
+pub struct ReviewUrl {
+    pub forge: Forge,
+    pub host: String,
+    pub project: String,
+    pub number: u64,
+}
+
+/// recognises the review URL formats of each forge, without making any requests
+pub fn parse_review_url(url: &str) -> Option<ReviewUrl> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let rest = rest.split(['?', '#']).next()?;
+    let mut segments = rest.split('/').filter(|segment| !segment.is_empty());
+    let host = segments.next()?.to_owned();
+    let segments: Vec<&str> = segments.collect();
+
+    // github.com/owner/repo/pull/123
+    if let [owner, repo, "pull", number, ..] = segments.as_slice() {
+        return Some(ReviewUrl {
+            forge: Forge::GitHub,
+            host,
+            project: format!("{owner}/{repo}"),
+            number: number.parse().ok()?,
+        });
+    }
+
+    // gitlab.com/group/subgroup/project/-/merge_requests/123
+    if let Some(dash) = segments.iter().position(|segment| *segment == "-") {
+        if let ["merge_requests", number, ..] = &segments[dash + 1..] {
+            return Some(ReviewUrl {
+                forge: Forge::GitLab,
+                host,
+                project: segments[..dash].join("/"),
+                number: number.parse().ok()?,
+            });
+        }
+    }
+
+    // gerrit.example.com/c/project/+/123/4
+    if let (Some(&"c"), Some(plus)) = (
+        segments.first(),
+        segments.iter().position(|segment| *segment == "+"),
+    ) {
+        return Some(ReviewUrl {
+            forge: Forge::Gerrit,
+            host,
+            project: segments[1..plus].join("/"),
+            number: segments.get(plus + 1)?.parse().ok()?,
+        });
+    }
+
+    None
+}
+
+/// the latest commit under review, and the branch it was pushed from if the forge has one
+pub struct ReviewHead {
+    pub commit: String,
+    pub branch: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubPull {
+    head: GitHubPullHead,
+}
+
+#[derive(Deserialize)]
+struct GitHubPullHead {
+    sha: String,
+    #[serde(rename = "ref")]
+    branch: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabMergeRequest {
+    sha: String,
+    source_branch: String,
+}
+
+#[derive(Deserialize)]
+struct GerritChange {
+    current_revision: String,
+}
+
+pub async fn query_review_head(review: &ReviewUrl) -> Result<ReviewHead> {
+    let client = client();
+    let ReviewUrl {
+        forge,
+        host,
+        project,
+        number,
+    } = review;
+
+    match forge {
+        Forge::GitHub => {
+            let url = format!("{}/repos/{project}/pulls/{number}", github_api(host));
+            let pull: GitHubPull = client.get_json(*forge, host, &url).await?;
+            Ok(ReviewHead {
+                commit: pull.head.sha,
+                branch: Some(pull.head.branch),
+            })
+        }
+        Forge::GitLab => {
+            let url = format!(
+                "https://{host}/api/v4/projects/{}/merge_requests/{number}",
+                project.replace('/', "%2F")
+            );
+            let merge_request: GitLabMergeRequest = client.get_json(*forge, host, &url).await?;
+            Ok(ReviewHead {
+                commit: merge_request.sha,
+                branch: Some(merge_request.source_branch),
+            })
+        }
+        Forge::Gerrit => {
+            let url = format!("https://{host}/changes/{number}?o=CURRENT_REVISION");
+            let change: GerritChange = client.get_json(*forge, host, &url).await?;
+            Ok(ReviewHead {
+                commit: change.current_revision,
+                branch: None,
+            })
+        }
+    }
+}
+
+/// where a review stands on its forge
+pub struct ReviewStatus {
+    pub is_open: bool,
+    pub is_approved: bool,
+    pub is_merged: bool,
+}
+
+#[derive(Deserialize)]
+struct GitHubPullState {
+    state: String,
+    #[serde(default)]
+    merged: bool,
+}
+
+#[derive(Deserialize)]
+struct GitHubReview {
+    state: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabMergeRequestState {
+    state: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabApprovals {
+    #[serde(default)]
+    approved: bool,
+}
+
+#[derive(Deserialize)]
+struct GerritChangeStatus {
+    status: String,
+    #[serde(default)]
+    labels: HashMap<String, GerritLabel>,
+}
+
+#[derive(Deserialize)]
+struct GerritLabel {
+    approved: Option<serde_json::Value>,
+}
+
+pub async fn query_review_status(review: &ReviewUrl) -> Result<ReviewStatus> {
+    let client = client();
+    let ReviewUrl {
+        forge,
+        host,
+        project,
+        number,
+    } = review;
+
+    match forge {
+        Forge::GitHub => {
+            let url = format!("{}/repos/{project}/pulls/{number}", github_api(host));
+            let pull: GitHubPullState = client.get_json(*forge, host, &url).await?;
+            let reviews: Vec<GitHubReview> = client
+                .get_json(*forge, host, &format!("{url}/reviews"))
+                .await?;
+
+            // comments don't change a review's outcome, but requesting changes overrides earlier approvals
+            let verdict = reviews
+                .iter()
+                .rev()
+                .find(|review| review.state != "COMMENTED")
+                .map(|review| review.state.as_str());
+
+            Ok(ReviewStatus {
+                is_open: pull.state == "open",
+                is_approved: verdict == Some("APPROVED"),
+                is_merged: pull.merged,
+            })
+        }
+        Forge::GitLab => {
+            let url = format!(
+                "https://{host}/api/v4/projects/{}/merge_requests/{number}",
+                project.replace('/', "%2F")
+            );
+            let merge_request: GitLabMergeRequestState =
+                client.get_json(*forge, host, &url).await?;
+            let approvals: GitLabApprovals = client
+                .get_json(*forge, host, &format!("{url}/approvals"))
+                .await?;
+
+            Ok(ReviewStatus {
+                is_open: merge_request.state == "opened",
+                is_approved: approvals.approved,
+                is_merged: merge_request.state == "merged",
+            })
+        }
+        Forge::Gerrit => {
+            let url = format!("https://{host}/changes/{number}?o=LABELS");
+            let change: GerritChangeStatus = client.get_json(*forge, host, &url).await?;
+
+            Ok(ReviewStatus {
+                is_open: change.status == "NEW",
+                is_approved: change
+                    .labels
+                    .get("Code-Review")
+                    .is_some_and(|label| label.approved.is_some()),
+                is_merged: change.status == "MERGED",
+            })
+        }
+    }
+}
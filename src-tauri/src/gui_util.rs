@@ -1,25 +1,27 @@
 //! Analogous to cli_util from jj-cli
 //! We reuse a bit of jj-cli code, but many of its modules include TUI concerns or are not suitable for a long-running server
 
-use std::{cell::OnceCell, collections::HashMap, env::VarError, path::{Path, PathBuf}, rc::Rc, sync::Arc};
+use std::{cell::{OnceCell, RefCell}, collections::{BTreeSet, HashMap}, env::VarError, fs, path::{Path, PathBuf}, rc::Rc, sync::{atomic::{AtomicUsize, Ordering}, mpsc::{Receiver, Sender}, Arc, Mutex}, time::{Duration, Instant, SystemTime}};
 
 use anyhow::{anyhow, Context, Result};
-use config::Config;
-use git2::Repository;
+use chrono::Local;
+use git2::{Oid, Repository};
 use itertools::Itertools;
 use jj_cli::{
     cli_util::{check_stale_working_copy, short_operation_hash, WorkingCopyFreshness},
     config::LayeredConfigs,
     git_util::is_colocated_git_workspace,
 };
-use jj_lib::{backend::BackendError, default_index::{AsCompositeIndex, DefaultReadonlyIndex}, file_util::relative_path, gitignore::GitIgnoreFile, op_store::WorkspaceId, repo::RepoLoaderError, repo_path::RepoPath, revset::{RevsetEvaluationError, RevsetIteratorExt, RevsetResolutionError}, rewrite, view::View, working_copy::{CheckoutStats, SnapshotOptions}};
+use jj_lib::{backend::BackendError, default_index::{AsCompositeIndex, DefaultReadonlyIndex}, file_util::relative_path, gitignore::GitIgnoreFile, matchers::EverythingMatcher, merged_tree::MergedTree, op_store::{OperationId, WorkspaceId}, repo::RepoLoaderError, repo_path::{RepoPath, RepoPathBuf}, revset::{RevsetEvaluationError, RevsetFilterPredicate, RevsetIteratorExt, RevsetResolutionError}, rewrite::{self, RebaseOptions, RebasedCommit}, view::View, working_copy::{CheckoutStats, SnapshotOptions, WorkingCopy}};
 use jj_lib::{
     backend::{ChangeId, CommitId},
     commit::Commit,
+    fsmonitor::FsmonitorKind,
     git,
     git_backend::GitBackend,
     hex_util::to_reverse_hex,
     id_prefix::IdPrefixContext,
+    local_working_copy::{LocalWorkingCopy, LocalWorkingCopyFactory},
     object_id::ObjectId,
     op_heads_store,
     operation::Operation,
@@ -29,24 +31,79 @@ use jj_lib::{
         RevsetParseContext, RevsetWorkspaceContext,
     },
     settings::{ConfigResultExt, UserSettings},
+    str_util::StringPattern,
     transaction::Transaction,
     workspace::{self, Workspace, WorkspaceLoader},
 };
 use thiserror::Error;
 
-use crate::{config::GGSettings, messages::{self, RevId}};
+use crate::{config::{FakeEnvironment, GGSettings}, forge, messages::{self, RevId}, processes::{ToolInvocation, ToolProcesses}, replay::Recorder, store::RepoStore, worker::{autosave::{self, AutosaveTimer}, queries::QueryCancellation, watcher::{self, WatchFilter}, SessionEvent}};
+
+/// operation metadata key identifying the gg command which produced an operation
+pub const ACTION_TAG: &str = "gg.action";
+
+/// operation metadata key recording the remote which an operation pushed to
+pub const PUSH_TAG: &str = "gg.push";
+
+/// description of operations which only record changes to the working copy, shared with jj
+pub const SNAPSHOT_DESCRIPTION: &str = "snapshot working copy";
+
+// a prompt nobody answers shouldn't block the worker forever
+const INPUT_TIMEOUT: Duration = Duration::from_secs(300);
+
+// snapshots quicker than this don't report progress at all
+const SNAPSHOT_PROGRESS_DELAY: Duration = Duration::from_millis(500);
+const SNAPSHOT_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// records a warning on a session, with the named values as its translation parameters
+macro_rules! warning {
+    ($ws:expr, $code:literal, $fmt:literal $(, $name:ident = $value:expr)* $(,)?) => {{
+        $(let $name = $value;)*
+        $ws.warn(
+            $code,
+            [$((stringify!($name).to_owned(), $name.to_string())),*].into_iter().collect(),
+            format!($fmt $(, $name = $name)*),
+        );
+    }};
+}
+
+pub(crate) use warning;
 
 /// state that doesn't depend on jj-lib borrowings
 pub struct WorkerSession {
     pub log_page_size: usize,
     pub latest_query: Option<String>,
+    /// revset and filter of each log pane's query, for requesting later pages
+    pub pane_queries: HashMap<String, (String, messages::LogFilter)>,
+    pub events: Option<Sender<messages::RepoEvent>>,
+    pub tools: ToolProcesses,
+    pub background_policy: messages::BackgroundPolicy,
+    /// readers serve queries from the latest operation while another session runs mutations
+    pub read_only: bool,
+    /// the worker's own queue, which background work such as watching for changes adds events to
+    pub queue: Option<Sender<SessionEvent>>,
+    /// lets commands stop queries which the worker is too busy to be told about through its queue
+    pub cancellation: QueryCancellation,
+    /// replaces the clock and identity, for tests and demos which need the same ids every run
+    pub fake_environment: Option<FakeEnvironment>,
+    /// writes each event to a session recording, if the user is capturing one
+    pub recorder: Option<Recorder>,
 }
 
 impl Default for WorkerSession {
     fn default() -> Self {
         WorkerSession {
             log_page_size: 1000, // XXX make configurable?
-            latest_query: None
+            latest_query: None,
+            pane_queries: HashMap::new(),
+            events: None,
+            tools: ToolProcesses::default(),
+            background_policy: messages::BackgroundPolicy::default(),
+            read_only: false,
+            queue: None,
+            cancellation: QueryCancellation::default(),
+            fake_environment: None,
+            recorder: None
         }
     }    
 }
@@ -60,6 +117,26 @@ pub struct WorkspaceSession<'a> {
     workspace: Workspace,
     aliases_map: RevsetAliasesMap,    
     is_large: bool,
+    pub(crate) store: RepoStore,
+    pub(crate) date_display: messages::DateDisplay,
+    /// the gg command currently being executed, recorded in operation metadata
+    pub(crate) action: Option<String>,
+    /// the operation reverted by the last undo, and the operation which reverted it
+    pub(crate) redo_target: Option<(OperationId, OperationId)>,
+    /// colocated git state when the current transaction started, and the operation it started from
+    git_fingerprint: Option<(OperationId, GitFingerprint)>,
+    /// the worker's event queue, read directly by commands which are blocked waiting for the user
+    pub(crate) input: Option<&'a Receiver<SessionEvent>>,
+    /// problems which didn't stop the current command, reported with its result
+    warnings: RefCell<Vec<messages::Warning>>,
+    /// settings which were ignored when the workspace was loaded, reported with every status
+    config_warnings: Vec<messages::Warning>,
+    /// snapshots the working copy when other programs change it, until dropped
+    watcher: Option<notify::RecommendedWatcher>,
+    /// loads operations written by other processes, until dropped
+    op_watcher: Option<notify::RecommendedWatcher>,
+    /// periodically snapshots and checkpoints the working copy, until dropped
+    autosave: Option<AutosaveTimer>,
 
     // operation-specific data, containing a repo view and derived extras
     operation: SessionOperation,
@@ -72,9 +149,23 @@ pub struct SessionOperation {
     pub wc_id: CommitId,
     branches_index: OnceCell<Rc<BranchIndex>>,
     prefix_context: OnceCell<Rc<IdPrefixContext>>,
-    immutable_revisions: OnceCell<Rc<RevsetExpression>>
+    immutable_revisions: OnceCell<Rc<RevsetExpression>>,
+    /// headers already formatted at this operation, keyed by commit and whether immutability was supplied
+    headers: RefCell<HashMap<(CommitId, Option<bool>), messages::RevHeader>>
+}
+
+/// the parts of a colocated git repo which other programs, such as IDE plugins, are likely to change
+#[derive(PartialEq, Eq, Debug)]
+struct GitFingerprint {
+    head: Option<(Option<String>, Option<Oid>)>,
+    index_modified: Option<SystemTime>
 }
 
+/// a colocated git repo changed while a transaction was in progress; retrying will start from the new state
+#[derive(Debug, Error)]
+#[error("The git repo was changed by another program while the command was running. Its changes have been imported; try again.")]
+pub struct GitChangedError;
+
 #[derive(Debug, Error)]
 pub enum RevsetError {
     #[error(transparent)]
@@ -95,17 +186,17 @@ impl WorkerSession {
     pub fn load_directory(&mut self, cwd: &Path) -> Result<WorkspaceSession> {
         let loader = WorkspaceLoader::init(find_workspace_dir(cwd))?;
 
-        let defaults = Config::builder()
-            .add_source(jj_cli::config::default_config())
-            .add_source(config::File::from_str(include_str!("config/gg.toml"), config::FileFormat::Toml))
-            .build()?;
-
-        let mut configs = LayeredConfigs::from_environment(defaults);
+        let mut configs = LayeredConfigs::from_environment(crate::config::default_config()?);
         configs.read_user_config()?;
         configs.read_repo_config(loader.repo_path())?;
-        let config = configs.merge();
+        let (config, mut config_problems) = crate::config::repair_config(configs.merge())?;
+        let mut config = crate::config::sign_by_default(config)?;
+        if let Some(fake_environment) = &self.fake_environment {
+            config = fake_environment.apply(config)?;
+        }
         let settings = UserSettings::from_config(config);
 
+        self.publish_load_stage(messages::LoadStage::LoadingOperation);
         let workspace = loader.load(
             &settings,
             &StoreFactories::default(),
@@ -114,6 +205,7 @@ impl WorkerSession {
 
         let operation = Self::load_at_head(&settings, &workspace)?;
 
+        self.publish_load_stage(messages::LoadStage::LoadingIndex);
         let index_store = workspace.repo_loader().index_store();
         let index = index_store
             .get_index_at_op(&operation.repo.operation(), workspace.repo_loader().store())?;
@@ -124,22 +216,63 @@ impl WorkerSession {
             true
         };
 
-        let aliases_map = build_aliases_map(&configs)?;
+        let aliases_map = build_aliases_map(&configs, &mut config_problems)?;
+        let config_warnings: Vec<messages::Warning> = config_problems
+            .into_iter()
+            .map(|message| {
+                log::warn!("{message}");
+                messages::Warning {
+                    code: "config-invalid".to_owned(),
+                    message,
+                }
+            })
+            .collect();
 
         let is_colocated = is_colocated_git_workspace(&workspace, &operation.repo);
 
+        let mut store = RepoStore::load(workspace.repo_path());
+        if let Err(err) = store.set_workspace_path(workspace.workspace_id().as_str(), workspace.workspace_root()) {
+            log::warn!("record workspace path: {err:#}");
+        }
+
+        let date_display = messages::DateDisplay {
+            time_zone: settings.ui_time_zone(),
+            relative: settings.ui_relative_dates(),
+            format: settings.ui_date_format(),
+        };
+
         Ok(WorkspaceSession {
             session: self,
             is_large,
             settings,
             workspace,
             aliases_map,
+            store,
+            date_display,
+            action: None,
+            redo_target: None,
+            git_fingerprint: None,
+            input: None,
+            warnings: RefCell::new(Vec::new()),
+            config_warnings,
+            watcher: None,
+            op_watcher: None,
+            autosave: None,
             operation,
             is_colocated
         })
     }
 
-        fn load_at_head(
+    /// only the session which writes to the working copy reports progress, since it does every stage
+    pub fn publish_load_stage(&self, stage: messages::LoadStage) {
+        if let (Some(events), false) = (&self.events, self.read_only) {
+            if events.send(messages::RepoEvent::LoadProgress { stage }).is_err() {
+                log::debug!("no subscribers for load progress");
+            }
+        }
+    }
+
+    fn load_at_head(
         settings: &UserSettings,
         workspace: &Workspace,
     ) -> Result<SessionOperation> {
@@ -203,10 +336,170 @@ impl WorkspaceSession<'_> {
         }
     }
 
+    pub fn workspace_root(&self) -> &Path {
+        self.workspace.workspace_root()
+    }
+
+    /// creates a workspace which shares this one's repo; jj records it in an operation of its own
+    pub fn add_workspace(&mut self, destination: &Path, workspace_id: WorkspaceId) -> Result<Workspace> {
+        let (workspace, _) = Workspace::init_workspace_with_existing_repo(
+            &self.settings,
+            destination,
+            &self.operation.repo,
+            &LocalWorkingCopyFactory {},
+            workspace_id,
+        )?;
+        self.load_latest()?;
+        Ok(workspace)
+    }
+
+    /// moves this session to a sibling workspace, keeping the loaded repo and its index rather than reopening them
+    pub fn switch_workspace(&mut self, name: &str) -> Result<()> {
+        let workspace_id = WorkspaceId::new(name.to_owned());
+        if self.operation.repo.view().get_wc_commit_id(&workspace_id).is_none() {
+            return Err(anyhow!(r#"No workspace named "{name}""#));
+        }
+
+        let workspace_root = self
+            .store
+            .workspace_path(name)
+            .ok_or_else(|| anyhow!(r#"The location of workspace "{name}" is unknown; open its directory instead"#))?
+            .to_owned();
+
+        // secondary workspaces have a file pointing at the repo instead of a directory
+        let repo_link = workspace_root.join(".jj").join("repo");
+        let repo_dir = if repo_link.is_file() {
+            workspace_root.join(".jj").join(fs::read_to_string(&repo_link)?.trim())
+        } else {
+            repo_link
+        };
+        let is_sibling = match (fs::canonicalize(&repo_dir), fs::canonicalize(self.repo_dir())) {
+            (Ok(other), Ok(this)) => other == this,
+            _ => false
+        };
+        if !is_sibling {
+            return Err(anyhow!("{} is not a workspace of this repo", workspace_root.display()));
+        }
+
+        let working_copy = LocalWorkingCopy::load(
+            self.operation.repo.store().clone(),
+            workspace_root.clone(),
+            workspace_root.join(".jj").join("working_copy"),
+        );
+        if working_copy.workspace_id() != &workspace_id {
+            return Err(anyhow!(r#"{} is not workspace "{name}""#, workspace_root.display()));
+        }
+        let workspace = Workspace::new(&workspace_root, Box::new(working_copy), self.workspace.repo_loader().clone())?;
+
+        self.is_colocated = is_colocated_git_workspace(&workspace, &self.operation.repo);
+        self.workspace = workspace;
+        self.git_fingerprint = None;
+        self.operation = SessionOperation::new(self.operation.repo.clone(), &workspace_id);
+        Ok(())
+    }
+
+    /// the repo's directory, which other workspaces may share
+    pub fn repo_dir(&self) -> &Path {
+        self.workspace.repo_path()
+    }
+
+    /// git commands also work on this workspace, and so git hooks and the git index are relevant
+    pub fn is_colocated(&self) -> bool {
+        self.is_colocated
+    }
+
     pub fn should_check_immutable(&self) -> bool {
         self.settings.query_check_immutable().unwrap_or(!self.is_large)
     }
 
+    /// chosen for this repo in gg, falling back to jj's revsets.log
+    pub fn default_query(&self) -> String {
+        match self.store.default_query() {
+            Some(query) => query.to_owned(),
+            None => self.settings.default_revset()
+        }
+    }
+
+    /// trunk() if it finds anything, otherwise a local main, master or trunk branch
+    pub fn find_trunk(&self) -> Option<CommitId> {
+        // trunk() is an alias which users may have broken, so this is best-effort
+        match self.evaluate_revset_str("trunk()") {
+            Ok(revset) => {
+                // jj's default trunk() falls back to the root commit, which isn't useful as a trunk
+                if let Some(id) = revset.iter().next().filter(|id| id != self.repo().store().root_commit_id()) {
+                    return Some(id);
+                }
+            }
+            Err(err) => warning!(self, "trunk-failed", "trunk() could not be evaluated: {error}", error = err)
+        }
+
+        ["main", "master", "trunk"]
+            .into_iter()
+            .find_map(|name| self.view().get_local_branch(name).as_normal().cloned())
+    }
+
+    /// jj reads this file along with the user's config
+    pub fn repo_config_path(&self) -> PathBuf {
+        self.workspace.repo_path().join("config.toml")
+    }
+
+    /// watches the workspace if this session can snapshot it, replacing any watcher for a previous workspace
+    pub fn start_watching(&mut self) {
+        self.watcher = None;
+        self.op_watcher = None;
+
+        let Some(queue) = self.session.queue.clone() else {
+            return;
+        };
+
+        // op heads are a single small directory, so they're watched even in large repos
+        let enabled = self.settings.watcher_enabled();
+        if enabled != Some(false) {
+            let repo_dir = self.repo_dir().to_owned();
+            match watcher::watch_operations(&repo_dir, queue.clone()) {
+                Ok(watcher) => self.op_watcher = Some(watcher),
+                Err(err) => warning!(self, "watch-failed", "Can't watch {path} for operations: {error}", path = repo_dir.display(), error = format!("{err:#}")),
+            }
+        }
+
+        if !enabled.unwrap_or(!self.is_large) {
+            return;
+        }
+
+        let root = self.workspace_root().to_owned();
+        let filter = WatchFilter::new(&root, &self.settings.watcher_ignore());
+        match watcher::watch(&root, filter, queue) {
+            Ok(watcher) => self.watcher = Some(watcher),
+            Err(err) => warning!(self, "watch-failed", "Can't watch {path} for changes: {error}", path = root.display(), error = format!("{err:#}")),
+        }
+    }
+
+    /// autosaves the workspace if configured and this session can snapshot it, replacing any timer for a previous workspace
+    pub fn start_autosave(&mut self) {
+        self.autosave = None;
+
+        if let (Some(queue), Some(interval)) = (self.session.queue.clone(), self.settings.autosave_interval()) {
+            self.autosave = Some(autosave::start(interval, queue));
+        }
+    }
+
+    pub fn should_warm_up(&self) -> bool {
+        self.settings.query_warm_up().unwrap_or(self.is_large)
+    }
+
+    /// catches up with operations committed by other sessions; returns true if there were any
+    pub fn load_latest(&mut self) -> Result<bool> {
+        self.store = RepoStore::load(self.workspace.repo_path());
+
+        let op_heads = self.workspace.repo_loader().op_heads_store().get_op_heads();
+        if op_heads.len() == 1 && op_heads[0] == *self.operation.repo.op_id() {
+            return Ok(false);
+        }
+
+        self.operation = WorkerSession::load_at_head(&self.settings, &self.workspace)?;
+        Ok(true)
+    }
+
     /***********************************************************/
     /* Functions for evaluating revset expressions             */
     /* unfortunately parse_context and resolver are not cached */
@@ -224,6 +517,36 @@ impl WorkspaceSession<'_> {
         self.evaluate_revset_expr(revset_expr)
     }
 
+    pub fn evaluate_filtered_revset_str<'op>(&'op self, revset_str: &str, filter: &messages::LogFilter) -> Result<Box<dyn Revset + 'op>, RevsetError> {
+        let mut revset_expr = parse_revset(&self.parse_context(), revset_str)?;
+        if !filter.paths.is_empty() || !filter.tree_paths.is_empty() {
+            let paths = filter.paths.iter()
+                .chain(filter.tree_paths.iter().map(|path| &path.repo_path))
+                .map(|path| RepoPathBuf::from_internal_string(path.trim_matches('/')))
+                .collect();
+            revset_expr = revset_expr.intersection(&RevsetExpression::filter(RevsetFilterPredicate::File(Some(paths))));
+        }
+        if let Some(author) = filter.author.as_ref().filter(|author| !author.is_empty()) {
+            revset_expr = revset_expr.intersection(&RevsetExpression::filter(RevsetFilterPredicate::Author(StringPattern::Substring(author.clone()))));
+        }
+        if let Some(committer) = filter.committer.as_ref().filter(|committer| !committer.is_empty()) {
+            revset_expr = revset_expr.intersection(&RevsetExpression::filter(RevsetFilterPredicate::Committer(StringPattern::Substring(committer.clone()))));
+        }
+
+        // jj has no date predicates, so the matching commits are enumerated and become the revset
+        if filter.after.is_some() || filter.before.is_some() {
+            let after = filter.after.map_or(i64::MIN, |time| time.timestamp_millis());
+            let before = filter.before.map_or(i64::MAX, |time| time.timestamp_millis());
+            let revset = self.evaluate_revset_expr(revset_expr)?;
+            let ids = revset.iter().commits(self.operation.repo.store())
+                .filter_map_ok(|commit| (after..=before).contains(&commit.committer().timestamp.timestamp.0).then(|| commit.id().clone()))
+                .collect::<Result<Vec<_>, BackendError>>()?;
+            revset_expr = RevsetExpression::commits(ids);
+        }
+
+        self.evaluate_revset_expr(revset_expr)
+    }
+
     pub fn evaluate_revset_commits<'op>(&'op self, ids: &[messages::CommitId]) -> Result<Box<dyn Revset + 'op>, RevsetError> {
         let expr = RevsetExpression::commits(
         ids.iter().map(|id| CommitId::try_from_hex(id.hex.as_str()).expect("frontend-validated id")).collect()
@@ -370,12 +693,28 @@ impl WorkspaceSession<'_> {
     pub fn format_config(&self) -> Result<messages::RepoConfig> {
         let absolute_path = self.workspace.workspace_root().into();
 
-        let git_remotes = match self.git_repo()? {
-            Some(repo) => repo.remotes()?.iter().flatten().map(|s| s.to_owned()).collect(),
-            None => vec![]
+        let git_remotes = self.format_git_remotes()?;
+
+        let capabilities = messages::RepoCapabilities {
+            has_git_backend: self.operation.git_backend().is_some(),
+            has_remotes: !git_remotes.is_empty(),
+            is_signing_configured: self.settings.signing_backend().is_some(),
+            has_watchman: matches!(self.settings.fsmonitor_kind(), Ok(Some(FsmonitorKind::Watchman))),
+            is_colocated: self.is_colocated,
         };
 
-        let default_query = self.settings.default_revset();
+        let upstream = forge::select_upstream(&git_remotes);
+
+        let default_query = self.default_query();
+
+        // prefer the remote branch, which is what trunk() usually names
+        let trunk = self.find_trunk().and_then(|id| {
+            let names = self.branches_index().get(&id);
+            names.iter()
+                .find(|name| matches!(name, messages::RefName::RemoteBranch { .. }))
+                .or(names.first())
+                .cloned()
+        });
         
         let latest_query = self
             .session
@@ -389,11 +728,89 @@ impl WorkspaceSession<'_> {
             git_remotes,
             default_query,
             latest_query,
+            named_queries: self.format_named_queries(),
+            capabilities,
             status: self.format_status(),
-            theme: self.settings.ui_theme_override()
+            theme: self.settings.ui_theme_override(),
+            upstream,
+            trunk,
+            dates: self.date_display.clone()
         })
     }
 
+    /// asks the frontend for credentials, since there's no terminal for git to prompt on; None if the user cancels
+    pub fn request_credentials(&self, url: &str, username: Option<&str>) -> Option<(String, String)> {
+        let rx = self.input?;
+
+        let fields = match username {
+            Some(_) => vec!["password".to_owned()],
+            None => vec!["username".to_owned(), "password".to_owned()]
+        };
+        self.publish(messages::RepoEvent::InputRequested {
+            request: messages::InputRequest {
+                title: "Git Credentials".to_owned(),
+                detail: match username {
+                    Some(username) => format!("Password for {username} at {url}"),
+                    None => format!("Username and password for {url}")
+                },
+                fields
+            }
+        });
+
+        loop {
+            match rx.recv_timeout(INPUT_TIMEOUT) {
+                Ok(SessionEvent::ProvideCredentials { mut response }) => {
+                    if response.cancel {
+                        return None;
+                    }
+                    let username = match username {
+                        Some(username) => username.to_owned(),
+                        None => response.fields.remove("username")?
+                    };
+                    return Some((username, response.fields.remove("password")?));
+                }
+                // the frontend is modal while it waits, so anything else is stale
+                Ok(evt) => log::warn!("dropped {evt:?} while waiting for credentials"),
+                Err(err) => {
+                    log::warn!("no credentials received: {err}");
+                    return None;
+                }
+            }
+        }
+    }
+
+    pub fn notify_remotes_changed(&self) -> Result<()> {
+        self.publish(messages::RepoEvent::RemotesChanged { remotes: self.format_git_remotes()? });
+        Ok(())
+    }
+
+    pub fn format_git_remotes(&self) -> Result<Vec<messages::GitRemote>> {
+        let Some(repo) = self.git_repo()? else {
+            return Ok(vec![]);
+        };
+
+        let mut remotes = Vec::new();
+        for name in repo.remotes()?.iter().flatten() {
+            let remote = repo.find_remote(name)?;
+            let fetch_url = remote.url().unwrap_or_default().to_owned();
+            let push_url = remote.pushurl().filter(|url| *url != fetch_url).map(|url| url.to_owned());
+            let project = forge::parse_remote_url(&fetch_url);
+            remotes.push(messages::GitRemote {
+                name: name.to_owned(),
+                fetch_url,
+                push_url,
+                last_fetch: self.store.last_fetch(name).map(|time| time.with_timezone(&Local)),
+                project
+            });
+        }
+
+        Ok(remotes)
+    }
+
+    pub fn format_named_queries(&self) -> Vec<messages::NamedQuery> {
+        self.store.named_queries().iter().map(|query| messages::NamedQuery { name: query.name.clone(), revset: query.revset.clone() }).collect()
+    }
+
     pub fn format_status(&self) -> messages::RepoStatus {
         messages::RepoStatus {
             operation_description: self
@@ -404,7 +821,37 @@ impl WorkspaceSession<'_> {
                 .metadata
                 .description
                 .clone(),
+            operation_id: self.operation.repo.op_id().hex(),
             working_copy: self.format_commit_id(&self.operation.wc_id),
+            warnings: self
+                .config_warnings
+                .iter()
+                .chain(self.warnings.borrow().iter())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// records a problem which shouldn't fail the current command, so that the user sees it alongside the result
+    pub fn warn(&self, code: &str, params: HashMap<String, String>, message: String) {
+        log::warn!("{message}");
+        self.warnings.borrow_mut().push(messages::Warning { code: code.to_owned(), params, message });
+    }
+
+    /// for results which are only sent once; statuses are formatted repeatedly, so they copy the warnings instead
+    pub fn take_warnings(&self) -> Vec<messages::Warning> {
+        self.warnings.take()
+    }
+
+    pub fn clear_warnings(&self) {
+        self.warnings.borrow_mut().clear();
+    }
+
+    // jj keeps going when a ref can't be exported, leaving git's copy out of date
+    fn warn_failed_exports(&self, failed: &[git::FailedRefExport]) {
+        if !failed.is_empty() {
+            let names = failed.iter().map(|failed| failed.name.to_string()).collect::<Vec<_>>().join(", ");
+            warning!(self, "export-failed", "{count} refs failed to export to git: {names}", count = failed.len(), names = names);
         }
     }
 
@@ -438,6 +885,12 @@ impl WorkspaceSession<'_> {
     }
 
     pub fn format_header(&self, commit: &Commit, known_immutable: Option<bool>) -> Result<messages::RevHeader> {
+        // the same commits are formatted repeatedly by selections and mutation results, and nothing in a header changes within an operation
+        let key = (commit.id().clone(), known_immutable);
+        if let Some(header) = self.operation.headers.borrow().get(&key) {
+            return Ok(header.clone());
+        }
+
         let index = self.branches_index();
         let branches = index.get(commit.id()).iter().cloned().collect();
 
@@ -445,18 +898,32 @@ impl WorkspaceSession<'_> {
             .map(|x| Result::Ok(x))
             .unwrap_or_else(|| self.check_immutable(vec![commit.id().clone()]))?;
 
-        Ok(messages::RevHeader {
+        let header = messages::RevHeader {
             id: self.format_id(commit),
             description: commit.description().into(),
-            author: commit.author().into(),
+            author: messages::RevAuthor::new(commit.author(), &self.date_display),
             has_conflict: commit.has_conflict()?,
             is_working_copy: *commit.id() == self.operation.wc_id,
             is_immutable,
             branches,
-            parent_ids: commit.parent_ids().iter().map(|commit_id| self.format_commit_id(commit_id)).collect()
-        })
+            parent_ids: commit.parent_ids().iter().map(|commit_id| self.format_commit_id(commit_id)).collect(),
+            signature: commit.is_signed().then_some(messages::RevSignature {
+                status: messages::SignatureStatus::Unverified,
+                key: None,
+                display: None
+            })
+        };
+
+        self.operation.headers.borrow_mut().insert(key, header.clone());
+        Ok(header)
     }
     
+    /// discards everything derived from the current operation, so benchmarks can measure it being rebuilt
+    #[doc(hidden)]
+    pub fn reset_operation_cache(&mut self) {
+        self.operation = SessionOperation::new(self.operation.repo.clone(), self.workspace.workspace_id());
+    }
+
     pub fn format_path<T: AsRef<RepoPath>>(&self, repo_path: T) -> messages::TreePath {
         let base_path = self.workspace.workspace_root();
         let relative_path = relative_path(base_path, &repo_path.as_ref().to_fs_path(base_path));
@@ -492,7 +959,19 @@ impl WorkspaceSession<'_> {
 
     pub fn start_transaction(&mut self) -> Result<Transaction> {
         self.import_and_snapshot(true)?;
-        Ok(self.operation.repo.start_transaction(&self.settings))
+        self.advance_fake_clock()?;
+        let tx = self.operation.repo.start_transaction(&self.settings);
+        self.git_fingerprint = self.read_git_fingerprint()?.map(|fingerprint| (tx.base_repo().op_id().clone(), fingerprint));
+        Ok(tx)
+    }
+
+    /// takes the git fingerprint again, so that gg's own changes to the repo (such as by hooks) aren't
+    /// mistaken for another program's
+    pub fn refresh_git_fingerprint(&mut self) -> Result<()> {
+        if let Some((op_id, _)) = self.git_fingerprint.take() {
+            self.git_fingerprint = self.read_git_fingerprint()?.map(|fingerprint| (op_id, fingerprint));
+        }
+        Ok(())
     }
 
     pub fn finish_transaction(
@@ -504,6 +983,15 @@ impl WorkspaceSession<'_> {
             return Ok(None);
         }
 
+        // exporting over someone else's changes would leave jj and git diverged, so start again from theirs
+        if let Some((op_id, old_fingerprint)) = self.git_fingerprint.take() {
+            if &op_id == tx.base_repo().op_id() && self.read_git_fingerprint()?.as_ref() != Some(&old_fingerprint) {
+                drop(tx);
+                self.import_and_snapshot(true)?;
+                return Err(GitChangedError.into());
+            }
+        }
+
         tx.mut_repo().rebase_descendants(&self.settings)?;
 
         let old_repo = tx.base_repo().clone();
@@ -528,11 +1016,23 @@ impl WorkspaceSession<'_> {
             if let Some(wc_commit) = &maybe_new_wc_commit {
                 git::reset_head(tx.mut_repo(), &git_repo, wc_commit)?;
             }
-            git::export_refs(tx.mut_repo())?;
+            let failed_exports = git::export_refs(tx.mut_repo())?;
+            self.warn_failed_exports(&failed_exports);
+        }
+
+        let changed_branches = changed_branches(old_repo.view(), tx.repo().view());
+
+        if let Some(action) = &self.action {
+            tx.set_tag(ACTION_TAG.to_owned(), action.clone());
         }
 
         self.operation = SessionOperation::new(tx.commit(description), self.workspace.workspace_id());
 
+        self.publish(messages::RepoEvent::OperationApplied { status: self.format_status() });
+        if !changed_branches.is_empty() {
+            self.publish(messages::RepoEvent::BranchesChanged { names: changed_branches });
+        }
+
         // XXX do this only if loaded at head, which is currently always true, but won't be once we have undo-redo
         if let Some(new_commit) = &maybe_new_wc_commit {            
             self.update_working_copy(maybe_old_wc_commit.as_ref(), new_commit)?;
@@ -547,6 +1047,10 @@ impl WorkspaceSession<'_> {
             return Ok(false)
         }
 
+        // importing absorbs any outside changes, so a fingerprint left by an abandoned transaction is obsolete
+        self.git_fingerprint = None;
+        self.advance_fake_clock()?;
+
         if self.is_colocated {
             self.import_git_head()?;
         }
@@ -557,9 +1061,56 @@ impl WorkspaceSession<'_> {
             self.import_git_refs()?;
         }
 
+        if updated_working_copy {
+            self.publish(messages::RepoEvent::SnapshotTaken { status: self.format_status() });
+        }
+
         Ok(updated_working_copy)
     }
 
+    // a real clock would have moved on since the last transaction, and commits written in the same second would otherwise share ids
+    fn advance_fake_clock(&mut self) -> Result<()> {
+        if let Some(fake_environment) = &mut self.session.fake_environment {
+            self.settings = fake_environment.tick(&self.settings)?;
+        }
+        Ok(())
+    }
+
+    /// subscribers may come and go, so failing to deliver an event is not an error
+    pub fn publish(&self, event: messages::RepoEvent) {
+        if let Some(events) = &self.session.events {
+            if events.send(event).is_err() {
+                log::debug!("no subscribers for repo event");
+            }
+        }
+    }
+
+    /// runs an external tool in the workspace root, reporting its exit as a repo event
+    pub fn spawn_tool(&self, name: &str, program: &Path, args: Vec<String>, env: Vec<(String, PathBuf)>, temp_dir: Option<PathBuf>) -> Result<u32> {
+        self.session.tools.spawn(ToolInvocation {
+            name: name.to_owned(),
+            program: program.to_owned(),
+            args,
+            env,
+            cwd: self.workspace.workspace_root().clone(),
+            timeout: self.settings.tool_timeout(),
+            temp_dir
+        }, self.session.events.clone())
+    }
+
+    /// runs an external tool in the workspace root, waiting for its output
+    pub fn run_tool(&self, name: &str, program: &Path, args: Vec<String>, input: Vec<u8>, timeout: Duration) -> Result<String> {
+        self.session.tools.run_with_input(ToolInvocation {
+            name: name.to_owned(),
+            program: program.to_owned(),
+            args,
+            env: Vec::new(),
+            cwd: self.workspace.workspace_root().clone(),
+            timeout: Some(timeout),
+            temp_dir: None
+        }, input)
+    }
+
     fn snapshot_working_copy(&mut self) -> Result<bool> {
         let workspace_id = self.workspace.workspace_id().to_owned();
         let get_wc_commit = |repo: &ReadonlyRepo| -> Result<Option<_>, _> {
@@ -578,6 +1129,9 @@ impl WorkspaceSession<'_> {
         // Compare working-copy tree and operation with repo's, and reload as needed.
         let mut locked_ws = self.workspace.start_working_copy_mutation()?;
         let old_op_id = locked_ws.locked_wc().old_operation_id().clone();
+        // the workspace is borrowed until the lock is released, so warnings have to wait
+        let mut stale_update = None;
+        let mut failed_exports = Vec::new();
         let (repo, wc_commit) = match check_stale_working_copy(
             locked_ws.locked_wc(),
             &wc_commit,
@@ -585,6 +1139,7 @@ impl WorkspaceSession<'_> {
         )? {
             WorkingCopyFreshness::Fresh => (repo, wc_commit),
             WorkingCopyFreshness::Updated(wc_operation) => {
+                stale_update = Some(short_operation_hash(wc_operation.id()));
                 let repo = repo.reload_at(&wc_operation)?;
                 let wc_commit = if let Some(wc_commit) = get_wc_commit(&repo)? {
                     wc_commit
@@ -608,15 +1163,21 @@ impl WorkspaceSession<'_> {
             }
         };
         
+        let reporter = self.session.events.clone().map(SnapshotReporter::new);
+        let progress = reporter.as_ref().map(|reporter| move |path: &RepoPath| reporter.scanned(path));
         let new_tree_id = locked_ws.locked_wc().snapshot(SnapshotOptions {
             base_ignores,
             fsmonitor_kind: self.settings.fsmonitor_kind()?,
-            progress: None,
+            progress: progress.as_ref().map(|progress| progress as &(dyn Fn(&RepoPath) + Sync)),
             max_new_file_size: self.settings.max_new_file_size()?,
         })?;
 
         let did_anything = new_tree_id != *wc_commit.tree_id();
 
+        if let Some(reporter) = &reporter {
+            reporter.finish(&wc_commit.tree()?, &repo.store().get_root_tree(&new_tree_id)?);
+        }
+
         if did_anything {
             let mut tx =
                 repo.start_transaction(&self.settings);
@@ -630,14 +1191,19 @@ impl WorkspaceSession<'_> {
             mut_repo.rebase_descendants(&self.settings)?;
 
             if self.is_colocated {
-                git::export_refs(mut_repo)?;
+                failed_exports = git::export_refs(mut_repo)?;
             }
     
-            self.operation = SessionOperation::new(tx.commit("snapshot working copy"), &workspace_id);
+            self.operation = SessionOperation::new(tx.commit(SNAPSHOT_DESCRIPTION), &workspace_id);
         }
         
         locked_ws.finish(self.operation.repo.op_id().clone())?;
 
+        if let Some(operation) = stale_update {
+            warning!(self, "working-copy-updated", "The working copy was stale and has been updated to operation {operation}", operation = operation);
+        }
+        self.warn_failed_exports(&failed_exports);
+
         Ok(did_anything)
     }
 
@@ -695,17 +1261,40 @@ impl WorkspaceSession<'_> {
         Ok(())
     }
 
+    fn read_git_fingerprint(&self) -> Result<Option<GitFingerprint>> {
+        if !self.is_colocated {
+            return Ok(None);
+        }
+
+        let Some(repo) = self.git_repo()? else {
+            return Ok(None);
+        };
+
+        // an unborn HEAD can't be read, which is still a state worth comparing
+        let head = repo.head().ok().map(|head| (head.name().map(|name| name.to_owned()), head.target()));
+        let index_modified = fs::metadata(repo.path().join("index")).and_then(|metadata| metadata.modified()).ok();
+
+        Ok(Some(GitFingerprint { head, index_modified }))
+    }
+
     fn import_git_refs(&mut self) -> Result<()> {
         let git_settings = self.settings.git_settings();
         let mut tx = self.operation.repo.start_transaction(&self.settings);
         // Automated import shouldn't fail because of reserved remote name.
-        git::import_some_refs(tx.mut_repo(), &git_settings, |ref_name| {
+        let stats = git::import_some_refs(tx.mut_repo(), &git_settings, |ref_name| {
             !git::is_reserved_git_remote_ref(ref_name)
         })?;
         if !tx.mut_repo().has_changes() {
             return Ok(());
         }
 
+        if !stats.abandoned_commits.is_empty() {
+            warning!(self, "import-abandoned",
+                "Abandoned {count} commits which are no longer reachable from git refs",
+                count = stats.abandoned_commits.len()
+            );
+        }
+
         tx.mut_repo().rebase_descendants(&self.settings)?;
             
         self.finish_transaction(tx, "import git refs")?;
@@ -718,10 +1307,12 @@ impl WorkspaceSession<'_> {
     /* complicate the interface of trait Mutation                                                    */
     /*************************************************************************************************/
 
+    /// rebased commits which were abandoned for being empty are mapped to their parent
     pub fn disinherit_children(
         &self,
         tx: &mut Transaction,
         target: &Commit,
+        options: &RebaseOptions,
     ) -> Result<HashMap<CommitId, CommitId>> {
         // find all children of target
         let children_expr = RevsetExpression::commit(target.id().clone()).children();
@@ -759,19 +1350,20 @@ impl WorkspaceSession<'_> {
                 .commits(tx.base_repo().store())
                 .try_collect()?;
 
-            rebased_commit_ids.insert(
-                child_commit.id().clone(),
-                rewrite::rebase_commit(
-                    &self.settings,
-                    tx.mut_repo(),
-                    child_commit,
-                    &new_child_parents,
-                )?
-                .id()
-                .clone(),
-            );
+            let rebased_commit = rewrite::rebase_commit_with_options(
+                &self.settings,
+                tx.mut_repo(),
+                child_commit,
+                &new_child_parents,
+                options,
+            )?;
+            let rebased_id = match rebased_commit {
+                RebasedCommit::Rewritten(commit) => commit.id().clone(),
+                RebasedCommit::Abandoned { parent } => parent.id().clone(),
+            };
+            rebased_commit_ids.insert(child_commit.id().clone(), rebased_id);
         }
-        rebased_commit_ids.extend(tx.mut_repo().rebase_descendants_return_map(&self.settings)?);
+        rebased_commit_ids.extend(tx.mut_repo().rebase_descendants_with_options_return_map(&self.settings, options.clone())?);
 
         Ok(rebased_commit_ids)
     }
@@ -790,7 +1382,8 @@ impl SessionOperation {
             wc_id,
             branches_index: OnceCell::default(),
             prefix_context: OnceCell::default(),
-            immutable_revisions: OnceCell::default()
+            immutable_revisions: OnceCell::default(),
+            headers: RefCell::default()
         }
     }
 
@@ -843,7 +1436,11 @@ fn find_workspace_dir(cwd: &Path) -> &Path {
         .unwrap_or(cwd)
 }
 
-fn build_aliases_map(layered_configs: &LayeredConfigs) -> Result<RevsetAliasesMap> {
+// an alias which can't be parsed, perhaps written for another version of jj, is left out with a warning
+fn build_aliases_map(
+    layered_configs: &LayeredConfigs,
+    problems: &mut Vec<String>,
+) -> Result<RevsetAliasesMap> {
     const TABLE_KEY: &str = "revset-aliases";
     let mut aliases_map = RevsetAliasesMap::new();
     // Load from all config layers in order. 'f(x)' in default layer should be
@@ -855,10 +1452,13 @@ fn build_aliases_map(layered_configs: &LayeredConfigs) -> Result<RevsetAliasesMa
             continue;
         };
         for (decl, value) in table.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
-            value
+            let result = value
                 .into_string()
                 .map_err(|e| anyhow!(e))
-                .and_then(|v| aliases_map.insert(&decl, v).map_err(|e| anyhow!(e)))?;
+                .and_then(|v| aliases_map.insert(&decl, v).map_err(|e| anyhow!(e)));
+            if let Err(err) = result {
+                problems.push(format!("Ignoring revset alias {decl}: {err}"));
+            }
         }
     }
     Ok(aliases_map)
@@ -944,7 +1544,7 @@ impl BranchIndex {
         }
     }
 
-    fn get(&self, id: &CommitId) -> &[messages::RefName] {
+    pub fn get(&self, id: &CommitId) -> &[messages::RefName] {
         if let Some(names) = self.index.get(id) {
             names
         } else {
@@ -953,6 +1553,72 @@ impl BranchIndex {
     }
 }
 
+/// throttled progress events for snapshots slow enough that the UI would otherwise seem hung;
+/// jj scans files in parallel, so this is called from several threads
+struct SnapshotReporter {
+    started: Instant,
+    scanned: AtomicUsize,
+    last_sent: Mutex<Option<Instant>>,
+    events: Mutex<Sender<messages::RepoEvent>>,
+}
+
+impl SnapshotReporter {
+    fn new(events: Sender<messages::RepoEvent>) -> SnapshotReporter {
+        SnapshotReporter {
+            started: Instant::now(),
+            scanned: AtomicUsize::new(0),
+            last_sent: Mutex::new(None),
+            events: Mutex::new(events)
+        }
+    }
+
+    fn scanned(&self, path: &RepoPath) {
+        let files_scanned = self.scanned.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.started.elapsed() < SNAPSHOT_PROGRESS_DELAY {
+            return;
+        }
+
+        // another thread is already reporting
+        let Ok(mut last_sent) = self.last_sent.try_lock() else {
+            return;
+        };
+        if last_sent.is_some_and(|time| time.elapsed() < SNAPSHOT_PROGRESS_INTERVAL) {
+            return;
+        }
+        *last_sent = Some(Instant::now());
+
+        self.send(messages::RepoEvent::SnapshotProgress {
+            files_scanned,
+            files_added: None,
+            current_dir: path.parent().map(|dir| dir.as_internal_file_string().to_owned())
+        });
+    }
+
+    /// sends a final event with the number of new files, if any progress was reported
+    fn finish(&self, old_tree: &MergedTree, new_tree: &MergedTree) {
+        if self.last_sent.lock().expect("progress state poisoned").is_none() {
+            return;
+        }
+
+        let files_added = old_tree
+            .diff(new_tree, &EverythingMatcher)
+            .filter(|(_, diff)| matches!(diff, Ok((before, after)) if before.is_absent() && after.is_present()))
+            .count();
+
+        self.send(messages::RepoEvent::SnapshotProgress {
+            files_scanned: self.scanned.load(Ordering::Relaxed),
+            files_added: Some(files_added),
+            current_dir: None
+        });
+    }
+
+    fn send(&self, event: messages::RepoEvent) {
+        if self.events.lock().expect("progress sender poisoned").send(event).is_err() {
+            log::debug!("no subscribers for snapshot progress");
+        }
+    }
+}
+
 fn build_branches_index(repo: &ReadonlyRepo) -> BranchIndex {
     let mut index = BranchIndex::default();
     for (branch_name, branch_target) in repo.view().branches() {
@@ -980,3 +1646,12 @@ fn build_branches_index(repo: &ReadonlyRepo) -> BranchIndex {
     }
     index
 }
+
+fn changed_branches(old_view: &View, new_view: &View) -> Vec<String> {
+    let names: BTreeSet<&str> = old_view.local_branches().chain(new_view.local_branches()).map(|(name, _)| name).collect();
+    names
+        .into_iter()
+        .filter(|name| old_view.get_local_branch(name) != new_view.get_local_branch(name))
+        .map(|name| name.to_owned())
+        .collect()
+}
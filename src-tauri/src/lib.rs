@@ -0,0 +1,1790 @@
+mod automation;
+mod config;
+mod crash;
+mod credentials;
+mod forge;
+pub mod gui_util;
+mod handler;
+mod menu;
+mod messages;
+mod processes;
+mod replay;
+mod store;
+#[cfg(all(test, not(feature = "ts-rs")))]
+mod test_repo;
+#[cfg(all(test, not(feature = "ts-rs")))]
+mod tests;
+pub mod worker;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+
+use anyhow::{anyhow, Context, Result};
+use log::LevelFilter;
+use tauri::menu::Menu;
+use tauri::{ipc::InvokeError, Manager};
+use tauri::{State, WebviewWindow, Window, WindowEvent, Wry};
+use tauri_plugin_window_state::StateFlags;
+
+use crate::config::GGSettings;
+use gui_util::WorkerSession;
+use messages::{
+    AbandonRevisions, AddGitRemote, AddWorkspace, BackoutRevision, BreakStaleLock,
+    CheckoutRevision, CopyChanges, CopyWorkspaceChanges, CreateCheckpoint, CreateRevision,
+    DeleteNamedQuery, DescribeRevision, DescribeRevisions, DuplicateRevisions,
+    EditRevisionMetadata, FetchRemote, ForgetWorkspace, ImportPatches, InsertRevision, MoveBranch,
+    MoveChanges, MoveRevision, MoveRevisions, MoveSource, MutationResult, PushRemote,
+    RedoOperation, RemoveGitRemote, RenameGitRemote, ResolveConflict, RestoreCheckpoint,
+    RestoreFileFromOperation, RestoreOperation, RevId, SaveNamedQuery, SetAnnotation,
+    SetDefaultQuery, SetReviewState, SetTrunk, SignRevision, SplitRevision, SyncReviewStates,
+    TrackBranch, TreePath, UndoOperation, UntrackBranch, WriteResolvedFile,
+};
+use worker::{queries::QueryCancellation, Mutation, Session, SessionEvent};
+
+#[derive(Default)]
+struct AppState(Mutex<HashMap<String, WindowState>>);
+
+struct WindowState {
+    _worker: JoinHandle<()>,
+    channel: Sender<SessionEvent>,
+    _reader: JoinHandle<()>,
+    reader_channel: Sender<SessionEvent>,
+    queries: QueryCancellation,
+    revision_menu: Menu<Wry>,
+    tree_menu: Menu<Wry>,
+    ref_menu: Menu<Wry>,
+}
+
+impl AppState {
+    fn get_sender(&self, window_label: &str) -> Sender<SessionEvent> {
+        self.0
+            .lock()
+            .expect("state mutex poisoned")
+            .get(window_label)
+            .expect("session not found")
+            .channel
+            .clone()
+    }
+
+    /// queries go to a separate worker, so that they can be answered during long mutations
+    fn get_reader(&self, window_label: &str) -> Sender<SessionEvent> {
+        self.0
+            .lock()
+            .expect("state mutex poisoned")
+            .get(window_label)
+            .expect("session not found")
+            .reader_channel
+            .clone()
+    }
+
+    /// stops the reader's current query for a pane, which would otherwise hold up anything sent after it
+    fn cancel_query(&self, window_label: &str, pane: &str) {
+        self.0
+            .lock()
+            .expect("state mutex poisoned")
+            .get(window_label)
+            .expect("session not found")
+            .queries
+            .cancel(pane);
+    }
+}
+
+/// the app's entry point, which is in the library so that benchmarks can use its modules
+pub fn run() -> Result<()> {
+    let debug = std::env::args()
+        .find(|arg| arg.as_str() == "--debug")
+        .is_some();
+
+    crash::install_panic_hook();
+
+    // developers reproduce a recorded session against a copy of the user's repo, without a window
+    let mut replay_args = std::env::args().skip_while(|arg| arg != "--replay").skip(1);
+    if let Some(recording) = replay_args.next() {
+        let workspace = match replay_args.next() {
+            Some(workspace) => PathBuf::from(workspace),
+            None => std::env::current_dir()?,
+        };
+        return replay::replay(
+            Path::new(&recording),
+            &workspace,
+            &mut std::io::stdout().lock(),
+        );
+    }
+
+    let recorder = if std::env::args().any(|arg| arg == "--record") {
+        Some(replay::Recorder::create().context("start session recording")?)
+    } else {
+        None
+    };
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(
+            tauri_plugin_window_state::Builder::default()
+                .with_state_flags(
+                    StateFlags::SIZE
+                        | StateFlags::POSITION
+                        | StateFlags::SIZE
+                        | StateFlags::FULLSCREEN,
+                )
+                .build(),
+        )
+        .plugin(
+            tauri_plugin_log::Builder::default()
+                .level(LevelFilter::Warn)
+                .level_for(
+                    "gg_lib",
+                    if debug {
+                        LevelFilter::Debug
+                    } else {
+                        LevelFilter::Warn
+                    },
+                )
+                .build(),
+        )
+        .invoke_handler(tauri::generate_handler![
+            notify_window_ready,
+            forward_accelerator,
+            forward_context_menu,
+            has_forge_token,
+            check_forge_token,
+            set_forge_token,
+            clear_forge_token,
+            set_background_policy,
+            provide_credentials,
+            query_log,
+            query_log_next_page,
+            refresh_query,
+            search_log,
+            cancel_query,
+            query_revision,
+            query_children,
+            query_workspaces,
+            query_workspace,
+            switch_workspace,
+            query_git_remotes,
+            query_context,
+            query_template_context,
+            query_is_ancestor,
+            resolve_id_prefix,
+            resolve_drag_payload,
+            lookup_text,
+            get_commit_url,
+            query_slow_queries,
+            query_large_paths,
+            query_revision_tree,
+            query_file_contents,
+            query_blame,
+            query_file_history,
+            query_owners,
+            query_revision_diff,
+            query_file_diff,
+            query_conflict,
+            query_conflicts,
+            query_merge_tools,
+            launch_diff_tool,
+            reveal_in_editor,
+            reveal_crash_report,
+            query_operations,
+            query_checkpoints,
+            query_annotations,
+            query_review_states,
+            query_named_queries,
+            list_available_commands,
+            query_change_history,
+            query_suggested_description,
+            export_operation_history,
+            checkout_revision,
+            create_revision,
+            insert_revision,
+            describe_revision,
+            describe_revisions,
+            edit_revision_metadata,
+            sign_revision,
+            duplicate_revisions,
+            break_stale_lock,
+            add_workspace,
+            forget_workspace,
+            abandon_revisions,
+            move_revision,
+            move_source,
+            move_revisions,
+            move_changes,
+            split_revision,
+            backout_revision,
+            import_patches,
+            copy_changes,
+            copy_workspace_changes,
+            write_resolved_file,
+            resolve_conflict,
+            track_branch,
+            untrack_branch,
+            move_branch,
+            push_remote,
+            fetch_remote,
+            add_git_remote,
+            remove_git_remote,
+            rename_git_remote,
+            undo_operation,
+            redo_operation,
+            restore_operation,
+            restore_file_from_operation,
+            create_checkpoint,
+            restore_checkpoint,
+            set_default_query,
+            save_named_query,
+            delete_named_query,
+            set_annotation,
+            set_review_state,
+            sync_review_states,
+            set_trunk
+        ])
+        .menu(menu::build_main)
+        .setup(move |app| {
+            if let Some(recorder) = &recorder {
+                log::info!("recording session to {}", recorder.path().display());
+            }
+
+            let window = app
+                .get_webview_window("main")
+                .ok_or(anyhow!("preconfigured window not found"))?;
+            let (sender, receiver) = channel();
+            let (reader_sender, reader_receiver) = channel();
+            let (event_sender, event_receiver) = channel();
+
+            let queue = sender.clone();
+            let writer_events = event_sender.clone();
+            let writer_recorder = recorder.clone();
+            let window_worker = spawn_worker(&window, receiver, move || WorkerSession {
+                events: Some(writer_events.clone()),
+                queue: Some(queue.clone()),
+                recorder: writer_recorder.clone(),
+                ..Default::default()
+            });
+            let queries = QueryCancellation::default();
+            let reader_queries = queries.clone();
+            let reader_events = event_sender.clone();
+            let reader_recorder = recorder.clone();
+            let window_reader = spawn_worker(&window, reader_receiver, move || WorkerSession {
+                read_only: true,
+                events: Some(reader_events.clone()),
+                cancellation: reader_queries.clone(),
+                recorder: reader_recorder.clone(),
+                ..Default::default()
+            });
+
+            // repo changes go to the whole window, not just whichever component caused them
+            let handle = window.clone();
+            thread::spawn(move || {
+                for event in event_receiver {
+                    if let Err(err) = handle.emit("gg://repo/event", event) {
+                        log::error!("emit repo event: {err:#}");
+                    }
+                }
+            });
+
+            window.on_menu_event(|w, e| handler::fatal!(menu::handle_event(w, e)));
+
+            let handle = window.clone();
+            window.on_window_event(move |event| handle_window_event(&handle, event));
+
+            let handle = window.clone();
+            window.listen("gg://revision/select", move |event| {
+                let payload: Result<Option<messages::RevHeader>, serde_json::Error> =
+                    serde_json::from_str(event.payload());
+                if let Some(menu) = handle.menu() {
+                    if let Ok(selection) = payload {
+                        handler::fatal!(menu::handle_selection(menu, selection));
+                    }
+                }
+            });
+
+            let (revision_menu, tree_menu, ref_menu) = menu::build_context(app.handle())?;
+
+            let app_state = app.state::<AppState>();
+            app_state.0.lock().unwrap().insert(
+                window.label().to_owned(),
+                WindowState {
+                    _worker: window_worker,
+                    channel: sender,
+                    _reader: window_reader,
+                    reader_channel: reader_sender,
+                    queries,
+                    revision_menu,
+                    tree_menu,
+                    ref_menu,
+                },
+            );
+
+            // opt-in, because it lets other programs act with the user's credentials
+            if config::read_user_settings().is_ok_and(|settings| settings.automation_enabled()) {
+                if let Err(err) = automation::start(app.handle().clone()) {
+                    log::error!("start automation endpoint: {err:#}");
+                }
+            }
+
+            Ok(())
+        })
+        .manage(AppState::default())
+        .run(tauri::generate_context!())?;
+
+    Ok(())
+}
+
+/// runs a worker thread, restarting its session if it fails
+fn spawn_worker(
+    window: &WebviewWindow,
+    receiver: Receiver<SessionEvent>,
+    create_session: impl Fn() -> WorkerSession + Send + 'static,
+) -> JoinHandle<()> {
+    let handle = window.clone();
+    thread::spawn(move || {
+        log::info!("start worker");
+
+        while let Err(err) = create_session().handle_events(&receiver).context("worker") {
+            log::info!("restart worker: {err:#}");
+
+            // it's ok if the worker has to restart, as long as we can notify the frontend of it
+            handler::fatal!(handle.emit(
+                "gg://repo/config",
+                messages::RepoConfig::WorkerError {
+                    message: format!("{err:#}"),
+                },
+            ));
+        }
+    })
+}
+
+#[tauri::command(async)]
+fn notify_window_ready(window: Window) {
+    log::debug!("window opened; loading cwd");
+    handler::fatal!(window.show());
+    handler::nonfatal!(try_open_repository(&window, None));
+}
+
+#[tauri::command]
+fn set_background_policy(
+    window: Window,
+    app_state: State<AppState>,
+    policy: messages::BackgroundPolicy,
+) -> Result<(), InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_sender(window.label());
+    session_tx
+        .send(SessionEvent::SetBackgroundPolicy { policy })
+        .map_err(InvokeError::from_error)
+}
+
+/// the writer is blocked in a fetch or push, reading its own queue, so this doesn't wait for a reply
+#[tauri::command]
+fn provide_credentials(
+    window: Window,
+    app_state: State<AppState>,
+    response: messages::InputResponse,
+) -> Result<(), InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_sender(window.label());
+    session_tx
+        .send(SessionEvent::ProvideCredentials { response })
+        .map_err(InvokeError::from_error)
+}
+
+#[tauri::command]
+fn forward_accelerator(window: Window, key: char) {
+    if key == 'o' {
+        menu::repo_open(&window);
+    }
+}
+
+#[tauri::command]
+fn forward_context_menu(window: Window, context: messages::Operand) -> Result<(), InvokeError> {
+    menu::handle_context(window, context).map_err(InvokeError::from_anyhow)?;
+    Ok(())
+}
+
+#[tauri::command(async)]
+fn has_forge_token(forge: messages::Forge, host: String) -> Result<bool, InvokeError> {
+    credentials::get_token(forge, &host)
+        .map(|token| token.is_some())
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command]
+async fn check_forge_token(forge: messages::Forge, host: String) -> Result<String, InvokeError> {
+    forge::query_token_user(forge, &host)
+        .await
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn set_forge_token(mutation: messages::SetForgeToken) -> Result<(), InvokeError> {
+    credentials::set_token(mutation.forge, &mutation.host, &mutation.token)
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn clear_forge_token(mutation: messages::ClearForgeToken) -> Result<(), InvokeError> {
+    credentials::clear_token(mutation.forge, &mutation.host).map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_log(
+    window: Window,
+    app_state: State<AppState>,
+    revset: String,
+    filter: Option<messages::LogFilter>,
+    pane: Option<String>,
+    page_size: Option<usize>,
+    count: Option<messages::LogCount>,
+) -> Result<messages::LogPage, InvokeError> {
+    let pane = pane.unwrap_or_else(|| worker::DEFAULT_PANE.to_owned());
+
+    // a new query replaces the pane's previous one, so there's no point finishing it
+    app_state.cancel_query(window.label(), &pane);
+
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryLog {
+            tx: call_tx,
+            pane,
+            query: revset,
+            filter: filter.unwrap_or_default(),
+            page_size,
+            count,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_log_next_page(
+    window: Window,
+    app_state: State<AppState>,
+    pane: Option<String>,
+) -> Result<messages::LogPage, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryLogNextPage {
+            tx: call_tx,
+            pane: pane.unwrap_or_else(|| worker::DEFAULT_PANE.to_owned()),
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn cancel_query(
+    window: Window,
+    app_state: State<AppState>,
+    pane: Option<String>,
+) -> Result<(), InvokeError> {
+    let pane = pane.unwrap_or_else(|| worker::DEFAULT_PANE.to_owned());
+    app_state.cancel_query(window.label(), &pane);
+
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    session_tx
+        .send(SessionEvent::CancelQuery { pane })
+        .map_err(InvokeError::from_error)
+}
+
+#[tauri::command(async)]
+fn search_log(
+    window: Window,
+    app_state: State<AppState>,
+    text: String,
+    is_regex: Option<bool>,
+    pane: Option<String>,
+) -> Result<messages::LogSearch, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::SearchLog {
+            tx: call_tx,
+            pane: pane.unwrap_or_else(|| worker::DEFAULT_PANE.to_owned()),
+            text,
+            is_regex: is_regex.unwrap_or(false),
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn refresh_query(
+    window: Window,
+    app_state: State<AppState>,
+    pane: Option<String>,
+) -> Result<messages::LogDelta, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::RefreshQuery {
+            tx: call_tx,
+            pane: pane.unwrap_or_else(|| worker::DEFAULT_PANE.to_owned()),
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_revision(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+) -> Result<messages::RevResult, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryRevision { tx: call_tx, id })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_large_paths(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    limit: usize,
+) -> Result<messages::LargePaths, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryLargePaths {
+            tx: call_tx,
+            id,
+            limit,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_revision_tree(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    dir: Option<TreePath>,
+) -> Result<Vec<messages::TreeEntry>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryRevisionTree {
+            tx: call_tx,
+            id,
+            dir,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_file_contents(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    path: TreePath,
+) -> Result<messages::FileContents, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryFileContents {
+            tx: call_tx,
+            id,
+            path,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_blame(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    path: TreePath,
+) -> Result<messages::FileBlame, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryBlame {
+            tx: call_tx,
+            id,
+            path,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_file_history(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    path: TreePath,
+    after: Option<messages::CommitId>,
+    page_size: Option<usize>,
+) -> Result<messages::LogPage, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryFileHistory {
+            tx: call_tx,
+            id,
+            path,
+            after,
+            page_size,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_children(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+) -> Result<Vec<messages::RevHeader>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryChildren { tx: call_tx, id })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_workspaces(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<Vec<messages::WorkspaceInfo>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryWorkspaces { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_workspace(
+    window: Window,
+    app_state: State<AppState>,
+    name: String,
+) -> Result<messages::RevResult, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryWorkspace { tx: call_tx, name })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_git_remotes(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<Vec<messages::GitRemote>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryGitRemotes { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_context(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+) -> Result<messages::RevContext, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryContext { tx: call_tx, id })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_template_context(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+) -> Result<messages::TemplateContext, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryTemplateContext { tx: call_tx, id })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_is_ancestor(
+    window: Window,
+    app_state: State<AppState>,
+    ancestor_id: messages::CommitId,
+    descendant_id: messages::CommitId,
+) -> Result<bool, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryIsAncestor {
+            tx: call_tx,
+            ancestor_id,
+            descendant_id,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn resolve_id_prefix(
+    window: Window,
+    app_state: State<AppState>,
+    prefix: String,
+) -> Result<messages::PrefixResolution, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::ResolveIdPrefix {
+            tx: call_tx,
+            prefix,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn resolve_drag_payload(
+    window: Window,
+    app_state: State<AppState>,
+    pane: Option<String>,
+    op_id: String,
+    payloads: Vec<messages::DragPayload>,
+) -> Result<messages::DragResolution, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::ResolveDragPayload {
+            tx: call_tx,
+            pane: pane.unwrap_or_else(|| worker::DEFAULT_PANE.to_owned()),
+            op_id,
+            payloads,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn lookup_text(
+    window: Window,
+    app_state: State<AppState>,
+    text: String,
+) -> Result<messages::TextLookup, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::LookupText { tx: call_tx, text })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn get_commit_url(
+    window: Window,
+    app_state: State<AppState>,
+    target: messages::WebTarget,
+) -> Result<Option<String>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::GetCommitUrl {
+            tx: call_tx,
+            target,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_slow_queries(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<messages::SlowQueries, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QuerySlowQueries { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_owners(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+) -> Result<messages::RevOwners, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryOwners { tx: call_tx, id })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_revision_diff(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    style: messages::DiffStyle,
+    after: Option<messages::DiffCursor>,
+    page_size: Option<usize>,
+) -> Result<messages::DiffPage, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryRevisionDiff {
+            tx: call_tx,
+            id,
+            style,
+            after,
+            page_size,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_file_diff(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    path: TreePath,
+    style: messages::DiffStyle,
+    after: Option<messages::DiffCursor>,
+    page_size: Option<usize>,
+) -> Result<messages::DiffPage, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryFileDiff {
+            tx: call_tx,
+            id,
+            path,
+            style,
+            after,
+            page_size,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_conflict(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    path: TreePath,
+) -> Result<messages::ThreeWayMerge, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryConflict {
+            tx: call_tx,
+            id,
+            path,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_conflicts(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+) -> Result<Vec<messages::FileConflict>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryConflicts { tx: call_tx, id })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_merge_tools(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<Vec<messages::MergeTool>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryMergeTools { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn launch_diff_tool(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    path: TreePath,
+    tool: String,
+) -> Result<u32, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_sender(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::LaunchDiffTool {
+            tx: call_tx,
+            id,
+            path,
+            tool,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn reveal_in_editor(
+    window: Window,
+    app_state: State<AppState>,
+    path: TreePath,
+    line: Option<usize>,
+) -> Result<u32, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_sender(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::RevealInEditor {
+            tx: call_tx,
+            path,
+            line,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn reveal_crash_report(
+    window: Window,
+    app_state: State<AppState>,
+    path: String,
+) -> Result<u32, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_sender(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::RevealCrashReport {
+            tx: call_tx,
+            path: PathBuf::from(path),
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_operations(
+    window: Window,
+    app_state: State<AppState>,
+    after: Option<String>,
+    page_size: Option<usize>,
+) -> Result<messages::OperationPage, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryOperations {
+            tx: call_tx,
+            after,
+            page_size,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_checkpoints(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<Vec<messages::Checkpoint>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryCheckpoints { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_annotations(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<Vec<messages::ChangeAnnotation>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryAnnotations { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_review_states(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<Vec<messages::ChangeReview>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryReviewStates { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_named_queries(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<Vec<messages::NamedQuery>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryNamedQueries { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn list_available_commands(
+    window: Window,
+    app_state: State<AppState>,
+    ids: Vec<RevId>,
+) -> Result<Vec<messages::AvailableCommand>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::ListAvailableCommands { tx: call_tx, ids })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_change_history(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    limit: Option<usize>,
+) -> Result<messages::ChangeHistory, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryChangeHistory {
+            tx: call_tx,
+            id,
+            limit,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_suggested_description(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+) -> Result<Option<String>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QuerySuggestedDescription { tx: call_tx, id })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn export_operation_history(
+    window: Window,
+    app_state: State<AppState>,
+    options: messages::ExportOperationHistory,
+) -> Result<usize, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_reader(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::ExportOperationHistory {
+            tx: call_tx,
+            options,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn checkout_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CheckoutRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn create_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CreateRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn insert_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: InsertRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn describe_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: DescribeRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn describe_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: DescribeRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn edit_revision_metadata(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: EditRevisionMetadata,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn add_workspace(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: AddWorkspace,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn forget_workspace(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: ForgetWorkspace,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn break_stale_lock(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: BreakStaleLock,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn sign_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SignRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn duplicate_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: DuplicateRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn abandon_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: AbandonRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn move_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: MoveRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn move_source(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: MoveSource,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn move_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: MoveRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn move_changes(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: MoveChanges,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn split_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SplitRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn backout_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: BackoutRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn import_patches(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: ImportPatches,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn copy_changes(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CopyChanges,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn copy_workspace_changes(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CopyWorkspaceChanges,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn write_resolved_file(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: WriteResolvedFile,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn resolve_conflict(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: ResolveConflict,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn track_branch(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: TrackBranch,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn untrack_branch(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: UntrackBranch,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn move_branch(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: MoveBranch,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn push_remote(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: PushRemote,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn fetch_remote(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: FetchRemote,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn add_git_remote(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: AddGitRemote,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn remove_git_remote(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RemoveGitRemote,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn rename_git_remote(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RenameGitRemote,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn undo_operation(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: UndoOperation,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn redo_operation(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, RedoOperation)
+}
+
+#[tauri::command(async)]
+fn restore_operation(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RestoreOperation,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn restore_file_from_operation(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RestoreFileFromOperation,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn create_checkpoint(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CreateCheckpoint,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn restore_checkpoint(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RestoreCheckpoint,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn set_annotation(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SetAnnotation,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn set_review_state(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SetReviewState,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn sync_review_states(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, SyncReviewStates)
+}
+
+#[tauri::command(async)]
+fn set_default_query(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SetDefaultQuery,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn save_named_query(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SaveNamedQuery,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn delete_named_query(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: DeleteNamedQuery,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn set_trunk(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SetTrunk,
+) -> Result<MutationResult, InvokeError> {
+    let result = try_mutate(window.clone(), app_state, mutation)?;
+
+    // revset aliases are loaded with the workspace, so both workers need to reload it
+    if matches!(result, MutationResult::Unchanged) {
+        handler::nonfatal!(try_open_repository(&window, None));
+    }
+
+    Ok(result)
+}
+
+#[tauri::command(async)]
+fn switch_workspace(
+    window: Window,
+    app_state: State<AppState>,
+    name: String,
+) -> Result<(), InvokeError> {
+    let switch = |session_tx: Sender<SessionEvent>| -> Result<messages::RepoConfig> {
+        let (call_tx, call_rx) = channel();
+        session_tx.send(SessionEvent::SwitchWorkspace {
+            tx: call_tx,
+            name: name.clone(),
+        })?;
+        call_rx.recv()?
+    };
+
+    // like opening, the reader switches second so that it starts from the writer's snapshot
+    switch(app_state.get_sender(window.label())).map_err(InvokeError::from_anyhow)?;
+    let config = switch(app_state.get_reader(window.label())).map_err(InvokeError::from_anyhow)?;
+
+    window
+        .emit("gg://repo/config", config)
+        .map_err(InvokeError::from_error)
+}
+
+fn try_open_repository(window: &Window, cwd: Option<PathBuf>) -> Result<()> {
+    log::info!("load workspace {cwd:#?}");
+
+    let app_state = window.state::<AppState>();
+
+    // the reader opens second, so that it starts from the writer's snapshot
+    let config = match open_workspace(&app_state.get_sender(window.label()), cwd.clone())? {
+        Ok(messages::RepoConfig::Workspace { .. }) => {
+            open_workspace(&app_state.get_reader(window.label()), cwd.clone())?
+        }
+        config => config,
+    };
+
+    match config {
+        Ok(config) => {
+            log::debug!("load workspace succeeded");
+            window.emit("gg://repo/config", config)?;
+        }
+        Err(err) => {
+            log::warn!("load workspace failed: {err}");
+            window.emit(
+                "gg://repo/config",
+                messages::RepoConfig::LoadError {
+                    absolute_path: cwd.unwrap_or(PathBuf::new()).into(),
+                    message: format!("{:#?}", err),
+                },
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn open_workspace(
+    session_tx: &Sender<SessionEvent>,
+    wd: Option<PathBuf>,
+) -> Result<Result<messages::RepoConfig>> {
+    let (call_tx, call_rx) = channel();
+    session_tx.send(SessionEvent::OpenWorkspace { tx: call_tx, wd })?;
+    Ok(call_rx.recv()?)
+}
+
+fn try_mutate<T: Mutation + Send + Sync + 'static>(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: T,
+) -> Result<MutationResult, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_sender(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::ExecuteMutation {
+            tx: call_tx,
+            mutation: Box::new(mutation),
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx.recv().map_err(InvokeError::from_error)
+}
+
+fn handle_window_event(window: &WebviewWindow, event: &WindowEvent) {
+    match *event {
+        WindowEvent::Focused(true) => {
+            log::debug!("window focused; requesting snapshot");
+
+            let app_state = window.state::<AppState>();
+
+            let session_tx: Sender<SessionEvent> = app_state.get_sender(window.label());
+            let (call_tx, call_rx) = channel();
+
+            handler::nonfatal!(session_tx.send(SessionEvent::ExecuteSnapshot { tx: call_tx }));
+
+            if let Some(status) = handler::nonfatal!(call_rx.recv()) {
+                handler::nonfatal!(window.emit("gg://repo/status", status));
+            }
+        }
+        _ => (),
+    }
+}
@@ -196,6 +196,21 @@ pub fn build_context(
                 true,
                 None::<&str>,
             )?,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &MenuItem::with_id(
+                app_handle,
+                "tree_reveal",
+                "Open in editor",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "tree_history",
+                "Show history",
+                true,
+                None::<&str>,
+            )?,
         ],
     )?;
 
@@ -350,6 +365,8 @@ pub fn handle_event(window: &Window, event: MenuEvent) -> Result<()> {
         "revision_restore" => window.emit("gg://context/revision", "restore")?,
         "tree_squash" => window.emit("gg://context/tree", "squash")?,
         "tree_restore" => window.emit("gg://context/tree", "restore")?,
+        "tree_reveal" => window.emit("gg://context/tree", "reveal")?,
+        "tree_history" => window.emit("gg://context/tree", "history")?,
         "branch_track" => window.emit("gg://context/branch", "track")?,
         "branch_untrack" => window.emit("gg://context/branch", "untrack")?,
         _ => (),
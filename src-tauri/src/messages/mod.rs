@@ -6,16 +6,16 @@ mod queries;
 pub use mutations::*;
 pub use queries::*;
 
-use std::path::Path;
+use std::{collections::HashMap, fmt, path::Path};
 
 use chrono::{DateTime, FixedOffset, Local, LocalResult, TimeZone, Utc};
-use jj_lib::backend::{Signature, Timestamp};
+use jj_lib::backend::{MillisSinceEpoch, Signature, Timestamp};
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "ts-rs")]
 use ts_rs::TS;
 
 /// Utility type used to abstract crlf/<br>/etc
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -37,7 +37,7 @@ where
 }
 
 /// Utility type used for platform-specific display
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -56,7 +56,7 @@ impl<T: AsRef<Path>> From<T> for DisplayPath {
 }
 
 /// Utility type used for round-tripping
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -79,11 +79,18 @@ pub enum RepoConfig {
     Initial,
     Workspace {
         absolute_path: DisplayPath,
-        git_remotes: Vec<String>,
+        git_remotes: Vec<GitRemote>,
         default_query: String,
         latest_query: String,
+        named_queries: Vec<NamedQuery>,
+        capabilities: RepoCapabilities,
         status: RepoStatus,
         theme: Option<String>,
+        /// The forge project of the upstream or origin remote, if recognised
+        upstream: Option<ForgeProject>,
+        /// The branch which trunk() resolves to, if any
+        trunk: Option<RefName>,
+        dates: DateDisplay,
     },
     #[allow(dead_code)]
     TimeoutError,
@@ -104,11 +111,390 @@ pub enum RepoConfig {
 )]
 pub struct RepoStatus {
     pub operation_description: String,
+    /// Full id of the operation, for queries which check that the frontend is up to date
+    pub operation_id: String,
     pub working_copy: CommitId,
+    /// Raised while producing this status, by the command which led to it
+    pub warnings: Vec<Warning>,
 }
 
-/// Branch or tag name with metadata.
+/// A problem which didn't stop a command, but which the user should know about
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct Warning {
+    /// Stable identifier which the frontend can use to look up a translation
+    pub code: String,
+    /// Values to substitute into a translation, by name
+    pub params: HashMap<String, String>,
+    /// English text, for use when no translation is available
+    pub message: String,
+}
+
+/// Text generated by the backend, which the frontend can translate
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct LocalizedText {
+    /// Stable identifier which the frontend can use to look up a translation
+    pub code: String,
+    /// Values to substitute into a translation, by name
+    pub params: HashMap<String, String>,
+    /// English text, for use when no translation is available
+    pub message: String,
+}
+
+impl LocalizedText {
+    fn new(code: &str, params: &[(&str, String)], message: String) -> LocalizedText {
+        LocalizedText {
+            code: code.to_owned(),
+            params: params
+                .iter()
+                .map(|(name, value)| ((*name).to_owned(), value.clone()))
+                .collect(),
+            message,
+        }
+    }
+}
+
+/// System conditions, reported by the frontend, under which gg should avoid work the user didn't ask for
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct BackgroundPolicy {
+    /// Running on battery with a power saver mode enabled
+    pub power_saving: bool,
+    /// The network connection is metered
+    pub metered: bool,
+}
+
+impl BackgroundPolicy {
+    /// whether background work should wait, given whether it would use the network
+    pub fn suspends(&self, uses_network: bool) -> bool {
+        self.power_saving || (uses_network && self.metered)
+    }
+}
+
+/// How timestamps are formatted; the worker does this so that every view shows the same dates
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DateDisplay {
+    /// "local", "utc", "original" (as recorded with the timestamp) or a fixed offset such as "+10:00"
+    pub time_zone: String,
+    /// Relative dates, like "3 days ago", go stale and may need to be refreshed
+    pub relative: bool,
+    /// strftime-style format for absolute dates
+    pub format: String,
+}
+
+impl DateDisplay {
+    pub fn format(&self, timestamp: &Timestamp) -> String {
+        self.localize(timestamp).message
+    }
+
+    /// codes are date-absolute, with the formatted date, or date-ago and date-in, with a count and unit
+    pub fn localize(&self, timestamp: &Timestamp) -> LocalizedText {
+        let Some(datetime) = datetime_from_timestamp(timestamp) else {
+            return LocalizedText::new("date-invalid", &[], String::new());
+        };
+
+        if self.relative {
+            return format_age(Local::now().signed_duration_since(datetime));
+        }
+
+        let datetime = match self.time_zone.as_str() {
+            "original" => datetime,
+            "utc" => datetime.with_timezone(&Utc).fixed_offset(),
+            zone => match zone.parse::<FixedOffset>() {
+                Ok(offset) => datetime.with_timezone(&offset),
+                Err(_) => datetime.with_timezone(&Local).fixed_offset(),
+            },
+        };
+        let date = datetime.format(&self.format).to_string();
+        LocalizedText::new("date-absolute", &[("date", date.clone())], date)
+    }
+}
+
+fn format_age(age: chrono::Duration) -> LocalizedText {
+    const UNITS: [(i64, &str); 6] = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (7 * 24 * 60 * 60, "week"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+
+    let seconds = age.num_seconds();
+    let Some((count, unit)) = UNITS
+        .iter()
+        .find(|(unit_seconds, _)| seconds.abs() >= *unit_seconds)
+        .map(|(unit_seconds, unit)| (seconds.abs() / unit_seconds, unit))
+    else {
+        return LocalizedText::new("date-just-now", &[], "just now".to_owned());
+    };
+
+    let params = [("count", count.to_string()), ("unit", (*unit).to_owned())];
+    let plural = if count == 1 { "" } else { "s" };
+    if seconds < 0 {
+        LocalizedText::new("date-in", &params, format!("in {count} {unit}{plural}"))
+    } else {
+        LocalizedText::new("date-ago", &params, format!("{count} {unit}{plural} ago"))
+    }
+}
+
+/// Changes made to the repo by the worker or the tools it runs, broadcast to any interested part of the frontend
+#[derive(Serialize, Clone)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum RepoEvent {
+    OperationApplied {
+        status: RepoStatus,
+    },
+    BranchesChanged {
+        names: Vec<String>,
+    },
+    SnapshotTaken {
+        status: RepoStatus,
+    },
+    /// Another jj or gg process wrote an operation, which has been loaded
+    RepoChangedExternally {
+        status: RepoStatus,
+    },
+    /// Sent periodically during slow snapshots, and once more when they finish
+    SnapshotProgress {
+        files_scanned: usize,
+        /// Only known once the snapshot is complete
+        files_added: Option<usize>,
+        current_dir: Option<String>,
+    },
+    ToolExited {
+        pid: u32,
+        name: String,
+        exit_code: Option<i32>,
+        timed_out: bool,
+    },
+    /// Remotes are git configuration rather than repo state, so changing them isn't an operation
+    RemotesChanged {
+        remotes: Vec<GitRemote>,
+    },
+    /// A command is waiting for the user to answer
+    InputRequested {
+        request: InputRequest,
+    },
+    /// Sent as a repo is opened, before its config is ready
+    LoadProgress {
+        stage: LoadStage,
+    },
+    /// Rows of a pane's log which matched a search, sent in batches while it continues
+    SearchMatches {
+        pane: String,
+        rows: Vec<usize>,
+    },
+}
+
+/// Steps in opening a repo, which can each be slow for large repos
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum LoadStage {
+    LoadingOperation,
+    LoadingIndex,
+    SnapshottingWorkingCopy,
+    /// Warming up the default query, which continues after the config has been sent
+    EvaluatingQuery,
+}
+
+/// Questions for the user, such as the username and password for a git remote
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct InputRequest {
+    pub title: String,
+    pub detail: String,
+    /// Names of the values to ask for; "password" should be masked
+    pub fields: Vec<String>,
+}
+
+/// Answers to an InputRequest, keyed by field
+#[derive(Deserialize)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct InputResponse {
+    pub cancel: bool,
+    pub fields: HashMap<String, String>,
+}
+
+// events are logged, and fields may hold passwords
+impl fmt::Debug for InputResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InputResponse")
+            .field("cancel", &self.cancel)
+            .field("fields", &self.fields.keys().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A git remote, with the time gg last fetched from it (if ever)
+#[derive(Serialize, Clone)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct GitRemote {
+    pub name: String,
+    pub fetch_url: String,
+    /// Only present if different from the fetch url
+    pub push_url: Option<String>,
+    pub last_fetch: Option<DateTime<Local>>,
+    /// Detected from the fetch url
+    pub project: Option<ForgeProject>,
+}
+
+/// Code hosts with APIs gg can talk to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum Forge {
+    GitHub,
+    GitLab,
+    Gerrit,
+}
+
+/// A URL attached to a change by the user, such as a CI run or a review
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct AnnotationLink {
+    pub label: String,
+    pub url: String,
+}
+
+/// Features which only some repos support, so that the frontend can hide whatever won't work
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RepoCapabilities {
+    pub has_git_backend: bool,
+    pub has_remotes: bool,
+    pub is_signing_configured: bool,
+    /// core.fsmonitor is watchman, so snapshots needn't scan the whole working copy
+    pub has_watchman: bool,
+    pub is_colocated: bool,
+}
+
+/// A revset saved by the user for this repo, offered alongside the built-in queries
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct NamedQuery {
+    pub name: String,
+    pub revset: String,
+}
+
+/// Where the user's review of a change has got to, tracked locally
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum ReviewState {
+    Todo,
+    InReview,
+    Approved,
+    Landed,
+}
+
+/// A repository hosted on a forge
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ForgeProject {
+    pub forge: Forge,
+    pub host: String,
+    /// e.g. owner/repo, or a GitLab group path
+    pub project: String,
+}
+
+/// Stores an API token for a forge instance, identified by its hostname
+#[derive(Deserialize)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetForgeToken {
+    pub forge: Forge,
+    pub host: String,
+    pub token: String,
+}
+
+// like InputResponse, this may be logged
+impl fmt::Debug for SetForgeToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SetForgeToken")
+            .field("forge", &self.forge)
+            .field("host", &self.host)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ClearForgeToken {
+    pub forge: Forge,
+    pub host: String,
+}
+
+/// Branch or tag name with metadata.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(tag = "type")]
 #[cfg_attr(
     feature = "ts-rs",
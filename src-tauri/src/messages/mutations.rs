@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use super::*;
 
 /// Common result type for mutating commands
@@ -18,15 +20,48 @@ pub enum MutationResult {
         new_selection: RevHeader,
     },
     PreconditionError {
+        /// Stable identifier which the frontend can use to look up a translation
+        code: String,
+        /// Values to substitute into a translation, by name
+        params: HashMap<String, String>,
+        /// English text, for use when no translation is available
         message: String,
     },
+    /// A push was stopped by the secret scan; it can be retried with allow_secrets set
+    SecretsFound {
+        remote_name: String,
+        findings: Vec<SecretFinding>,
+    },
+    /// A git hook rejected the change; its output explains why
+    HookFailed {
+        hook: String,
+        output: MultilineString,
+    },
+    /// A lock file stopped the command; stale locks can be broken with BreakStaleLock
+    Locked {
+        message: MultilineString,
+        locks: Vec<RepoLock>,
+    },
+    /// A rebase which abandoned the commits it left empty
+    Rebased {
+        new_status: RepoStatus,
+        abandoned_empty: usize,
+    },
+    /// A fetch made new commits visible
+    Fetched {
+        new_status: RepoStatus,
+        new_commits: usize,
+        incoming: Vec<IncomingCommits>,
+    },
     InternalError {
         message: MultilineString,
+        /// Written if gg.crash-reports.enabled is set
+        report: Option<DisplayPath>,
     },
 }
 
 /// Makes a revision the working copy
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -37,7 +72,7 @@ pub struct CheckoutRevision {
 }
 
 /// Creates a new revision and makes it the working copy
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -47,7 +82,7 @@ pub struct CreateRevision {
     pub parent_ids: Vec<RevId>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -59,7 +94,7 @@ pub struct InsertRevision {
     pub before_id: RevId,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -68,9 +103,11 @@ pub struct InsertRevision {
 pub struct MoveRevision {
     pub id: RevId,
     pub parent_ids: Vec<RevId>,
+    /// Abandon commits which the rebase leaves empty; if unset, gg.rebase.skip-empty decides
+    pub skip_empty: Option<bool>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -79,10 +116,43 @@ pub struct MoveRevision {
 pub struct MoveSource {
     pub id: RevId,
     pub parent_ids: Vec<CommitId>,
+    /// Abandon commits which the rebase leaves empty; if unset, gg.rebase.skip-empty decides
+    pub skip_empty: Option<bool>,
+}
+
+/// Rebases several revisions onto the same parents in one operation
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct MoveRevisions {
+    pub ids: Vec<RevId>,
+    pub parent_ids: Vec<RevId>,
+    pub mode: RebaseMode,
+    /// Abandon commits which the rebase leaves empty; if unset, gg.rebase.skip-empty decides
+    pub skip_empty: Option<bool>,
+}
+
+/// Which commits a rebase moves, like jj rebase's -r, -s and -b
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum RebaseMode {
+    /// Only the revisions; their descendants stay behind, and keep any ancestors which are also moved
+    Single,
+    /// The revisions and all of their descendants
+    WithDescendants,
+    /// Every revision between the new parents and the given ones, with their descendants
+    WholeBranch,
 }
 
 /// Updates a revision's description
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -92,10 +162,138 @@ pub struct DescribeRevision {
     pub id: RevId,
     pub new_description: String,
     pub reset_author: bool,
+    /// Replaces the description's Co-authored-by trailers; null leaves them as written
+    pub co_authors: Option<Vec<CoAuthor>>,
+}
+
+/// Describes several revisions in one operation, such as a ticket number for a whole stack
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DescribeRevisions {
+    pub revisions: Vec<RevisionDescription>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevisionDescription {
+    pub id: RevId,
+    pub new_description: String,
+}
+
+/// Rewrites a revision's author and timestamps, leaving its description and content alone
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct EditRevisionMetadata {
+    pub id: RevId,
+    /// Fields which are null keep their current values
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    pub author_timestamp: Option<DateTime<FixedOffset>>,
+    /// The committer is always the current user, but their timestamp can be chosen
+    pub committer_timestamp: Option<DateTime<FixedOffset>>,
+}
+
+/// Someone credited with a revision alongside its author, as forges such as GitHub recognise them
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct CoAuthor {
+    pub name: String,
+    pub email: String,
+}
+
+/// Creates a workspace in a new or empty directory, like `jj workspace add`
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct AddWorkspace {
+    pub path: String,
+    /// Defaults to the directory's name
+    pub name: Option<String>,
+    /// The parent of the new working-copy commit; defaults to the parents of the current one
+    pub revision_id: Option<RevId>,
+}
+
+/// Stops tracking another workspace's working copy; its files are left alone
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ForgetWorkspace {
+    pub name: String,
+}
+
+/// A lock file in the workspace, the repo or its git backend
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RepoLock {
+    pub path: String,
+    /// Only found on Linux
+    pub holder: Option<LockHolder>,
+    pub age_seconds: u64,
+    /// No process holds the lock, or it's old enough that none is likely to
+    pub is_stale: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub name: Option<String>,
+}
+
+/// Deletes a lock file which no process holds
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct BreakStaleLock {
+    pub path: String,
+}
+
+/// Signs a revision which was committed without a signature, or with someone else's
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SignRevision {
+    pub id: RevId,
 }
 
 /// Creates a copy of the revision with the same parents and content
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -105,7 +303,7 @@ pub struct DuplicateRevisions {
     pub ids: Vec<RevId>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -115,7 +313,43 @@ pub struct AbandonRevisions {
     pub ids: Vec<CommitId>,
 }
 
-#[derive(Deserialize, Debug)]
+/// Creates a revision which reverts another; by default, on top of the working copy
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct BackoutRevision {
+    pub id: RevId,
+    pub parent_ids: Vec<RevId>,
+}
+
+/// Splits changes to some paths out of a revision, into a new undescribed parent
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SplitRevision {
+    pub id: RevId,
+    pub paths: Vec<TreePath>,
+}
+
+/// Creates a chain of revisions from emailed patches; the path is an mbox file or a directory of messages
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ImportPatches {
+    pub path: String,
+    pub base_id: RevId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -127,7 +361,7 @@ pub struct MoveChanges {
     pub paths: Vec<TreePath>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -139,7 +373,162 @@ pub struct CopyChanges {
     pub paths: Vec<TreePath>,
 }
 
-#[derive(Deserialize, Debug)]
+/// Applies changes from another workspace's working copy to this one's
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct CopyWorkspaceChanges {
+    pub workspace_name: String,
+    /// All changes if empty
+    pub paths: Vec<TreePath>,
+    /// Squash rather than copy, which leaves the other workspace stale until it's next used
+    #[serde(default)]
+    pub remove_from_source: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct WriteResolvedFile {
+    pub id: RevId,
+    pub path: TreePath,
+    pub content: String,
+}
+
+/// Resolves a two-sided file conflict by choosing a side for each of its conflict hunks, in order
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ResolveConflict {
+    pub id: RevId,
+    pub path: TreePath,
+    pub choices: Vec<ConflictSide>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum ConflictSide {
+    Base,
+    Left,
+    Right,
+    /// Left, then right
+    Both,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct CreateCheckpoint {
+    pub label: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RestoreCheckpoint {
+    pub label: String,
+}
+
+/// Defines trunk() for this repo, e.g. as "main@origin"
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetTrunk {
+    pub revset: String,
+}
+
+/// Replaces the notes and links kept for a change; an empty note without links removes them
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetAnnotation {
+    pub id: RevId,
+    pub note: String,
+    pub links: Vec<AnnotationLink>,
+}
+
+/// Records how far review of a change has got; None stops tracking it
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetReviewState {
+    pub id: RevId,
+    pub state: Option<ReviewState>,
+    /// A pull request, merge request or Gerrit change which SyncReviewStates can follow
+    pub url: Option<String>,
+}
+
+/// Updates tracked review states from the forges their review URLs point to
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SyncReviewStates;
+
+/// Chooses the log query shown when this repo is opened; None reverts to revsets.log
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetDefaultQuery {
+    pub query: Option<String>,
+}
+
+/// Saves a revset under a name, replacing any query already saved with that name
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SaveNamedQuery {
+    pub name: String,
+    pub revset: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DeleteNamedQuery {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -149,7 +538,7 @@ pub struct TrackBranch {
     pub name: RefName,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -159,7 +548,7 @@ pub struct UntrackBranch {
     pub name: RefName,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -170,7 +559,7 @@ pub struct MoveBranch {
     pub to_id: RevId,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -178,9 +567,39 @@ pub struct MoveBranch {
 )]
 pub struct PushRemote {
     pub remote_name: String,
+    /// Push even if the secret scan finds something
+    #[serde(default)]
+    pub allow_secrets: bool,
 }
 
-#[derive(Deserialize, Debug)]
+/// Commits brought in by a fetched remote branch
+#[derive(Serialize, Clone)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct IncomingCommits {
+    pub branch: RefName,
+    pub count: usize,
+    pub head: RevHeader,
+}
+
+/// A line in an outgoing commit which looks like a credential
+#[derive(Serialize, Clone)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SecretFinding {
+    pub commit: CommitId,
+    pub path: TreePath,
+    pub line: usize,
+    pub kind: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -188,12 +607,88 @@ pub struct PushRemote {
 )]
 pub struct FetchRemote {
     pub remote_name: String,
+    /// Branches to fetch, as patterns like "main" or "glob:feature/*"; everything if empty
+    #[serde(default)]
+    pub branch_patterns: Vec<String>,
+    /// Fetch from every remote instead of just `remote_name`
+    #[serde(default)]
+    pub all_remotes: bool,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
     ts(export, export_to = "../src/messages/")
 )]
-pub struct UndoOperation;
+pub struct AddGitRemote {
+    pub remote_name: String,
+    pub url: String,
+}
+
+/// Removes a remote along with its remote-tracking branches
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RemoveGitRemote {
+    pub remote_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RenameGitRemote {
+    pub old_name: String,
+    pub new_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct UndoOperation {
+    /// Undo even if the operation pushed to a remote, which will keep what was pushed
+    #[serde(default)]
+    pub allow_remote: bool,
+}
+
+/// Reapplies the operation reverted by the last undo, if nothing has happened since
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RedoOperation;
+
+/// Resets the repo to its state after an earlier operation, like `jj op restore`
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RestoreOperation {
+    pub operation_id: String,
+}
+
+/// Restores a file or directory in the working copy to its content after an earlier operation, which can
+/// recover changes that were only ever recorded by a snapshot
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RestoreFileFromOperation {
+    pub operation_id: String,
+    pub path: TreePath,
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use super::*;
 
 /// A change or commit id with a disambiguated prefix
@@ -7,7 +9,7 @@ pub trait Id {
     fn rest(&self) -> &String;
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(tag = "type")]
 #[cfg_attr(
     feature = "ts-rs",
@@ -32,7 +34,7 @@ impl Id for CommitId {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(tag = "type")]
 #[cfg_attr(
     feature = "ts-rs",
@@ -59,7 +61,7 @@ impl Id for ChangeId {
 
 /// A pair of ids representing the ui's view of a revision.
 /// The worker may use one or both depending on policy.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -70,7 +72,7 @@ pub struct RevId {
     pub commit: CommitId,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -85,9 +87,38 @@ pub struct RevHeader {
     pub is_immutable: bool,
     pub branches: Vec<RefName>,
     pub parent_ids: Vec<CommitId>,
+    pub signature: Option<RevSignature>,
+}
+
+/// A revision's signature; it's only verified when the revision is queried directly, since that runs the signing program
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevSignature {
+    pub status: SignatureStatus,
+    pub key: Option<String>,
+    /// The signer, as described by the signing program
+    pub display: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum SignatureStatus {
+    Unverified,
+    Good,
+    /// Valid, but made with a key that isn't known or trusted
+    Unknown,
+    Bad,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -97,16 +128,19 @@ pub struct RevAuthor {
     pub email: String,
     pub name: String,
     pub timestamp: chrono::DateTime<Local>,
+    /// According to the user's date settings
+    pub formatted_timestamp: LocalizedText,
 }
 
-impl From<&Signature> for RevAuthor {
-    fn from(value: &Signature) -> Self {
+impl RevAuthor {
+    pub fn new(value: &Signature, dates: &DateDisplay) -> Self {
         RevAuthor {
             name: value.name.clone(),
             email: value.email.clone(),
             timestamp: datetime_from_timestamp(&value.timestamp)
                 .expect("convert timestamp to datetime")
                 .with_timezone(&Local),
+            formatted_timestamp: dates.localize(&value.timestamp),
         }
     }
 }
@@ -121,6 +155,26 @@ pub struct RevChange {
     pub kind: ChangeKind,
     pub path: TreePath,
     pub has_conflict: bool,
+    pub file_kind: FileKind,
+}
+
+/// What a file contains, so that the frontend can preview images and avoid rendering binary data as text
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum FileKind {
+    Text,
+    Binary,
+    /// base64 contents of each side, only included in diffs and only for small images
+    Image {
+        mime: String,
+        before: Option<String>,
+        after: Option<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize)]
@@ -151,17 +205,25 @@ pub enum RevResult {
         parents: Vec<RevHeader>,
         changes: Vec<RevChange>,
         conflicts: Vec<TreePath>,
+        warnings: Vec<Warning>,
     },
 }
 
-#[derive(Serialize, Clone, Copy, Debug)]
+/// A checkout of the repo and the commit it was last snapshotted to
+#[derive(Serialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
     ts(export, export_to = "../src/messages/")
 )]
-pub struct LogCoordinates(pub usize, pub usize);
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub working_copy: RevHeader,
+    /// The workspace open in this window
+    pub is_current: bool,
+}
 
+/// Outcome of looking up a change or commit id entered by the user
 #[derive(Serialize, Debug)]
 #[serde(tag = "type")]
 #[cfg_attr(
@@ -169,6 +231,137 @@ pub struct LogCoordinates(pub usize, pub usize);
     derive(TS),
     ts(export, export_to = "../src/messages/")
 )]
+pub enum PrefixResolution {
+    Resolved {
+        id: RevId,
+    },
+    /// The prefix is shared by several ids, or names a divergent change
+    Ambiguous {
+        candidates: Vec<RevHeader>,
+    },
+    NotFound {
+        prefix: String,
+    },
+}
+
+/// One end of a drag, described by whatever the frontend has to hand; ids take precedence over the row
+#[derive(Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DragPayload {
+    /// Index of a row in the pane's log, as it was last sent
+    pub row: Option<usize>,
+    pub change_prefix: Option<String>,
+    pub commit_hex: Option<String>,
+}
+
+/// A drag endpoint as of the latest operation
+#[derive(Serialize, Debug)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum DragTarget {
+    Current {
+        header: RevHeader,
+    },
+    /// The change has been rewritten since the payload's commit; the header is its new commit
+    Rewritten {
+        header: RevHeader,
+    },
+    /// The change has diverged, and the payload doesn't say which commit was meant
+    Ambiguous {
+        candidates: Vec<RevHeader>,
+    },
+    /// The change was abandoned, or the payload didn't identify one
+    Missing,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DragResolution {
+    /// The operation which the targets are valid at
+    pub op_id: String,
+    /// Another operation has happened since the one the frontend last saw
+    pub is_stale: bool,
+    pub targets: Vec<DragTarget>,
+}
+
+/// Something which can be opened in a forge's web UI
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum WebTarget {
+    Commit {
+        id: CommitId,
+    },
+    Branch {
+        name: String,
+    },
+    File {
+        id: CommitId,
+        path: TreePath,
+        line: Option<u32>,
+    },
+}
+
+/// What pasted text was recognised as
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum LookupSource {
+    /// A full or partial change or commit id
+    Id,
+    /// A branch, remote branch or tag name
+    Ref,
+    /// A pull request, merge request or Gerrit change URL
+    Review,
+}
+
+/// Commits identified from pasted text
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct TextLookup {
+    /// Not present if nothing matched
+    pub source: Option<LookupSource>,
+    pub commits: Vec<RevHeader>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct LogCoordinates(pub usize, pub usize);
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
 pub enum LogLine {
     FromNode {
         source: LogCoordinates,
@@ -187,7 +380,7 @@ pub enum LogLine {
     },
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Clone, Debug, PartialEq)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -200,6 +393,105 @@ pub struct LogRow {
     pub lines: Vec<LogLine>,
 }
 
+/// Structured restrictions applied on top of a log query's revset
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct LogFilter {
+    /// Repo-relative paths, /-separated; revisions must modify one of these files or directories
+    pub paths: Vec<String>,
+    /// Files or directories picked from a tree, which restrict revisions like `paths`
+    #[serde(default)]
+    pub tree_paths: Vec<TreePath>,
+    /// Substring of the author's name or email
+    pub author: Option<String>,
+    /// Substring of the committer's name or email
+    pub committer: Option<String>,
+    /// Inclusive bounds on the commit timestamp
+    pub after: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+}
+
+/// How much effort to spend counting a log query's rows
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum LogCount {
+    /// Stops counting at gg.queries.large-repo-heuristic rows
+    Estimate,
+    Exact,
+}
+
+/// How long log queries have taken to produce their first page, slowest first
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SlowQueries {
+    pub queries: Vec<RevsetTiming>,
+    pub functions: Vec<RevsetFunctionTiming>,
+    /// Ways to speed up the default query, if it is slow
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevsetTiming {
+    pub query: String,
+    pub evaluations: usize,
+    pub average_ms: u32,
+    pub slowest_ms: u32,
+    pub is_default: bool,
+}
+
+/// Functions are charged for the whole of each query they appear in
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevsetFunctionTiming {
+    pub name: String,
+    pub evaluations: usize,
+    pub total_ms: u32,
+}
+
+/// A completed search of a log query; the matching rows have already been sent as events
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct LogSearch {
+    pub match_count: usize,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct LogTotal {
+    pub rows: usize,
+    /// If set, there are at least this many rows
+    pub is_estimate: bool,
+}
+
 #[derive(Serialize)]
 #[cfg_attr(
     feature = "ts-rs",
@@ -209,10 +501,561 @@ pub struct LogRow {
 pub struct LogPage {
     pub rows: Vec<LogRow>,
     pub has_more: bool,
+    /// Only included in the first page, if a count was requested
+    pub total: Option<LogTotal>,
+    pub warnings: Vec<Warning>,
+}
+
+/// Differences between the rows of a refreshed query and those previously sent
+#[derive(Serialize)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct LogDelta {
+    pub added: Vec<LogRow>,
+    pub changed: Vec<LogRow>,
+    pub removed: Vec<CommitId>,
+    pub has_more: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct LargePath {
+    pub path: TreePath,
+    pub size: u64,
+}
+
+/// The biggest files and directories in a revision's tree
+#[derive(Serialize)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct LargePaths {
+    pub total_size: u64,
+    pub files: Vec<LargePath>,
+    pub directories: Vec<LargePath>,
+}
+
+/// One level of a revision's tree; directories are expanded by querying them in turn
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct TreeEntry {
+    pub path: TreePath,
+    pub name: String,
+    pub kind: TreeEntryKind,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum TreeEntryKind {
+    File { size: u64, is_executable: bool },
+    Symlink { target: String },
+    Directory,
+    Submodule,
+    Conflict,
+}
+
+/// Lines of a file at some revision, grouped by the revision which introduced them
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct FileBlame {
+    pub path: TreePath,
+    pub lines: Vec<String>,
+    pub ranges: Vec<BlameRange>,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct BlameRange {
+    /// 1-based and inclusive
+    pub first_line: usize,
+    pub last_line: usize,
+    pub id: RevId,
+    pub author: RevAuthor,
+    /// First line of the revision's description
+    pub summary: String,
+}
+
+/// A read-only view of a file at some revision
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct FileContents {
+    pub path: TreePath,
+    pub file_kind: FileKind,
+    /// Empty unless the file is text; conflicted files include their conflict markers
+    pub text: String,
+    pub size: u64,
+    pub is_truncated: bool,
+    pub has_conflict: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct PathOwners {
+    pub path: TreePath,
+    pub owners: Vec<String>,
+}
+
+/// Owners of a revision's changed paths, according to its CODEOWNERS file
+#[derive(Serialize)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevOwners {
+    /// The CODEOWNERS file used, if one was found
+    pub source: Option<TreePath>,
+    pub paths: Vec<PathOwners>,
+}
+
+/// Pre-formatted values for displaying a revision, so that every view renders it the same way
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct TemplateContext {
+    /// Shortened to gg.template.id-length, or longer if needed to be unique
+    pub change_id: String,
+    pub commit_id: String,
+    pub author_initials: String,
+    /// The first of gg.template.age-buckets which the author timestamp falls into, or "older"
+    pub age_bucket: String,
+}
+
+/// Where a revision sits relative to branches and trunk
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevContext {
+    /// Branches pointing at the closest descendants which have any
+    pub nearest_branches: Vec<RefName>,
+    /// Whether the revision is an ancestor of trunk()
+    pub in_trunk: bool,
+    /// Branches pointing at trunk() itself, e.g. main
+    pub trunk_branches: Vec<RefName>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum MergeSegment {
+    Resolved {
+        text: String,
+    },
+    Conflict {
+        base: String,
+        left: String,
+        right: String,
+    },
+}
+
+/// A conflicted path; hunks are only listed for two-sided conflicts between files
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct FileConflict {
+    pub path: TreePath,
+    pub num_sides: usize,
+    pub hunks: Vec<ConflictHunk>,
+}
+
+/// A region which the two sides of a conflict changed in different ways
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ConflictHunk {
+    pub base: String,
+    pub left: String,
+    pub right: String,
+}
+
+/// The sides of a two-sided file conflict, plus the result of merging them with conflicts left in place
+#[derive(Serialize)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ThreeWayMerge {
+    pub path: TreePath,
+    pub base: String,
+    pub left: String,
+    pub right: String,
+    pub segments: Vec<MergeSegment>,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum MergeToolSource {
+    Jj,
+    Git,
+    Known,
+}
+
+/// An external tool with jj-style argument templates, or a shell command using git's variables for git
+/// tools; empty args mean it can't do that job
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct MergeTool {
+    pub name: String,
+    pub source: MergeToolSource,
+    pub program: String,
+    /// Where the program was found, if it's installed
+    pub path: Option<DisplayPath>,
+    pub merge_args: Vec<String>,
+    pub diff_args: Vec<String>,
+}
+
+/// A page of the operation log, newest first
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct OperationPage {
+    pub rows: Vec<OperationRow>,
+    pub has_more: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct OperationRow {
+    /// Short hash; pass the last row's id to get the next page
+    pub id: String,
+    pub description: String,
+    pub timestamp: DateTime<Local>,
+    pub formatted_timestamp: String,
+    /// Includes gg.action for operations made by gg
+    pub tags: HashMap<String, String>,
+    /// Earlier snapshots folded into this one by gg.operations.snapshots
+    pub collapsed_snapshots: usize,
+}
+
+/// The commits a change pointed to, from an operation until the one which began the previous entry
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ChangeMapping {
+    /// More than one if the change was divergent
+    pub commits: Vec<CommitId>,
+    /// Short hash of the earliest operation with this mapping
+    pub operation_id: String,
+    pub timestamp: DateTime<Local>,
+    pub formatted_timestamp: String,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ChangeHistory {
+    /// Newest first
+    pub entries: Vec<ChangeMapping>,
+    /// Older operations weren't examined
+    pub has_more: bool,
+}
+
+/// Notes and links the user has attached to a change
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ChangeAnnotation {
+    /// Full change id, in the same form as ChangeId.hex
+    pub change_id: String,
+    pub note: String,
+    pub links: Vec<AnnotationLink>,
+    pub updated: DateTime<Local>,
+}
+
+/// Something the user can do with the current selection, for the command palette
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct AvailableCommand {
+    /// Name of the backend command which carries it out
+    pub command: String,
+    pub label: String,
+    /// For commands which act on one of the selection's branches
+    pub branch: Option<RefName>,
+    /// For commands which act on a remote
+    pub remote_name: Option<String>,
+}
+
+impl AvailableCommand {
+    pub fn new(command: &str, label: impl Into<String>) -> Self {
+        AvailableCommand {
+            command: command.to_owned(),
+            label: label.into(),
+            branch: None,
+            remote_name: None,
+        }
+    }
+
+    pub fn with_branch(mut self, branch: &RefName) -> Self {
+        self.branch = Some(branch.clone());
+        self
+    }
+
+    pub fn with_remote(mut self, remote_name: &str) -> Self {
+        self.remote_name = Some(remote_name.to_owned());
+        self
+    }
+}
+
+/// A change whose review state the user is tracking
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ChangeReview {
+    /// Full change id, in the same form as ChangeId.hex
+    pub change_id: String,
+    pub state: ReviewState,
+    pub url: Option<String>,
+    pub updated: DateTime<Local>,
+}
+
+/// A named save point in the operation log
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct Checkpoint {
+    pub label: String,
+    pub operation_id: String,
+    pub created: DateTime<Local>,
+    pub is_autosave: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum HistoryFormat {
+    Json,
+    Csv,
+}
+
+/// Writes the operation log to a file; filters are inclusive and optional
+#[derive(Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ExportOperationHistory {
+    pub path: String,
+    pub format: HistoryFormat,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub username: Option<String>,
+}
+
+/// How the lines of a diff are arranged for display
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum DiffStyle {
+    Unified,
+    SideBySide,
+}
+
+/// Where the next page of a diff starts: a file, and how many of its hunks' lines were already sent
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DiffCursor {
+    pub repo_path: String,
+    pub line: usize,
+}
+
+/// Part of a revision's diff, limited to roughly a page of lines
+#[derive(Serialize)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DiffPage {
+    pub files: Vec<FileDiff>,
+    pub next: Option<DiffCursor>,
+}
+
+/// A file's changes; a large file is split over several pages, with `continued` set after the first
+#[derive(Serialize)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct FileDiff {
+    pub path: TreePath,
+    pub kind: ChangeKind,
+    pub file_kind: FileKind,
+    /// false for binary files, conflicts and symlinks, which have no hunks
+    pub has_lines: bool,
+    pub continued: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Changed lines with some context, as one column or paired old and new columns
+#[derive(Serialize, Debug)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum DiffHunk {
+    Unified { lines: Vec<DiffLine> },
+    SideBySide { rows: Vec<DiffRow> },
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub old_number: Option<usize>,
+    pub new_number: Option<usize>,
+    pub text: String,
+    /// Words which differ from the line's counterpart on the other side, if it has one
+    pub highlights: Vec<TextRange>,
+}
+
+/// Part of a line, in UTF-16 code units so that it can be used to slice a JavaScript string
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct TextRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum DiffLineKind {
+    Context,
+    Removed,
+    Added,
+}
+
+/// A removed line beside the added line which replaced it; context lines appear on both sides
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DiffRow {
+    pub left: Option<DiffLine>,
+    pub right: Option<DiffLine>,
+}
+
+pub(crate) fn timestamp_from_datetime(datetime: &DateTime<FixedOffset>) -> Timestamp {
+    Timestamp {
+        timestamp: MillisSinceEpoch(datetime.timestamp_millis()),
+        tz_offset: datetime.offset().local_minus_utc() / 60,
+    }
 }
 
 // from time_util, which is not pub
-fn datetime_from_timestamp(context: &Timestamp) -> Option<DateTime<FixedOffset>> {
+pub(crate) fn datetime_from_timestamp(context: &Timestamp) -> Option<DateTime<FixedOffset>> {
     let utc = match Utc.timestamp_opt(
         context.timestamp.0.div_euclid(1000),
         (context.timestamp.0.rem_euclid(1000)) as u32 * 1000000,
@@ -0,0 +1,202 @@
+//! External tools (editors, diff and merge tools) run as child processes, tracked until they exit
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Read, Write},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{mpsc::Sender, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::messages::RepoEvent;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// everything needed to launch a tool; the temp dir, if any, is deleted once it exits
+pub struct ToolInvocation {
+    pub name: String,
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    /// variables set for the tool, such as the file paths which git's tool commands read
+    pub env: Vec<(String, PathBuf)>,
+    pub cwd: PathBuf,
+    pub timeout: Option<Duration>,
+    pub temp_dir: Option<PathBuf>,
+}
+
+/// processes are shared with their watcher threads, which reap them
+#[derive(Default, Clone)]
+pub struct ToolProcesses {
+    running: Arc<Mutex<HashMap<u32, Child>>>,
+}
+
+impl ToolProcesses {
+    /// creates a fresh directory for a tool's input or output files, which only this user can access;
+    /// a name another user could predict and create first would let them plant links in it
+    pub fn create_temp_dir(&self) -> Result<PathBuf> {
+        let dir = tempfile::Builder::new()
+            .prefix("gg-")
+            .tempdir()
+            .context("create temp dir")?;
+        Ok(dir.into_path())
+    }
+
+    /// starts a tool and returns its pid; a ToolExited event is sent when it finishes
+    pub fn spawn(
+        &self,
+        invocation: ToolInvocation,
+        events: Option<Sender<RepoEvent>>,
+    ) -> Result<u32> {
+        let child = match Command::new(&invocation.program)
+            .args(&invocation.args)
+            .envs(invocation.env.iter().map(|(name, value)| (name, value)))
+            .current_dir(&invocation.cwd)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                if let Some(temp_dir) = &invocation.temp_dir {
+                    _ = fs::remove_dir_all(temp_dir);
+                }
+                return Err(err).with_context(|| format!("spawn {}", invocation.program.display()));
+            }
+        };
+        let pid = child.id();
+
+        self.running
+            .lock()
+            .expect("process table poisoned")
+            .insert(pid, child);
+
+        let processes = self.clone();
+        thread::spawn(move || processes.watch(pid, invocation, events));
+
+        Ok(pid)
+    }
+
+    /// runs a tool to completion, feeding it input and returning what it printed
+    pub fn run_with_input(&self, invocation: ToolInvocation, input: Vec<u8>) -> Result<String> {
+        let mut child = Command::new(&invocation.program)
+            .args(&invocation.args)
+            .envs(invocation.env.iter().map(|(name, value)| (name, value)))
+            .current_dir(&invocation.cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("spawn {}", invocation.program.display()))?;
+        let pid = child.id();
+
+        // pipes are serviced on their own threads so that a chatty tool can't deadlock us
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let writer = thread::spawn(move || stdin.write_all(&input));
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let reader = thread::spawn(move || {
+            let mut output = String::new();
+            stdout.read_to_string(&mut output).map(|_| output)
+        });
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let error_reader = thread::spawn(move || {
+            let mut output = String::new();
+            stderr.read_to_string(&mut output).map(|_| output)
+        });
+
+        self.running
+            .lock()
+            .expect("process table poisoned")
+            .insert(pid, child);
+        let (exit_code, timed_out) = self.wait(pid, &invocation);
+
+        if let Some(temp_dir) = &invocation.temp_dir {
+            _ = fs::remove_dir_all(temp_dir);
+        }
+
+        // tools which don't need all their input may close stdin early
+        _ = writer.join();
+        let output = reader
+            .join()
+            .map_err(|_| anyhow!("stdout reader panicked"))?
+            .context("read stdout")?;
+        let errors = error_reader
+            .join()
+            .map_err(|_| anyhow!("stderr reader panicked"))?
+            .unwrap_or_default();
+
+        if timed_out {
+            Err(anyhow!("{} timed out", invocation.name))
+        } else if exit_code != Some(0) {
+            Err(anyhow!(
+                "{} failed ({}): {}",
+                invocation.name,
+                exit_code.map_or("killed".to_owned(), |code| format!("exit code {code}")),
+                errors.trim()
+            ))
+        } else {
+            Ok(output)
+        }
+    }
+
+    fn watch(&self, pid: u32, invocation: ToolInvocation, events: Option<Sender<RepoEvent>>) {
+        let (exit_code, timed_out) = self.wait(pid, &invocation);
+
+        if let Some(temp_dir) = &invocation.temp_dir {
+            if let Err(err) = fs::remove_dir_all(temp_dir) {
+                log::warn!("remove {}: {err}", temp_dir.display());
+            }
+        }
+
+        if let Some(events) = events {
+            _ = events.send(RepoEvent::ToolExited {
+                pid,
+                name: invocation.name,
+                exit_code,
+                timed_out,
+            });
+        }
+    }
+
+    // polls rather than blocking in wait(), so that the timeout can be enforced
+    fn wait(&self, pid: u32, invocation: &ToolInvocation) -> (Option<i32>, bool) {
+        let started = Instant::now();
+        let mut timed_out = false;
+
+        let exit_code = loop {
+            let mut running = self.running.lock().expect("process table poisoned");
+            let Some(child) = running.get_mut(&pid) else {
+                break None;
+            };
+
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    running.remove(&pid);
+                    break status.code();
+                }
+                Ok(None) => {
+                    if invocation
+                        .timeout
+                        .is_some_and(|timeout| started.elapsed() > timeout)
+                    {
+                        log::warn!("{} timed out; killing pid {pid}", invocation.name);
+                        timed_out = true;
+                        _ = child.kill();
+                    }
+                }
+                Err(err) => {
+                    log::error!("wait for {}: {err}", invocation.name);
+                    running.remove(&pid);
+                    break None;
+                }
+            }
+
+            drop(running);
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        (exit_code, timed_out)
+    }
+}
@@ -0,0 +1,271 @@
+//! Per-repo state owned by gg rather than jj, persisted alongside the repo's own data
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::messages::ReviewState;
+
+const STORE_DIR: &str = "gg";
+const STORE_FILE: &str = "state.json";
+
+#[derive(Serialize, Deserialize, Default)]
+struct StoreData {
+    #[serde(default)]
+    last_fetch: HashMap<String, DateTime<Utc>>,
+    #[serde(default)]
+    checkpoints: Vec<Checkpoint>,
+    #[serde(default)]
+    default_query: Option<String>,
+    /// in the order the user saved them
+    #[serde(default)]
+    named_queries: Vec<NamedQuery>,
+    /// jj doesn't record where a repo's workspaces are, so gg remembers the ones it has seen
+    #[serde(default)]
+    workspaces: HashMap<String, PathBuf>,
+    /// keyed by change id, so that they follow changes through rewrites
+    #[serde(default)]
+    annotations: HashMap<String, Annotation>,
+    /// also keyed by change id
+    #[serde(default)]
+    reviews: HashMap<String, Review>,
+}
+
+/// A user-named operation, which may later be restored
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Checkpoint {
+    pub label: String,
+    pub op_id: String,
+    pub created: DateTime<Utc>,
+    /// made by the autosave timer rather than the user, and discarded when it's no longer recent
+    #[serde(default)]
+    pub autosave: bool,
+}
+
+/// A revset the user has saved under a name
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NamedQuery {
+    pub name: String,
+    pub revset: String,
+}
+
+/// The user's own notes and links for a change, such as its CI run or review
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Annotation {
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub links: Vec<AnnotationLink>,
+    pub updated: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AnnotationLink {
+    pub label: String,
+    pub url: String,
+}
+
+/// The user's progress reviewing a change, optionally mirroring a review on a forge
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Review {
+    pub state: ReviewState,
+    #[serde(default)]
+    pub url: Option<String>,
+    pub updated: DateTime<Utc>,
+}
+
+pub struct RepoStore {
+    path: PathBuf,
+    data: StoreData,
+}
+
+impl RepoStore {
+    /// missing or unreadable state is not an error - gg can always start over
+    pub fn load(repo_path: &Path) -> RepoStore {
+        let path = repo_path.join(STORE_DIR).join(STORE_FILE);
+
+        let data = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+                log::warn!("discarding unreadable {}: {err}", path.display());
+                StoreData::default()
+            }),
+            Err(_) => StoreData::default(),
+        };
+
+        RepoStore { path, data }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("create store dir")?;
+        }
+        fs::write(&self.path, serde_json::to_vec_pretty(&self.data)?)
+            .with_context(|| format!("write {}", self.path.display()))
+    }
+
+    pub fn last_fetch(&self, remote_name: &str) -> Option<DateTime<Utc>> {
+        self.data.last_fetch.get(remote_name).copied()
+    }
+
+    pub fn set_last_fetch(&mut self, remote_name: &str, time: DateTime<Utc>) -> Result<()> {
+        self.data.last_fetch.insert(remote_name.to_owned(), time);
+        self.save()
+    }
+
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.data.checkpoints
+    }
+
+    pub fn checkpoint(&self, label: &str) -> Option<&Checkpoint> {
+        self.data
+            .checkpoints
+            .iter()
+            .find(|checkpoint| checkpoint.label == label)
+    }
+
+    pub fn default_query(&self) -> Option<&str> {
+        self.data.default_query.as_deref()
+    }
+
+    pub fn set_default_query(&mut self, query: Option<String>) -> Result<()> {
+        self.data.default_query = query;
+        self.save()
+    }
+
+    pub fn named_queries(&self) -> &[NamedQuery] {
+        &self.data.named_queries
+    }
+
+    /// replaces any query with the same name, keeping its position
+    pub fn save_named_query(&mut self, query: NamedQuery) -> Result<()> {
+        match self
+            .data
+            .named_queries
+            .iter_mut()
+            .find(|existing| existing.name == query.name)
+        {
+            Some(existing) => *existing = query,
+            None => self.data.named_queries.push(query),
+        }
+        self.save()
+    }
+
+    /// false if there was no query with this name
+    pub fn delete_named_query(&mut self, name: &str) -> Result<bool> {
+        let count = self.data.named_queries.len();
+        self.data.named_queries.retain(|query| query.name != name);
+        if self.data.named_queries.len() == count {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    pub fn workspace_path(&self, name: &str) -> Option<&Path> {
+        self.data.workspaces.get(name).map(PathBuf::as_path)
+    }
+
+    pub fn set_workspace_path(&mut self, name: &str, path: &Path) -> Result<()> {
+        if self.workspace_path(name) == Some(path) {
+            return Ok(());
+        }
+        self.data
+            .workspaces
+            .insert(name.to_owned(), path.to_owned());
+        self.save()
+    }
+
+    pub fn remove_workspace_path(&mut self, name: &str) -> Result<()> {
+        if self.data.workspaces.remove(name).is_some() {
+            self.save()
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn add_checkpoint(&mut self, checkpoint: Checkpoint) -> Result<()> {
+        self.data.checkpoints.push(checkpoint);
+        self.save()
+    }
+
+    pub fn annotations(&self) -> &HashMap<String, Annotation> {
+        &self.data.annotations
+    }
+
+    /// None removes any annotation for the change
+    pub fn set_annotation(
+        &mut self,
+        change_id: &str,
+        annotation: Option<Annotation>,
+    ) -> Result<()> {
+        match annotation {
+            Some(annotation) => {
+                self.data
+                    .annotations
+                    .insert(change_id.to_owned(), annotation);
+            }
+            None => {
+                if self.data.annotations.remove(change_id).is_none() {
+                    return Ok(());
+                }
+            }
+        }
+        self.save()
+    }
+
+    pub fn reviews(&self) -> &HashMap<String, Review> {
+        &self.data.reviews
+    }
+
+    /// None stops tracking the change
+    pub fn set_review(&mut self, change_id: &str, review: Option<Review>) -> Result<()> {
+        match review {
+            Some(review) => {
+                self.data.reviews.insert(change_id.to_owned(), review);
+            }
+            None => {
+                if self.data.reviews.remove(change_id).is_none() {
+                    return Ok(());
+                }
+            }
+        }
+        self.save()
+    }
+
+    pub fn latest_autosave(&self) -> Option<&Checkpoint> {
+        self.data
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|checkpoint| checkpoint.autosave)
+    }
+
+    /// adds an autosave, then forgets the oldest ones beyond `keep`; user checkpoints are never forgotten
+    pub fn add_autosave(&mut self, checkpoint: Checkpoint, keep: usize) -> Result<()> {
+        self.data.checkpoints.push(checkpoint);
+
+        let mut excess = self
+            .data
+            .checkpoints
+            .iter()
+            .filter(|checkpoint| checkpoint.autosave)
+            .count()
+            .saturating_sub(keep);
+        self.data.checkpoints.retain(|checkpoint| {
+            if checkpoint.autosave && excess > 0 {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+
+        self.save()
+    }
+}
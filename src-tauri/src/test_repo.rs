@@ -0,0 +1,41 @@
+//! Fixtures for the test repo, shared by the unit tests and the benchmarks
+
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+};
+
+use tempfile::{tempdir, TempDir};
+use zip::ZipArchive;
+
+pub fn mkrepo() -> TempDir {
+    let repo_dir = tempdir().unwrap();
+    let mut archive_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    archive_path.push("resources/test-repo.zip");
+    let archive_file = File::open(&archive_path).unwrap();
+    let mut archive = ZipArchive::new(archive_file).unwrap();
+
+    archive.extract(repo_dir.path()).unwrap();
+
+    repo_dir
+}
+
+/// the test repo with its git repo moved into the workspace, like `jj git init --colocate`
+pub fn mkcolocatedrepo() -> TempDir {
+    let repo_dir = mkrepo();
+
+    let store_dir = repo_dir.path().join(".jj").join("repo").join("store");
+    let git_dir = repo_dir.path().join(".git");
+    fs::rename(store_dir.join("git"), &git_dir).unwrap();
+    fs::write(store_dir.join("git_target"), "../../../.git").unwrap();
+
+    let git_repo = git2::Repository::open(&git_dir).unwrap();
+    git_repo
+        .config()
+        .unwrap()
+        .set_bool("core.bare", false)
+        .unwrap();
+    git_repo.index().unwrap().write().unwrap();
+
+    repo_dir
+}
@@ -0,0 +1,68 @@
+//! Periodic snapshots of the working copy, recorded as checkpoints so that recent work can be recovered
+//! after a crash or a mistake
+
+use std::{
+    sync::mpsc::{channel, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+
+use anyhow::Result;
+use chrono::Utc;
+use jj_lib::object_id::ObjectId;
+
+use super::SessionEvent;
+use crate::{config::GGSettings, gui_util::WorkspaceSession, store::Checkpoint};
+
+/// queues Autosave at each interval until dropped
+pub struct AutosaveTimer {
+    _stop: Sender<()>,
+}
+
+pub fn start(interval: Duration, queue: Sender<SessionEvent>) -> AutosaveTimer {
+    let (stop_tx, stop_rx) = channel::<()>();
+
+    thread::spawn(move || loop {
+        match stop_rx.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => {
+                if queue.send(SessionEvent::Autosave).is_err() {
+                    return;
+                }
+            }
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+        }
+    });
+
+    AutosaveTimer { _stop: stop_tx }
+}
+
+/// checkpoints the current operation unless the last autosave already did; returns true if one was added
+pub fn record(ws: &mut WorkspaceSession) -> Result<bool> {
+    let op_id = ws.repo().op_id().hex();
+    if ws
+        .store
+        .latest_autosave()
+        .is_some_and(|autosave| autosave.op_id == op_id)
+    {
+        return Ok(false);
+    }
+
+    let created = Utc::now();
+    let label = format!("autosave {}", created.format("%Y-%m-%d %H:%M:%S"));
+    if ws.store.checkpoint(&label).is_some() {
+        return Ok(false);
+    }
+
+    let keep = ws.settings.autosave_keep();
+    ws.store.add_autosave(
+        Checkpoint {
+            label,
+            op_id,
+            created,
+            autosave: true,
+        },
+        keep,
+    )?;
+
+    Ok(true)
+}
@@ -0,0 +1,140 @@
+//! Attribution of each line of a file to the revision which introduced it
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use jj_lib::{
+    backend::CommitId,
+    diff::{Diff, DiffHunk},
+    repo_path::RepoPath,
+    revset::RevsetExpression,
+};
+
+use crate::{
+    gui_util::WorkspaceSession,
+    messages::{BlameRange, FileBlame, RevAuthor, RevId, TreePath},
+};
+
+use super::{diff::line_ranges, queries::read_file};
+
+/// walks back from a revision, passing each line on to the parent it was inherited from until it reaches the revision which added it
+pub fn query_blame(ws: &WorkspaceSession, id: RevId, path: TreePath) -> Result<FileBlame> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+    let repo_path = RepoPath::from_internal_string(&path.repo_path);
+
+    let Some(content) = read_file(ws, &commit.tree()?, repo_path)? else {
+        return Err(anyhow!("{} is not a file", path.repo_path));
+    };
+    let lines: Vec<String> = line_ranges(&content)
+        .into_iter()
+        .map(|range| String::from_utf8_lossy(&content[range]).into_owned())
+        .collect();
+
+    // lines which are still unattributed, as (number in that commit's version, number in the blamed version)
+    let mut pending: HashMap<CommitId, Vec<(usize, usize)>> = HashMap::new();
+    pending.insert(
+        commit.id().clone(),
+        (0..lines.len()).map(|line| (line, line)).collect(),
+    );
+    let mut contents: HashMap<CommitId, Vec<u8>> = HashMap::new();
+    contents.insert(commit.id().clone(), content);
+    let mut owners: Vec<Option<CommitId>> = vec![None; lines.len()];
+
+    // ancestors are evaluated children-first, so each commit has received all its lines before it's visited
+    let ancestors = RevsetExpression::commit(commit.id().clone()).ancestors();
+    for ancestor_id in ws.evaluate_revset_expr(ancestors)?.iter() {
+        if pending.is_empty() {
+            break;
+        }
+        let Some(mut remaining) = pending.remove(&ancestor_id) else {
+            continue;
+        };
+        let content = contents.remove(&ancestor_id).unwrap_or_default();
+
+        // in a merge, lines found in more than one parent are inherited from the first
+        for parent in ws.get_commit(&ancestor_id)?.parents() {
+            if remaining.is_empty() {
+                break;
+            }
+            if !contents.contains_key(parent.id()) {
+                let Some(parent_content) = read_file(ws, &parent.tree()?, repo_path)? else {
+                    continue;
+                };
+                contents.insert(parent.id().clone(), parent_content);
+            }
+
+            let parent_lines = map_lines(&contents[parent.id()], &content);
+            let (inherited, kept): (Vec<_>, Vec<_>) = remaining
+                .into_iter()
+                .partition(|(line, _)| parent_lines.get(*line).is_some_and(Option::is_some));
+            if !inherited.is_empty() {
+                pending.entry(parent.id().clone()).or_default().extend(
+                    inherited
+                        .into_iter()
+                        .filter_map(|(line, original)| Some((parent_lines[line]?, original))),
+                );
+            }
+            remaining = kept;
+        }
+
+        for (_, original) in remaining {
+            owners[original] = Some(ancestor_id.clone());
+        }
+    }
+
+    // consecutive lines from the same revision share a range
+    let mut ranges: Vec<BlameRange> = Vec::new();
+    let mut last_owner: Option<CommitId> = None;
+    for (line, owner) in owners.into_iter().enumerate() {
+        let owner = owner.unwrap_or_else(|| commit.id().clone());
+        if let (Some(range), true) = (ranges.last_mut(), last_owner.as_ref() == Some(&owner)) {
+            range.last_line = line + 1;
+            continue;
+        }
+
+        let owner_commit = ws.get_commit(&owner)?;
+        ranges.push(BlameRange {
+            first_line: line + 1,
+            last_line: line + 1,
+            id: ws.format_id(&owner_commit),
+            author: RevAuthor::new(owner_commit.author(), &ws.date_display),
+            summary: owner_commit
+                .description()
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_owned(),
+        });
+        last_owner = Some(owner);
+    }
+
+    Ok(FileBlame {
+        path: ws.format_path(repo_path),
+        lines,
+        ranges,
+    })
+}
+
+// for each line of the child, the number of the parent line it's unchanged from, if any
+fn map_lines(parent: &[u8], child: &[u8]) -> Vec<Option<usize>> {
+    let mut mapping = Vec::new();
+    let mut parent_line = 0;
+    for hunk in Diff::by_line(&[parent, child]).hunks() {
+        match hunk {
+            DiffHunk::Matching(text) => {
+                for _ in line_ranges(text) {
+                    mapping.push(Some(parent_line));
+                    parent_line += 1;
+                }
+            }
+            DiffHunk::Different(sides) => {
+                parent_line += line_ranges(sides[0]).len();
+                mapping.extend(line_ranges(sides[1]).iter().map(|_| None));
+            }
+        }
+    }
+    mapping
+}
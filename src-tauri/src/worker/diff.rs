@@ -0,0 +1,460 @@
+//! Line diffs of a revision's files, delivered in pages so that huge files don't have to be sent at once
+
+use std::{io::Read, ops::Range};
+
+use anyhow::{anyhow, Result};
+use jj_lib::{
+    backend::TreeValue,
+    diff::{self, Diff},
+    matchers::{EverythingMatcher, FilesMatcher, Matcher},
+    merge::MergedTreeValue,
+    repo_path::RepoPath,
+    rewrite,
+};
+
+use crate::{
+    gui_util::WorkspaceSession,
+    messages::{
+        ChangeKind, DiffCursor, DiffHunk, DiffLine, DiffLineKind, DiffPage, DiffRow, DiffStyle,
+        FileDiff, FileKind, RevId, TextRange, TreePath,
+    },
+};
+
+/// lines per page when the frontend doesn't ask for a size
+pub const DEFAULT_PAGE_SIZE: usize = 2000;
+// unchanged lines shown around each change, like `diff -u`
+const CONTEXT_LINES: usize = 3;
+// git's heuristic: a NUL within the first 8000 bytes makes a file binary
+const SNIFF_SIZE: usize = 8000;
+// larger images are identified, but their content isn't sent
+const MAX_PREVIEW_SIZE: usize = 256 * 1024;
+
+/// the changes in a revision, or in one of its files; each file without hunks counts as a line
+pub fn query_diff(
+    ws: &WorkspaceSession,
+    id: RevId,
+    path: Option<TreePath>,
+    style: DiffStyle,
+    after: Option<DiffCursor>,
+    page_size: usize,
+) -> Result<DiffPage> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+    let parent_tree = rewrite::merge_commit_trees(ws.repo(), &commit.parents())?;
+    let tree = commit.tree()?;
+
+    let matcher: Box<dyn Matcher> = match &path {
+        Some(path) => Box::new(FilesMatcher::new([RepoPath::from_internal_string(
+            &path.repo_path,
+        )])),
+        None => Box::new(EverythingMatcher),
+    };
+
+    let mut files = Vec::new();
+    let mut remaining = page_size.max(1);
+    let mut resume_at = after;
+    for (repo_path, values) in parent_tree.diff(&tree, matcher.as_ref()) {
+        let path_string = repo_path.as_internal_file_string().to_owned();
+
+        // files before the cursor were on earlier pages
+        let mut skip_lines = 0;
+        if let Some(cursor) = &resume_at {
+            if cursor.repo_path != path_string {
+                continue;
+            }
+            skip_lines = cursor.line;
+            resume_at = None;
+        }
+
+        if remaining == 0 {
+            return Ok(DiffPage {
+                files,
+                next: Some(DiffCursor {
+                    repo_path: path_string,
+                    line: skip_lines,
+                }),
+            });
+        }
+
+        let (before, after) = values?;
+        let kind = if before.is_present() && after.is_present() {
+            ChangeKind::Modified
+        } else if before.is_absent() {
+            ChangeKind::Added
+        } else {
+            ChangeKind::Deleted
+        };
+
+        let before_content = read_content(ws, &repo_path, &before)?;
+        let after_content = read_content(ws, &repo_path, &after)?;
+
+        let present_content = if after.is_present() {
+            &after_content
+        } else {
+            &before_content
+        };
+        let mut file_kind = match present_content {
+            Some(content) => detect_kind(&repo_path, content),
+            None => FileKind::Text,
+        };
+        if let FileKind::Image {
+            before: before_preview,
+            after: after_preview,
+            ..
+        } = &mut file_kind
+        {
+            *before_preview = preview(before_content.as_deref());
+            *after_preview = preview(after_content.as_deref());
+        }
+
+        let contents = match (before_content, after_content) {
+            (Some(before), Some(after)) if !before.contains(&0) && !after.contains(&0) => {
+                Some((before, after))
+            }
+            _ => None,
+        };
+
+        let Some((before_content, after_content)) = contents else {
+            files.push(FileDiff {
+                path: ws.format_path(repo_path),
+                kind,
+                file_kind,
+                has_lines: false,
+                continued: false,
+                hunks: Vec::new(),
+            });
+            remaining -= 1;
+            continue;
+        };
+
+        // hunks are sent whole where possible, but a page can end partway through one
+        let mut hunks = Vec::new();
+        let mut next = None;
+        let mut offset = 0;
+        for hunk in diff_hunks(&before_content, &after_content) {
+            let hunk_len = hunk.len();
+            if offset + hunk_len <= skip_lines {
+                offset += hunk_len;
+                continue;
+            }
+
+            let start = skip_lines.saturating_sub(offset);
+            if remaining == 0 {
+                next = Some(offset + start);
+                break;
+            }
+
+            let end = hunk_len.min(start + remaining);
+            remaining -= end - start;
+            hunks.push(format_hunk(hunk[start..end].to_vec(), style));
+            if end < hunk_len {
+                next = Some(offset + end);
+                break;
+            }
+            offset += hunk_len;
+        }
+
+        files.push(FileDiff {
+            path: ws.format_path(repo_path),
+            kind,
+            file_kind,
+            has_lines: true,
+            continued: skip_lines > 0,
+            hunks,
+        });
+
+        if let Some(line) = next {
+            return Ok(DiffPage {
+                files,
+                next: Some(DiffCursor {
+                    repo_path: path_string,
+                    line,
+                }),
+            });
+        }
+    }
+
+    Ok(DiffPage { files, next: None })
+}
+
+/// absent files are empty; conflicts and non-files have no content to show
+pub fn read_content(
+    ws: &WorkspaceSession,
+    repo_path: &RepoPath,
+    value: &MergedTreeValue,
+) -> Result<Option<Vec<u8>>> {
+    match value.as_resolved() {
+        Some(None) => Ok(Some(Vec::new())),
+        Some(Some(TreeValue::File { id, .. })) => {
+            let mut content = Vec::new();
+            ws.repo()
+                .store()
+                .read_file(repo_path, id)?
+                .read_to_end(&mut content)?;
+            Ok(Some(content))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// classifies one side of a change by the start of its content; conflicts and symlinks count as text
+pub fn sniff_kind(
+    ws: &WorkspaceSession,
+    repo_path: &RepoPath,
+    value: &MergedTreeValue,
+) -> Result<FileKind> {
+    let Some(Some(TreeValue::File { id, .. })) = value.as_resolved() else {
+        return Ok(FileKind::Text);
+    };
+
+    let mut prefix = Vec::new();
+    ws.repo()
+        .store()
+        .read_file(repo_path, id)?
+        .take(SNIFF_SIZE as u64)
+        .read_to_end(&mut prefix)?;
+    Ok(detect_kind(repo_path, &prefix))
+}
+
+// images are recognised by their signatures, except for svg, which is text and has to go by name
+pub fn detect_kind(repo_path: &RepoPath, content: &[u8]) -> FileKind {
+    let mime = if content.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if content.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if content.starts_with(b"GIF87a") || content.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if content.starts_with(&[0, 0, 1, 0]) {
+        Some("image/x-icon")
+    } else if repo_path
+        .as_internal_file_string()
+        .to_lowercase()
+        .ends_with(".svg")
+    {
+        Some("image/svg+xml")
+    } else {
+        None
+    };
+
+    match mime {
+        Some(mime) => FileKind::Image {
+            mime: mime.to_owned(),
+            before: None,
+            after: None,
+        },
+        None if content[..content.len().min(SNIFF_SIZE)].contains(&0) => FileKind::Binary,
+        None => FileKind::Text,
+    }
+}
+
+// absent sides read as empty, and have nothing to preview either
+pub fn preview(content: Option<&[u8]>) -> Option<String> {
+    content
+        .filter(|content| !content.is_empty() && content.len() <= MAX_PREVIEW_SIZE)
+        .map(encode_base64)
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let buffer = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |buffer, (index, byte)| {
+                buffer | (*byte as u32) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                text.push(ALPHABET[(buffer >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+// numbered lines, grouped into hunks of changes with the unchanged lines around them
+fn diff_hunks(before: &[u8], after: &[u8]) -> Vec<Vec<DiffLine>> {
+    let mut lines = Vec::new();
+    let mut old_number = 1;
+    let mut new_number = 1;
+    for hunk in Diff::by_line(&[before, after]).hunks() {
+        match hunk {
+            diff::DiffHunk::Matching(text) => {
+                for range in line_ranges(text) {
+                    lines.push(DiffLine {
+                        kind: DiffLineKind::Context,
+                        old_number: Some(old_number),
+                        new_number: Some(new_number),
+                        text: String::from_utf8_lossy(&text[range]).into_owned(),
+                        highlights: Vec::new(),
+                    });
+                    old_number += 1;
+                    new_number += 1;
+                }
+            }
+            diff::DiffHunk::Different(sides) => {
+                let [old_changes, new_changes] = changed_ranges(sides[0], sides[1]);
+                for range in line_ranges(sides[0]) {
+                    lines.push(DiffLine {
+                        kind: DiffLineKind::Removed,
+                        old_number: Some(old_number),
+                        new_number: None,
+                        text: String::from_utf8_lossy(&sides[0][range.clone()]).into_owned(),
+                        highlights: line_highlights(sides[0], &range, &old_changes),
+                    });
+                    old_number += 1;
+                }
+                for range in line_ranges(sides[1]) {
+                    lines.push(DiffLine {
+                        kind: DiffLineKind::Added,
+                        old_number: None,
+                        new_number: Some(new_number),
+                        text: String::from_utf8_lossy(&sides[1][range.clone()]).into_owned(),
+                        highlights: line_highlights(sides[1], &range, &new_changes),
+                    });
+                    new_number += 1;
+                }
+            }
+        }
+    }
+
+    // hunks whose context would overlap are merged
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if line.kind == DiffLineKind::Context {
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = lines.len().min(index + CONTEXT_LINES + 1);
+        match ranges.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| lines[start..end].to_vec())
+        .collect()
+}
+
+// pairs removed lines with the added lines after them, so that each row shows both versions
+fn side_by_side(lines: Vec<DiffLine>) -> Vec<DiffRow> {
+    let mut rows = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    for line in lines {
+        match line.kind {
+            DiffLineKind::Removed => removed.push(line),
+            DiffLineKind::Added => added.push(line),
+            DiffLineKind::Context => {
+                pair_changes(&mut rows, &mut removed, &mut added);
+                rows.push(DiffRow {
+                    left: Some(line.clone()),
+                    right: Some(line),
+                });
+            }
+        }
+    }
+    pair_changes(&mut rows, &mut removed, &mut added);
+
+    rows
+}
+
+fn format_hunk(lines: Vec<DiffLine>, style: DiffStyle) -> DiffHunk {
+    match style {
+        DiffStyle::Unified => DiffHunk::Unified { lines },
+        DiffStyle::SideBySide => DiffHunk::SideBySide {
+            rows: side_by_side(lines),
+        },
+    }
+}
+
+fn pair_changes(rows: &mut Vec<DiffRow>, removed: &mut Vec<DiffLine>, added: &mut Vec<DiffLine>) {
+    let mut left = removed.drain(..);
+    let mut right = added.drain(..);
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => break,
+            (left, right) => rows.push(DiffRow { left, right }),
+        }
+    }
+}
+
+// line terminators aren't part of the text; a missing final newline isn't distinguished
+pub fn line_ranges(text: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let end = text[start..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map_or(text.len(), |length| start + length);
+        let content_end = if end > start && text[end - 1] == b'\r' {
+            end - 1
+        } else {
+            end
+        };
+        ranges.push(start..content_end);
+        start = end + 1;
+    }
+    ranges
+}
+
+// words which differ between removed lines and the lines which replaced them, as byte ranges of each;
+// wholly added or removed lines aren't highlighted, since every word would be
+fn changed_ranges(old: &[u8], new: &[u8]) -> [Vec<Range<usize>>; 2] {
+    let mut ranges = [Vec::new(), Vec::new()];
+    if old.is_empty() || new.is_empty() {
+        return ranges;
+    }
+
+    let mut positions = [0, 0];
+    for hunk in Diff::default_refinement(&[old, new]).hunks() {
+        match hunk {
+            diff::DiffHunk::Matching(text) => {
+                for position in &mut positions {
+                    *position += text.len();
+                }
+            }
+            diff::DiffHunk::Different(sides) => {
+                for (side, text) in sides.iter().enumerate() {
+                    if !text.is_empty() {
+                        ranges[side].push(positions[side]..positions[side] + text.len());
+                    }
+                    positions[side] += text.len();
+                }
+            }
+        }
+    }
+
+    ranges
+}
+
+// the parts of a line which fall within changed ranges, relative to the start of the line
+fn line_highlights(text: &[u8], line: &Range<usize>, changes: &[Range<usize>]) -> Vec<TextRange> {
+    let utf16_offset = |byte: usize| {
+        String::from_utf8_lossy(&text[line.start..byte])
+            .encode_utf16()
+            .count()
+    };
+
+    changes
+        .iter()
+        .filter_map(|change| {
+            let start = change.start.max(line.start);
+            let end = change.end.min(line.end);
+            (start < end).then(|| TextRange {
+                start: utf16_offset(start),
+                end: utf16_offset(end),
+            })
+        })
+        .collect()
+}
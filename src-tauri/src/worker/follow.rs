@@ -0,0 +1,172 @@
+//! History of a single file, following it back across renames and copies
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use jj_lib::{
+    backend::{CommitId, TreeValue},
+    commit::Commit,
+    matchers::EverythingMatcher,
+    merged_tree::{MergedTree, MergedTreeValue},
+    object_id::ObjectId,
+    repo_path::{RepoPath, RepoPathBuf},
+    revset::RevsetExpression,
+};
+
+use crate::{
+    gui_util::WorkspaceSession,
+    messages::{self, LogCoordinates, LogLine, LogPage, LogRow, RevId, TreePath},
+};
+
+/// lists the ancestors of a revision which changed a file, newest first, as a single-column log;
+/// pages after the first resume from the last row already sent
+pub fn query_file_history(
+    ws: &WorkspaceSession,
+    id: RevId,
+    path: TreePath,
+    after: Option<messages::CommitId>,
+    page_size: usize,
+) -> Result<LogPage> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+
+    // the path the file had in each commit still to be visited, which differs between lines of history after a rename
+    let mut followed: HashMap<CommitId, RepoPathBuf> = HashMap::new();
+    followed.insert(
+        commit.id().clone(),
+        RepoPathBuf::from_internal_string(&path.repo_path),
+    );
+
+    let mut rows: Vec<LogRow> = Vec::new();
+    let mut row = 0;
+    let mut previous: Option<Commit> = None;
+    let mut skipping = after.is_some();
+    let mut has_more = false;
+
+    // ancestors are evaluated children-first, so each commit has been given its path before it's visited
+    let ancestors = RevsetExpression::commit(commit.id().clone()).ancestors();
+    for ancestor_id in ws.evaluate_revset_expr(ancestors)?.iter() {
+        if followed.is_empty() {
+            break;
+        }
+        let Some(repo_path) = followed.remove(&ancestor_id) else {
+            continue;
+        };
+
+        let ancestor = ws.get_commit(&ancestor_id)?;
+        if !follow_parents(&ancestor, &repo_path, &mut followed)? {
+            continue;
+        }
+
+        // rows on earlier pages are walked again, so that the graph lines and coordinates continue from them
+        if skipping {
+            skipping = after
+                .as_ref()
+                .is_some_and(|after| after.hex != ancestor_id.hex());
+            previous = Some(ancestor);
+            row += 1;
+            continue;
+        }
+
+        if rows.len() == page_size {
+            has_more = true;
+            break;
+        }
+
+        let mut lines = Vec::new();
+        if let Some(previous) = &previous {
+            lines.push(LogLine::ToNode {
+                source: LogCoordinates(0, row - 1),
+                target: LogCoordinates(0, row),
+                indirect: !previous.parent_ids().contains(&ancestor_id),
+            });
+        }
+
+        rows.push(LogRow {
+            revision: ws.format_header(&ancestor, None)?,
+            location: LogCoordinates(0, row),
+            padding: 0,
+            lines,
+        });
+
+        previous = Some(ancestor);
+        row += 1;
+    }
+
+    Ok(LogPage {
+        rows,
+        has_more,
+        total: None,
+        warnings: ws.take_warnings(),
+    })
+}
+
+/// passes the file's path on to each parent which had it, under its old name if it was renamed or copied,
+/// and returns whether the commit changed the file
+fn follow_parents(
+    commit: &Commit,
+    repo_path: &RepoPath,
+    followed: &mut HashMap<CommitId, RepoPathBuf>,
+) -> Result<bool> {
+    let tree = commit.tree()?;
+    let value = tree.path_value(repo_path);
+
+    let parents = commit.parents();
+    if parents.is_empty() {
+        return Ok(value.is_present());
+    }
+
+    // like git, a merge only counts as changing the file if it differs from every parent
+    let mut is_changed = true;
+    for parent in parents {
+        let parent_tree = parent.tree()?;
+        let parent_value = parent_tree.path_value(repo_path);
+        if parent_value == value {
+            is_changed = false;
+        }
+
+        let parent_path = if parent_value.is_present() {
+            Some(repo_path.to_owned())
+        } else {
+            find_source(&parent_tree, &tree, &value)?
+        };
+        if let Some(parent_path) = parent_path {
+            followed.entry(parent.id().clone()).or_insert(parent_path);
+        }
+    }
+
+    Ok(is_changed)
+}
+
+// without copy tracking, only files whose content was unchanged by the rename or copy can be found
+fn find_source(
+    parent_tree: &MergedTree,
+    tree: &MergedTree,
+    value: &MergedTreeValue,
+) -> Result<Option<RepoPathBuf>> {
+    let Some(Some(TreeValue::File { id, .. })) = value.as_resolved() else {
+        return Ok(None);
+    };
+    let is_same_file = |value: &MergedTreeValue| match value.as_resolved() {
+        Some(Some(TreeValue::File { id: source_id, .. })) => source_id == id,
+        _ => false,
+    };
+
+    // renames remove the old path, which is much cheaper to look for than an unchanged file to copy from
+    for (source_path, diff) in parent_tree.diff(tree, &EverythingMatcher) {
+        let (before, after) = diff?;
+        if after.is_absent() && is_same_file(&before) {
+            return Ok(Some(source_path));
+        }
+    }
+
+    for (source_path, source_value) in parent_tree.entries() {
+        if is_same_file(&source_value) {
+            return Ok(Some(source_path));
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,114 @@
+//! Export of the operation log, for teams that need an audit trail of history rewrites
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Utc};
+use itertools::Itertools;
+use jj_lib::{object_id::ObjectId, op_walk, operation::Operation};
+use serde::Serialize;
+
+use crate::{
+    gui_util::{WorkspaceSession, ACTION_TAG},
+    messages::{datetime_from_timestamp, ExportOperationHistory, HistoryFormat},
+};
+
+const CSV_HEADER: &str = "id,parents,start_time,end_time,username,hostname,action,description";
+
+#[derive(Serialize)]
+struct OperationRecord {
+    id: String,
+    parents: Vec<String>,
+    start_time: Option<DateTime<FixedOffset>>,
+    end_time: Option<DateTime<FixedOffset>>,
+    username: String,
+    hostname: String,
+    /// the gg command which produced this operation, if it was made by gg
+    action: Option<String>,
+    description: String,
+}
+
+/// writes matching operations, newest first, and returns how many there were
+pub fn export(ws: &WorkspaceSession, options: ExportOperationHistory) -> Result<usize> {
+    let mut records = Vec::new();
+
+    for op in op_walk::walk_ancestors(&[ws.repo().operation().clone()]) {
+        let record = format_operation(&op?);
+
+        if let Some(username) = &options.username {
+            if &record.username != username {
+                continue;
+            }
+        }
+        if let Some(start_time) = record.start_time.map(|time| time.with_timezone(&Utc)) {
+            if options.since.is_some_and(|since| start_time < since)
+                || options.until.is_some_and(|until| start_time > until)
+            {
+                continue;
+            }
+        }
+
+        records.push(record);
+    }
+
+    let content = match options.format {
+        HistoryFormat::Json => serde_json::to_string_pretty(&records)?,
+        HistoryFormat::Csv => format_csv(&records),
+    };
+
+    let path = Path::new(&options.path);
+    fs::write(path, content).with_context(|| format!("write {}", path.display()))?;
+
+    Ok(records.len())
+}
+
+fn format_operation(op: &Operation) -> OperationRecord {
+    let metadata = op.metadata();
+    OperationRecord {
+        id: op.id().hex(),
+        parents: op.parent_ids().iter().map(|id| id.hex()).collect(),
+        start_time: datetime_from_timestamp(&metadata.start_time),
+        end_time: datetime_from_timestamp(&metadata.end_time),
+        username: metadata.username.clone(),
+        hostname: metadata.hostname.clone(),
+        action: metadata.tags.get(ACTION_TAG).cloned(),
+        description: metadata.description.clone(),
+    }
+}
+
+fn format_csv(records: &[OperationRecord]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push_str("\r\n");
+
+    for record in records {
+        let fields = [
+            record.id.clone(),
+            record.parents.join(" "),
+            record
+                .start_time
+                .map(|time| time.to_rfc3339())
+                .unwrap_or_default(),
+            record
+                .end_time
+                .map(|time| time.to_rfc3339())
+                .unwrap_or_default(),
+            record.username.clone(),
+            record.hostname.clone(),
+            record.action.clone().unwrap_or_default(),
+            record.description.clone(),
+        ];
+        csv.push_str(&fields.iter().map(|field| quote_csv(field)).join(","));
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+// RFC 4180: fields containing separators, quotes or line breaks are quoted, with quotes doubled
+fn quote_csv(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
@@ -0,0 +1,158 @@
+//! Git hooks, for colocated repos whose policy relies on checks made by `git commit` and `git push`
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use git2::Repository;
+use thiserror::Error;
+
+use crate::{config::GGSettings, gui_util::WorkspaceSession};
+
+// linters and test suites can be slow, but they block the worker
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// a hook exited unsuccessfully, which cancels the command that ran it
+#[derive(Debug, Error)]
+#[error("{hook} hook failed: {output}")]
+pub struct HookFailedError {
+    pub hook: String,
+    pub output: String,
+}
+
+/// runs pre-commit and commit-msg, returning the description as commit-msg left it
+pub fn run_commit_hooks(
+    ws: &mut WorkspaceSession,
+    git_repo: &Repository,
+    description: String,
+) -> Result<String> {
+    if !hooks_enabled(ws) {
+        return Ok(description);
+    }
+
+    run_hook(ws, git_repo, "pre-commit", vec![], vec![])?;
+
+    // commit-msg edits the message in place, so it gets the file git would use
+    let message_path = git_repo.path().join("COMMIT_EDITMSG");
+    fs::write(&message_path, &description)
+        .with_context(|| format!("write {}", message_path.display()))?;
+    run_hook(
+        ws,
+        git_repo,
+        "commit-msg",
+        vec![message_path.to_string_lossy().into_owned()],
+        vec![],
+    )?;
+    let description = fs::read_to_string(&message_path)
+        .with_context(|| format!("read {}", message_path.display()))?;
+
+    Ok(description)
+}
+
+/// runs pre-push; `updates` are (local ref, local sha, remote ref, remote sha), with zeros for absent commits
+pub fn run_push_hook(
+    ws: &mut WorkspaceSession,
+    git_repo: &Repository,
+    remote_name: &str,
+    updates: &[(String, String, String, String)],
+) -> Result<()> {
+    if !hooks_enabled(ws) {
+        return Ok(());
+    }
+
+    let url = git_repo
+        .find_remote(remote_name)
+        .ok()
+        .and_then(|remote| remote.url().map(str::to_owned))
+        .unwrap_or_else(|| remote_name.to_owned());
+
+    let mut input = String::new();
+    for (local_ref, local_sha, remote_ref, remote_sha) in updates {
+        input.push_str(&format!(
+            "{local_ref} {local_sha} {remote_ref} {remote_sha}\n"
+        ));
+    }
+
+    run_hook(
+        ws,
+        git_repo,
+        "pre-push",
+        vec![remote_name.to_owned(), url],
+        input.into_bytes(),
+    )
+}
+
+// hooks are in git's directory, so they only make sense when git commands are also used
+fn hooks_enabled(ws: &WorkspaceSession) -> bool {
+    ws.settings.git_run_hooks() && ws.is_colocated()
+}
+
+// a missing hook passes, like in git
+fn run_hook(
+    ws: &mut WorkspaceSession,
+    git_repo: &Repository,
+    name: &str,
+    args: Vec<String>,
+    input: Vec<u8>,
+) -> Result<()> {
+    let Some(hook_path) = find_hook(ws, git_repo, name)? else {
+        return Ok(());
+    };
+
+    // windows can't execute scripts directly, but git for windows provides a shell
+    let (program, args) = if cfg!(windows) {
+        let script = hook_path.to_string_lossy().into_owned();
+        (
+            PathBuf::from("sh"),
+            [script].into_iter().chain(args).collect(),
+        )
+    } else {
+        (hook_path, args)
+    };
+
+    let timeout = ws.settings.tool_timeout().unwrap_or(DEFAULT_TIMEOUT);
+    let result = ws.run_tool(name, &program, args, input, timeout);
+
+    // hooks which stage files, like lint-staged, touch the index
+    ws.refresh_git_fingerprint()?;
+
+    match result {
+        Ok(output) => {
+            if !output.trim().is_empty() {
+                log::info!("{name}: {}", output.trim());
+            }
+            Ok(())
+        }
+        Err(err) => Err(HookFailedError {
+            hook: name.to_owned(),
+            output: format!("{err:#}"),
+        }
+        .into()),
+    }
+}
+
+// core.hooksPath is relative to the working tree, and git ignores hooks which aren't executable
+fn find_hook(ws: &WorkspaceSession, git_repo: &Repository, name: &str) -> Result<Option<PathBuf>> {
+    let hooks_dir = match git_repo.config()?.get_path("core.hooksPath") {
+        Ok(path) => ws.workspace_root().join(path),
+        Err(_) => git_repo.path().join("hooks"),
+    };
+
+    let hook_path = hooks_dir.join(name);
+    Ok(is_executable(&hook_path).then_some(hook_path))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -0,0 +1,116 @@
+//! Lock files left behind by crashed jj or git processes, which otherwise make every mutation fail
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use jj_lib::lock::FileLockError;
+
+use crate::{
+    gui_util::WorkspaceSession,
+    messages::{LockHolder, RepoLock},
+};
+
+// a lock is presumed abandoned once it's older than any reasonable command; finding no holder isn't enough,
+// since other users' and containers' processes can't be inspected
+const STALE_AGE: Duration = Duration::from_secs(10 * 60);
+
+// git's own files which it locks by creating `<name>.lock` beside them
+const GIT_LOCKED_FILES: [&str; 4] = ["index", "HEAD", "config", "packed-refs"];
+
+/// git reports locks with an error code, and jj with its own error type
+pub fn is_lock_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.is::<FileLockError>()
+            || cause
+                .downcast_ref::<git2::Error>()
+                .is_some_and(|err| err.code() == git2::ErrorCode::Locked)
+    })
+}
+
+/// lock files which currently exist in the workspace, the repo and its git backend
+pub fn find_locks(ws: &WorkspaceSession) -> Result<Vec<RepoLock>> {
+    let mut candidates = vec![
+        ws.workspace_root()
+            .join(".jj")
+            .join("working_copy")
+            .join("working_copy.lock"),
+        ws.repo_dir().join("op_heads").join("lock"),
+    ];
+
+    if let Some(git_repo) = ws.git_repo()? {
+        let git_dir = git_repo.path();
+        for name in GIT_LOCKED_FILES {
+            candidates.push(git_dir.join(format!("{name}.lock")));
+        }
+        find_ref_locks(&git_dir.join("refs"), &mut candidates);
+    }
+
+    Ok(candidates
+        .into_iter()
+        .filter_map(|path| describe_lock(&path))
+        .collect())
+}
+
+fn find_ref_locks(dir: &Path, locks: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            find_ref_locks(&path, locks);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "lock")
+        {
+            locks.push(path);
+        }
+    }
+}
+
+fn describe_lock(path: &Path) -> Option<RepoLock> {
+    let metadata = fs::metadata(path).ok()?;
+    let age = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .unwrap_or_default();
+    let holder = find_holder(path);
+
+    let is_stale = holder.is_none() && age >= STALE_AGE;
+
+    Some(RepoLock {
+        path: path.to_string_lossy().into_owned(),
+        holder,
+        age_seconds: age.as_secs(),
+        is_stale,
+    })
+}
+
+// git and jj keep their lock files open while they hold them, which procfs can reveal
+#[cfg(target_os = "linux")]
+fn find_holder(path: &Path) -> Option<LockHolder> {
+    let path = fs::canonicalize(path).ok()?;
+    fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+        let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+        let mut fds = fs::read_dir(entry.path().join("fd")).ok()?.flatten();
+        if !fds.any(|fd| fs::read_link(fd.path()).is_ok_and(|target| target == path)) {
+            return None;
+        }
+
+        let name = fs::read_to_string(entry.path().join("comm"))
+            .ok()
+            .map(|name| name.trim().to_owned());
+        Some(LockHolder { pid, name })
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn find_holder(_path: &Path) -> Option<LockHolder> {
+    None
+}
@@ -0,0 +1,72 @@
+//! Identifies the commits referred to by text pasted from elsewhere: ids, refs or review URLs
+
+use anyhow::Result;
+use jj_lib::{
+    commit::Commit,
+    revset::{RevsetExpression, RevsetResolutionError},
+    view::View,
+};
+use pollster::FutureExt;
+
+use crate::{
+    forge,
+    gui_util::{RevsetError, WorkspaceSession},
+    messages::{LookupSource, TextLookup},
+};
+
+use super::queries;
+
+pub fn lookup_text(ws: &WorkspaceSession, text: &str) -> Result<TextLookup> {
+    let text = text.trim();
+
+    // reviews are found by their head commit, which may only have been fetched as a branch
+    if let Some(review) = forge::parse_review_url(text) {
+        let head = forge::query_review_head(&review).block_on()?;
+        let mut commits = resolve_symbol(ws, &head.commit)?;
+        if commits.is_empty() {
+            if let Some(branch) = &head.branch {
+                commits = resolve_symbol(ws, branch)?;
+            }
+        }
+        return format_lookup(ws, LookupSource::Review, &commits);
+    }
+
+    let commits = resolve_symbol(ws, text)?;
+    let source = if is_ref(ws.view(), text) {
+        LookupSource::Ref
+    } else {
+        LookupSource::Id
+    };
+    format_lookup(ws, source, &commits)
+}
+
+// the same resolution as revsets, except that ambiguous prefixes yield their candidates
+fn resolve_symbol(ws: &WorkspaceSession, symbol: &str) -> Result<Vec<Commit>> {
+    match ws.evaluate_revset_expr(RevsetExpression::symbol(symbol.to_owned())) {
+        Ok(revset) => Ok(ws.resolve_multiple(revset)?),
+        Err(RevsetError::Resolution(
+            RevsetResolutionError::AmbiguousCommitIdPrefix(_)
+            | RevsetResolutionError::AmbiguousChangeIdPrefix(_),
+        )) => queries::find_candidates(ws, &symbol.to_lowercase()),
+        Err(RevsetError::Resolution(_)) => Ok(Vec::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn is_ref(view: &View, name: &str) -> bool {
+    match name.rsplit_once('@') {
+        Some((branch, remote)) => view.get_remote_branch(branch, remote).target.is_present(),
+        None => view.get_local_branch(name).is_present() || view.get_tag(name).is_present(),
+    }
+}
+
+fn format_lookup(
+    ws: &WorkspaceSession,
+    source: LookupSource,
+    commits: &[Commit],
+) -> Result<TextLookup> {
+    Ok(TextLookup {
+        source: (!commits.is_empty()).then_some(source),
+        commits: queries::format_candidates(ws, commits)?,
+    })
+}
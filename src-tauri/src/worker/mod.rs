@@ -2,24 +2,50 @@
 //! The worker thread is a state machine, running different handle functions based on loaded data
 
 use std::{
+    collections::HashMap,
     fmt::Debug,
     panic::{catch_unwind, AssertUnwindSafe},
     path::PathBuf,
     sync::mpsc::{Receiver, Sender},
+    time::Instant,
 };
 
 use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
 
-use crate::messages::{self, RevId};
+use crate::messages::{self, RevId, TreePath};
 use crate::{
-    gui_util::{WorkerSession, WorkspaceSession},
+    crash::{self, CrashReport},
+    gui_util::{GitChangedError, WorkerSession, WorkspaceSession},
     messages::LogPage,
 };
 
-use self::queries::LogQueryState;
+use self::{hooks::HookFailedError, profile::QueryProfile, queries::LogQueryState};
 
+pub mod autosave;
+pub mod blame;
+mod diff;
+pub mod follow;
+pub mod history;
+pub mod hooks;
+pub mod locks;
+mod lookup;
 pub mod mutations;
+pub mod owners;
+mod palette;
+mod patches;
+mod profile;
 pub mod queries;
+mod search;
+pub mod secrets;
+pub mod suggest;
+pub mod tools;
+mod trailers;
+mod tree;
+pub mod watcher;
+
+/// the pane showing the main log, whose query is remembered when a workspace is reopened
+pub const DEFAULT_PANE: &str = "log";
 
 #[derive(Debug)]
 pub enum SessionEvent {
@@ -29,27 +55,220 @@ pub enum SessionEvent {
         tx: Sender<Result<messages::RepoConfig>>,
         wd: Option<PathBuf>,
     },
+    SwitchWorkspace {
+        tx: Sender<Result<messages::RepoConfig>>,
+        name: String,
+    },
     QueryLog {
         tx: Sender<Result<messages::LogPage>>,
+        pane: String,
         query: String,
+        filter: messages::LogFilter,
+        page_size: Option<usize>,
+        count: Option<messages::LogCount>,
     },
     QueryLogNextPage {
         tx: Sender<Result<messages::LogPage>>,
+        pane: String,
+    },
+    RefreshQuery {
+        tx: Sender<Result<messages::LogDelta>>,
+        pane: String,
+    },
+    /// matching rows of the pane's query are published as RepoEvent::SearchMatches
+    SearchLog {
+        tx: Sender<Result<messages::LogSearch>>,
+        pane: String,
+        text: String,
+        is_regex: bool,
+    },
+    /// discards a pane's query, after QueryCancellation has stopped any evaluation in progress
+    CancelQuery {
+        pane: String,
     },
     QueryRevision {
         tx: Sender<Result<messages::RevResult>>,
         id: RevId,
     },
+    QueryChildren {
+        tx: Sender<Result<Vec<messages::RevHeader>>>,
+        id: RevId,
+    },
+    QueryWorkspaces {
+        tx: Sender<Result<Vec<messages::WorkspaceInfo>>>,
+    },
+    QueryGitRemotes {
+        tx: Sender<Result<Vec<messages::GitRemote>>>,
+    },
+    QueryWorkspace {
+        tx: Sender<Result<messages::RevResult>>,
+        name: String,
+    },
+    QueryContext {
+        tx: Sender<Result<messages::RevContext>>,
+        id: RevId,
+    },
+    QueryTemplateContext {
+        tx: Sender<Result<messages::TemplateContext>>,
+        id: RevId,
+    },
+    QueryIsAncestor {
+        tx: Sender<Result<bool>>,
+        ancestor_id: messages::CommitId,
+        descendant_id: messages::CommitId,
+    },
+    ResolveIdPrefix {
+        tx: Sender<Result<messages::PrefixResolution>>,
+        prefix: String,
+    },
+    ResolveDragPayload {
+        tx: Sender<Result<messages::DragResolution>>,
+        pane: String,
+        op_id: String,
+        payloads: Vec<messages::DragPayload>,
+    },
+    LookupText {
+        tx: Sender<Result<messages::TextLookup>>,
+        text: String,
+    },
+    GetCommitUrl {
+        tx: Sender<Result<Option<String>>>,
+        target: messages::WebTarget,
+    },
+    QuerySlowQueries {
+        tx: Sender<Result<messages::SlowQueries>>,
+    },
+    QueryLargePaths {
+        tx: Sender<Result<messages::LargePaths>>,
+        id: RevId,
+        limit: usize,
+    },
+    QueryRevisionTree {
+        tx: Sender<Result<Vec<messages::TreeEntry>>>,
+        id: RevId,
+        dir: Option<TreePath>,
+    },
+    QueryFileContents {
+        tx: Sender<Result<messages::FileContents>>,
+        id: RevId,
+        path: TreePath,
+    },
+    QueryBlame {
+        tx: Sender<Result<messages::FileBlame>>,
+        id: RevId,
+        path: TreePath,
+    },
+    QueryFileHistory {
+        tx: Sender<Result<messages::LogPage>>,
+        id: RevId,
+        path: TreePath,
+        after: Option<messages::CommitId>,
+        page_size: Option<usize>,
+    },
+    QueryOwners {
+        tx: Sender<Result<messages::RevOwners>>,
+        id: RevId,
+    },
+    QueryRevisionDiff {
+        tx: Sender<Result<messages::DiffPage>>,
+        id: RevId,
+        style: messages::DiffStyle,
+        after: Option<messages::DiffCursor>,
+        page_size: Option<usize>,
+    },
+    QueryFileDiff {
+        tx: Sender<Result<messages::DiffPage>>,
+        id: RevId,
+        path: TreePath,
+        style: messages::DiffStyle,
+        after: Option<messages::DiffCursor>,
+        page_size: Option<usize>,
+    },
+    QueryConflict {
+        tx: Sender<Result<messages::ThreeWayMerge>>,
+        id: RevId,
+        path: TreePath,
+    },
+    QueryConflicts {
+        tx: Sender<Result<Vec<messages::FileConflict>>>,
+        id: RevId,
+    },
+    QueryMergeTools {
+        tx: Sender<Result<Vec<messages::MergeTool>>>,
+    },
+    QueryOperations {
+        tx: Sender<Result<messages::OperationPage>>,
+        after: Option<String>,
+        page_size: Option<usize>,
+    },
+    QueryCheckpoints {
+        tx: Sender<Result<Vec<messages::Checkpoint>>>,
+    },
+    QueryAnnotations {
+        tx: Sender<Result<Vec<messages::ChangeAnnotation>>>,
+    },
+    QueryReviewStates {
+        tx: Sender<Result<Vec<messages::ChangeReview>>>,
+    },
+    QueryNamedQueries {
+        tx: Sender<Result<Vec<messages::NamedQuery>>>,
+    },
+    ListAvailableCommands {
+        tx: Sender<Result<Vec<messages::AvailableCommand>>>,
+        ids: Vec<RevId>,
+    },
+    QueryChangeHistory {
+        tx: Sender<Result<messages::ChangeHistory>>,
+        id: RevId,
+        limit: Option<usize>,
+    },
+    QuerySuggestedDescription {
+        tx: Sender<Result<Option<String>>>,
+        id: RevId,
+    },
+    ExportOperationHistory {
+        tx: Sender<Result<usize>>,
+        options: messages::ExportOperationHistory,
+    },
+    LaunchDiffTool {
+        tx: Sender<Result<u32>>,
+        id: RevId,
+        path: TreePath,
+        tool: String,
+    },
+    RevealInEditor {
+        tx: Sender<Result<u32>>,
+        path: TreePath,
+        line: Option<usize>,
+    },
+    RevealCrashReport {
+        tx: Sender<Result<u32>>,
+        path: PathBuf,
+    },
+    SetBackgroundPolicy {
+        policy: messages::BackgroundPolicy,
+    },
+    /// a snapshot the user didn't explicitly ask for, which may be suspended by the background policy
     ExecuteSnapshot {
         tx: Sender<Option<messages::RepoStatus>>,
     },
+    /// files in the workspace were changed by another program; any resulting status is published as a repo event
+    FilesChanged,
+    /// another process may have written an operation; if so, the new status is published as a repo event
+    OperationsChanged,
+    /// the autosave interval has elapsed, so the working copy should be snapshotted and checkpointed
+    Autosave,
     ExecuteMutation {
         tx: Sender<messages::MutationResult>,
         mutation: Box<dyn Mutation + Send + Sync>,
     },
+    /// answers an InputRequest; read by the mutation which is waiting for it, not the event loop
+    ProvideCredentials {
+        response: messages::InputResponse,
+    },
 }
 
-pub trait Mutation: Debug {
+pub trait Mutation: Debug + MutationParams {
     fn describe(&self) -> String {
         std::any::type_name::<Self>().to_owned()
     }
@@ -64,6 +283,17 @@ pub trait Mutation: Debug {
     }
 }
 
+/// the arguments a mutation was sent with, which session recordings replay
+pub trait MutationParams {
+    fn params(&self) -> serde_json::Result<serde_json::Value>;
+}
+
+impl<T: Serialize> MutationParams for T {
+    fn params(&self) -> serde_json::Result<serde_json::Value> {
+        serde_json::to_value(self)
+    }
+}
+
 pub trait Session {
     type Transition;
     fn handle_events(self, rx: &Receiver<SessionEvent>) -> Result<Self::Transition>;
@@ -78,9 +308,18 @@ impl Session for WorkerSession {
         loop {
             let evt = rx.recv();
             log::debug!("WorkerSession handling {evt:?}");
+            if let Ok(evt) = &evt {
+                record(&self, evt);
+            }
             match evt {
                 Ok(SessionEvent::EndSession) => return Ok(()),
+                Ok(SessionEvent::SetBackgroundPolicy { policy }) => self.background_policy = policy,
                 Ok(SessionEvent::ExecuteSnapshot { .. }) => (),
+                Ok(SessionEvent::FilesChanged) => (),
+                Ok(SessionEvent::OperationsChanged) => (),
+                Ok(SessionEvent::Autosave) => (),
+                Ok(SessionEvent::CancelQuery { .. }) => (),
+                Ok(SessionEvent::ProvideCredentials { .. }) => (),
                 Ok(SessionEvent::OpenWorkspace { mut tx, mut wd }) => loop {
                     let resolved_wd = match wd.clone().or(latest_wd) {
                         Some(wd) => wd,
@@ -110,8 +349,16 @@ impl Session for WorkerSession {
                     };
 
                     latest_wd = Some(resolved_wd);
-
-                    ws.import_and_snapshot(false)?;
+                    ws.input = Some(rx);
+
+                    // only the session which executes mutations may write to the working copy
+                    if !ws.session.read_only {
+                        ws.session
+                            .publish_load_stage(messages::LoadStage::SnapshottingWorkingCopy);
+                        ws.import_and_snapshot(false)?;
+                        ws.start_watching();
+                        ws.start_autosave();
+                    }
 
                     tx.send(ws.format_config())?;
 
@@ -147,64 +394,85 @@ pub enum WorkspaceResult {
 #[derive(Default)]
 struct WorkspaceState {
     pub unhandled_event: Option<SessionEvent>,
-    pub unpaged_query: Option<LogQueryState>,
+    /// paused queries, keyed by the pane displaying them
+    pub unpaged_queries: HashMap<String, LogQueryState>,
+    pub profile: QueryProfile,
 }
 
 impl WorkspaceState {
     pub fn handle_query(
         &mut self,
         ws: &WorkspaceSession,
-        tx: Sender<Result<LogPage>>,
+        pane: &str,
+        respond: impl FnOnce(Result<LogPage>) -> Result<()>,
         rx: &Receiver<SessionEvent>,
-        revset_str: Option<&str>,
+        query: Option<&(String, messages::LogFilter)>,
         query_state: Option<LogQueryState>,
     ) -> Result<()> {
-        let query_state = match query_state.or_else(|| self.unpaged_query.take()) {
-            Some(x) => x,
-            None => {
-                tx.send(Err(anyhow!(
+        let query_state = match (query_state, self.unpaged_queries.remove(pane)) {
+            (Some(mut x), previous_query) => {
+                x.reuse_cache(previous_query);
+                x
+            }
+            (None, Some(x)) => x,
+            (None, None) => {
+                respond(Err(anyhow!(
                     "page requested without query in progress or new query"
                 )))?;
 
                 self.unhandled_event = None;
-                self.unpaged_query = None;
                 return Ok(());
             }
         };
 
-        let revset_str = match revset_str {
-            Some(x) => x,
+        let (revset_str, filter) = match query {
+            Some((revset_str, filter)) => (revset_str.as_str(), filter),
             None => {
-                tx.send(Err(anyhow!("page requested without query in progress")))?;
+                respond(Err(anyhow!("page requested without query in progress")))?;
 
                 self.unhandled_event = None;
-                self.unpaged_query = None;
                 return Ok(());
             }
         };
 
+        let started = Instant::now();
         let revset = match ws
-            .evaluate_revset_str(revset_str)
+            .evaluate_filtered_revset_str(revset_str, filter)
             .context("evaluate revset")
         {
             Ok(x) => x,
             Err(err) => {
-                tx.send(Err(err))?;
+                respond(Err(err))?;
 
                 self.unhandled_event = None;
-                self.unpaged_query = None;
                 return Ok(());
             }
         };
 
-        let mut query = queries::LogQuery::new(ws, &*revset, query_state);
+        let query_key = queries::query_key(revset_str, filter);
+        let mut query = queries::LogQuery::new(ws, &query_key, &*revset, query_state);
         let page = query.get_page();
-        tx.send(page)?;
+
+        // a cancelled query's state is incomplete, so the pane will need a new query
+        if page
+            .as_ref()
+            .is_err_and(|err| err.is::<queries::QueryCancelledError>())
+        {
+            respond(page)?;
+
+            self.unhandled_event = None;
+            return Ok(());
+        }
+
+        if page.is_ok() {
+            self.profile.record(revset_str, started.elapsed());
+        }
+        respond(page)?;
 
         let QueryResult(next_event, next_query) = query.handle_events(rx).context("LogQuery")?;
 
         self.unhandled_event = Some(next_event);
-        self.unpaged_query = Some(next_query);
+        self.unpaged_queries.insert(pane.to_owned(), next_query);
         Ok(())
     }
 }
@@ -215,76 +483,381 @@ impl Session for WorkspaceSession<'_> {
     fn handle_events(mut self, rx: &Receiver<SessionEvent>) -> Result<WorkspaceResult> {
         let mut state = WorkspaceState::default();
 
+        // the frontend is busy rendering the RepoConfig, so get a head start on its first query;
+        // log queries go to the reader, so the writer would only be duplicating its work
+        if self.session.read_only && self.should_warm_up() {
+            match queries::warm_up(&self) {
+                Ok(query_state) => {
+                    state
+                        .unpaged_queries
+                        .insert(DEFAULT_PANE.to_owned(), query_state);
+                }
+                Err(err) => log::warn!("warm-up failed: {err:#}"),
+            }
+        }
+
         loop {
             let next_event = if state.unhandled_event.is_some() {
                 state.unhandled_event.take().unwrap()
             } else {
                 let evt = rx.recv();
                 log::debug!("WorkspaceSession handling {evt:?}");
+                if let Ok(evt) = &evt {
+                    record(self.session, evt);
+                }
                 evt?
             };
 
+            // each command reports only the warnings it raised
+            self.clear_warnings();
+
+            if self.session.read_only {
+                if let Err(err) = self.load_latest() {
+                    log::warn!("load latest operation: {err:#}");
+                }
+            }
+
             match next_event {
                 SessionEvent::EndSession => return Ok(WorkspaceResult::SessionComplete),
                 SessionEvent::OpenWorkspace { tx, wd: cwd } => {
+                    // reopening stays in whichever workspace was last switched to
+                    let cwd = cwd.or_else(|| Some(self.workspace_root().to_owned()));
                     return Ok(WorkspaceResult::Reopen(tx, cwd));
                 }
+                SessionEvent::SwitchWorkspace { tx, name } => match self.switch_workspace(&name) {
+                    Ok(()) => {
+                        // rows and stems were laid out around the other workspace's working copy
+                        state.unpaged_queries.clear();
+                        if !self.session.read_only {
+                            self.import_and_snapshot(false)?;
+                            self.start_watching();
+                            self.start_autosave();
+                        }
+
+                        tx.send(self.format_config())?;
+                    }
+                    Err(err) => tx.send(Err(err))?,
+                },
                 SessionEvent::QueryRevision { tx, id } => {
                     tx.send(queries::query_revision(&self, id))?
                 }
+                SessionEvent::QueryChildren { tx, id } => {
+                    tx.send(queries::query_children(&self, id))?
+                }
+                SessionEvent::QueryWorkspaces { tx } => {
+                    tx.send(queries::query_workspaces(&self))?
+                }
+                SessionEvent::QueryGitRemotes { tx } => tx.send(self.format_git_remotes())?,
+                SessionEvent::QueryWorkspace { tx, name } => {
+                    tx.send(queries::query_workspace(&self, name))?
+                }
+                SessionEvent::QueryContext { tx, id } => {
+                    tx.send(queries::query_context(&self, id))?
+                }
+                SessionEvent::QueryTemplateContext { tx, id } => {
+                    tx.send(queries::query_template_context(&self, id))?
+                }
+                SessionEvent::QueryIsAncestor {
+                    tx,
+                    ancestor_id,
+                    descendant_id,
+                } => tx.send(queries::is_ancestor(&self, &ancestor_id, &descendant_id))?,
+                SessionEvent::ResolveIdPrefix { tx, prefix } => {
+                    tx.send(queries::resolve_id_prefix(&self, &prefix))?
+                }
+                SessionEvent::ResolveDragPayload {
+                    tx,
+                    pane,
+                    op_id,
+                    payloads,
+                } => tx.send(queries::resolve_drag_payload(
+                    &self,
+                    state.unpaged_queries.get(&pane),
+                    &op_id,
+                    payloads,
+                ))?,
+                SessionEvent::LookupText { tx, text } => {
+                    tx.send(lookup::lookup_text(&self, &text))?
+                }
+                SessionEvent::GetCommitUrl { tx, target } => {
+                    tx.send(queries::get_commit_url(&self, target))?
+                }
+                SessionEvent::QuerySlowQueries { tx } => {
+                    tx.send(Ok(state.profile.format(&self.default_query())))?
+                }
+                SessionEvent::QueryLargePaths { tx, id, limit } => {
+                    tx.send(queries::query_large_paths(&self, id, limit))?
+                }
+                SessionEvent::QueryRevisionTree { tx, id, dir } => {
+                    tx.send(tree::query_revision_tree(&self, id, dir))?
+                }
+                SessionEvent::QueryFileContents { tx, id, path } => {
+                    tx.send(tree::query_file_contents(&self, id, path))?
+                }
+                SessionEvent::QueryBlame { tx, id, path } => {
+                    tx.send(blame::query_blame(&self, id, path))?
+                }
+                SessionEvent::QueryFileHistory {
+                    tx,
+                    id,
+                    path,
+                    after,
+                    page_size,
+                } => tx.send(follow::query_file_history(
+                    &self,
+                    id,
+                    path,
+                    after,
+                    page_size.unwrap_or(self.session.log_page_size),
+                ))?,
+                SessionEvent::QueryOwners { tx, id } => {
+                    tx.send(queries::query_owners(&self, id))?
+                }
+                SessionEvent::QueryRevisionDiff {
+                    tx,
+                    id,
+                    style,
+                    after,
+                    page_size,
+                } => tx.send(diff::query_diff(
+                    &self,
+                    id,
+                    None,
+                    style,
+                    after,
+                    page_size.unwrap_or(diff::DEFAULT_PAGE_SIZE),
+                ))?,
+                SessionEvent::QueryFileDiff {
+                    tx,
+                    id,
+                    path,
+                    style,
+                    after,
+                    page_size,
+                } => tx.send(diff::query_diff(
+                    &self,
+                    id,
+                    Some(path),
+                    style,
+                    after,
+                    page_size.unwrap_or(diff::DEFAULT_PAGE_SIZE),
+                ))?,
+                SessionEvent::QueryConflict { tx, id, path } => {
+                    tx.send(queries::query_conflict(&self, id, path))?
+                }
+                SessionEvent::QueryConflicts { tx, id } => {
+                    tx.send(queries::query_conflicts(&self, id))?
+                }
+                SessionEvent::QueryMergeTools { tx } => tx.send(tools::discover(&self))?,
+                SessionEvent::QueryOperations {
+                    tx,
+                    after,
+                    page_size,
+                } => tx.send(queries::query_operations(
+                    &self,
+                    after,
+                    page_size.unwrap_or(self.session.log_page_size),
+                ))?,
+                SessionEvent::QueryCheckpoints { tx } => {
+                    tx.send(queries::query_checkpoints(&self))?
+                }
+                SessionEvent::QueryAnnotations { tx } => {
+                    tx.send(queries::query_annotations(&self))?
+                }
+                SessionEvent::QueryReviewStates { tx } => {
+                    tx.send(queries::query_review_states(&self))?
+                }
+                SessionEvent::QueryNamedQueries { tx } => {
+                    tx.send(Ok(self.format_named_queries()))?
+                }
+                SessionEvent::ListAvailableCommands { tx, ids } => {
+                    tx.send(palette::list_available_commands(&self, ids))?
+                }
+                SessionEvent::QueryChangeHistory { tx, id, limit } => {
+                    tx.send(queries::query_change_history(
+                        &self,
+                        id,
+                        limit.unwrap_or(self.session.log_page_size),
+                    ))?
+                }
+                SessionEvent::QuerySuggestedDescription { tx, id } => {
+                    tx.send(suggest::suggest_description(&self, id))?
+                }
+                SessionEvent::ExportOperationHistory { tx, options } => {
+                    tx.send(history::export(&self, options))?
+                }
+                SessionEvent::LaunchDiffTool { tx, id, path, tool } => {
+                    tx.send(tools::launch_diff_tool(&self, id, path, &tool))?
+                }
+                SessionEvent::RevealInEditor { tx, path, line } => {
+                    tx.send(tools::reveal_in_editor(&self, path, line))?
+                }
+                SessionEvent::RevealCrashReport { tx, path } => {
+                    tx.send(crash::reveal(&self, &path))?
+                }
                 SessionEvent::QueryLog {
                     tx,
+                    pane,
                     query: revset_string,
+                    filter,
+                    page_size,
+                    count,
                 } => {
+                    let query = (revset_string, filter);
+                    let page_size = page_size.unwrap_or(self.session.log_page_size);
                     state.handle_query(
                         &self,
-                        tx,
+                        &pane,
+                        |page| Ok(tx.send(page)?),
                         rx,
-                        Some(&revset_string),
-                        Some(LogQueryState::new(self.session.log_page_size)),
+                        Some(&query),
+                        Some(LogQueryState::new(&pane, page_size).with_count(count)),
                     )?;
 
-                    self.session.latest_query = Some(revset_string);
+                    if pane == DEFAULT_PANE {
+                        self.session.latest_query = Some(query.0.clone());
+                    }
+                    self.session.pane_queries.insert(pane, query);
                 }
-                SessionEvent::QueryLogNextPage { tx } => {
-                    let revset_string = self.session.latest_query.as_ref().map(|x| x.as_str());
+                SessionEvent::QueryLogNextPage { tx, pane } => {
+                    state.handle_query(
+                        &self,
+                        &pane,
+                        |page| Ok(tx.send(page)?),
+                        rx,
+                        self.session.pane_queries.get(&pane),
+                        None,
+                    )?;
+                }
+                SessionEvent::CancelQuery { pane } => {
+                    state.unpaged_queries.remove(&pane);
+                }
+                SessionEvent::RefreshQuery { tx, pane } => {
+                    let (query_state, old_rows) = match state.unpaged_queries.remove(&pane) {
+                        Some(query_state) => {
+                            let (query_state, old_rows) = query_state.refresh();
+                            (Some(query_state), old_rows)
+                        }
+                        None => (None, Vec::new()),
+                    };
 
-                    state.handle_query(&self, tx, rx, revset_string, None)?;
+                    state.handle_query(
+                        &self,
+                        &pane,
+                        |page| Ok(tx.send(page.map(|page| queries::diff_rows(old_rows, page)))?),
+                        rx,
+                        self.session.pane_queries.get(&pane),
+                        query_state,
+                    )?;
+                }
+                SessionEvent::SearchLog {
+                    tx,
+                    pane,
+                    text,
+                    is_regex,
+                } => tx.send(search::search_log(&self, &pane, &text, is_regex))?,
+                SessionEvent::SetBackgroundPolicy { policy } => {
+                    self.session.background_policy = policy;
+                }
+                SessionEvent::ProvideCredentials { .. } => {
+                    log::warn!("credentials provided, but none were requested");
                 }
                 SessionEvent::ExecuteSnapshot { tx } => {
-                    if self.import_and_snapshot(false).is_ok_and(|updated| updated) {
+                    if self.session.background_policy.suspends(false) {
+                        log::debug!("power saving; skipping snapshot");
+                        tx.send(None)?;
+                    } else if self.import_and_snapshot(false).is_ok_and(|updated| updated) {
                         tx.send(Some(self.format_status()))?;
                     } else {
                         tx.send(None)?;
                     }
                 }
+                SessionEvent::FilesChanged => {
+                    if self.session.background_policy.suspends(false) {
+                        log::debug!("power saving; skipping snapshot of changed files");
+                    } else if let Err(err) = self.import_and_snapshot(true) {
+                        log::warn!("snapshot changed files: {err:#}");
+                    }
+                }
+                SessionEvent::OperationsChanged => match self.load_latest() {
+                    Ok(true) => self.publish(messages::RepoEvent::RepoChangedExternally {
+                        status: self.format_status(),
+                    }),
+                    // our own operations are already loaded
+                    Ok(false) => (),
+                    Err(err) => log::warn!("load external operation: {err:#}"),
+                },
+                SessionEvent::Autosave => {
+                    if self.session.background_policy.suspends(false) {
+                        log::debug!("power saving; skipping autosave");
+                    } else if let Err(err) = self
+                        .import_and_snapshot(true)
+                        .and_then(|_| autosave::record(&mut self))
+                    {
+                        log::warn!("autosave: {err:#}");
+                    }
+                }
                 SessionEvent::ExecuteMutation { tx, mutation } => {
                     let name = mutation.as_ref().describe();
-                    match catch_unwind(AssertUnwindSafe(|| {
-                        mutation.execute(&mut self).with_context(|| name.clone())
-                    })) {
+                    self.action = name.rsplit("::").next().map(|action| action.to_owned());
+                    let outcome = catch_unwind(AssertUnwindSafe(|| {
+                        let result = mutation.execute(&mut self);
+                        result.with_context(|| self.action.clone().unwrap_or_default())
+                    }));
+                    self.action = None;
+
+                    match outcome {
                         Ok(result) => {
                             tx.send(match result {
                                 Ok(result) => result,
-                                Err(err) => {
-                                    log::error!("{err:?}");
-                                    messages::MutationResult::InternalError {
-                                        message: (&*format!("{err:?}")).into(),
+                                Err(err) if err.downcast_ref::<GitChangedError>().is_some() => {
+                                    messages::MutationResult::PreconditionError {
+                                        code: "git-changed".to_owned(),
+                                        params: HashMap::new(),
+                                        message: GitChangedError.to_string(),
                                     }
                                 }
+                                Err(err) => match err.downcast::<HookFailedError>() {
+                                    Ok(HookFailedError { hook, output }) => {
+                                        messages::MutationResult::HookFailed {
+                                            hook,
+                                            output: (&*output).into(),
+                                        }
+                                    }
+                                    Err(err) => {
+                                        log::error!("{err:?}");
+                                        // a lock file is worth inspecting, since it may have been left by a crash
+                                        let locks = if locks::is_lock_error(&err) {
+                                            locks::find_locks(&self).unwrap_or_default()
+                                        } else {
+                                            Vec::new()
+                                        };
+                                        if locks.is_empty() {
+                                            let report = crash::report(
+                                                &self,
+                                                CrashReport::from_error(&name, &err),
+                                            );
+                                            messages::MutationResult::InternalError {
+                                                message: (&*format!("{err:?}")).into(),
+                                                report: report.map(Into::into),
+                                            }
+                                        } else {
+                                            messages::MutationResult::Locked {
+                                                message: (&*format!("{err:#}")).into(),
+                                                locks,
+                                            }
+                                        }
+                                    }
+                                },
                             })?;
                         }
                         Err(panic) => {
-                            let mut message = match panic.downcast::<&str>() {
-                                Ok(v) => *v,
-                                _ => "panic!()",
-                            }
-                            .to_owned();
-                            message.insert_str(0, ": ");
-                            message.insert_str(0, &name);
+                            let report = CrashReport::from_panic(&name, &*panic);
+                            let message = report.summary();
                             log::error!("{message}");
                             tx.send(messages::MutationResult::InternalError {
                                 message: (&*message).into(),
+                                report: crash::report(&self, report).map(Into::into),
                             })?;
                         }
                     }
@@ -296,6 +869,13 @@ impl Session for WorkspaceSession<'_> {
 
 pub struct QueryResult(SessionEvent, LogQueryState);
 
+// events are recorded as they're received, so that a replay sends the same sequence
+fn record(session: &WorkerSession, event: &SessionEvent) {
+    if let Some(recorder) = &session.recorder {
+        recorder.record(session.read_only, event);
+    }
+}
+
 impl Session for queries::LogQuery<'_, '_> {
     type Transition = QueryResult;
 
@@ -303,12 +883,29 @@ impl Session for queries::LogQuery<'_, '_> {
         loop {
             let evt = rx.recv();
             log::debug!("LogQuery handling {evt:?}");
+            if let Ok(evt) = &evt {
+                record(self.ws.session, evt);
+            }
             match evt {
                 Ok(SessionEvent::QueryRevision { tx, id }) => {
                     tx.send(queries::query_revision(&self.ws, id))?
                 }
-                Ok(SessionEvent::QueryLogNextPage { tx }) => tx.send(self.get_page())?,
-                Ok(unhandled) => return Ok(QueryResult(unhandled, self.state)),
+                Ok(SessionEvent::QueryLogNextPage { tx, pane }) if pane == self.state.pane() => {
+                    let page = self.get_page();
+                    let is_cancelled = page
+                        .as_ref()
+                        .is_err_and(|err| err.is::<queries::QueryCancelledError>());
+                    tx.send(page)?;
+
+                    // leaves the session to discard this query's state
+                    if is_cancelled {
+                        return Ok(QueryResult(
+                            SessionEvent::CancelQuery { pane },
+                            self.into_state(),
+                        ));
+                    }
+                }
+                Ok(unhandled) => return Ok(QueryResult(unhandled, self.into_state())),
                 Err(err) => return Err(anyhow!(err)),
             };
         }
@@ -1,41 +1,73 @@
 use std::{
+    collections::{HashMap, HashSet},
     fmt::Display,
+    fs,
     path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use futures_util::future;
 use indexmap::IndexMap;
 use itertools::Itertools;
+use jj_cli::cli_util::short_operation_hash;
 use jj_lib::{
-    backend::CommitId,
+    backend::{CommitId, MergedTreeId, TreeValue},
     commit::Commit,
-    git::{RemoteCallbacks, REMOTE_NAME_FOR_LOCAL_GIT_REPO},
-    matchers::{EverythingMatcher, FilesMatcher, Matcher},
+    files::ContentHunk,
+    git::{
+        BranchPushAction, GitBranchPushTargets, RemoteCallbacks, REMOTE_NAME_FOR_LOCAL_GIT_REPO,
+    },
+    matchers::{EverythingMatcher, FilesMatcher, Matcher, PrefixMatcher},
+    merge::Merge,
+    merged_tree::MergedTreeBuilder,
     object_id::ObjectId,
-    op_store::RefTarget,
+    op_store::{RefTarget, WorkspaceId},
     op_walk,
+    operation::Operation,
     repo::Repo,
     repo_path::RepoPath,
-    rewrite,
+    revset::RevsetExpression,
+    rewrite::{self, EmptyBehaviour, RebaseOptions, RebasedCommit},
+    signing::SignBehavior,
     str_util::StringPattern,
+    transaction::Transaction,
 };
+use pollster::FutureExt;
 
 use crate::{
-    gui_util::WorkspaceSession,
+    config::{self, GGSettings},
+    forge,
+    gui_util::{WorkspaceSession, PUSH_TAG},
     messages::{
-        AbandonRevisions, CheckoutRevision, CopyChanges, CreateRevision, DescribeRevision,
-        DuplicateRevisions, FetchRemote, InsertRevision, MoveBranch, MoveChanges, MoveRevision,
-        MoveSource, MutationResult, PushRemote, RefName, TrackBranch, TreePath, UndoOperation,
-        UntrackBranch,
+        timestamp_from_datetime, AbandonRevisions, AddGitRemote, AddWorkspace, BackoutRevision,
+        BreakStaleLock, CheckoutRevision, CoAuthor, ConflictSide, CopyChanges,
+        CopyWorkspaceChanges, CreateCheckpoint, CreateRevision, DeleteNamedQuery, DescribeRevision,
+        DescribeRevisions, DuplicateRevisions, EditRevisionMetadata, FetchRemote, ForgetWorkspace,
+        ImportPatches, IncomingCommits, InsertRevision, MoveBranch, MoveChanges, MoveRevision,
+        MoveRevisions, MoveSource, MutationResult, PushRemote, RebaseMode, RedoOperation, RefName,
+        RemoveGitRemote, RenameGitRemote, ResolveConflict, RestoreCheckpoint,
+        RestoreFileFromOperation, RestoreOperation, ReviewState, SaveNamedQuery, SetAnnotation,
+        SetDefaultQuery, SetReviewState, SetTrunk, SignRevision, SplitRevision, SyncReviewStates,
+        TrackBranch, TreePath, UndoOperation, UntrackBranch, WriteResolvedFile,
     },
+    store::{self, Annotation, Checkpoint, Review},
 };
 
-use super::Mutation;
+use super::{hooks, locks, patches, queries, secrets, trailers, Mutation};
 
+// codes are stable identifiers for the frontend; the message is english fallback text
 macro_rules! precondition {
-    ($($args:tt)*) => {
-        return Ok(MutationResult::PreconditionError { message: format!($($args)*) })
-    }
+    ($code:literal, $fmt:literal $(, $name:ident = $value:expr)* $(,)?) => {{
+        $(let $name = $value;)*
+        return Ok(MutationResult::PreconditionError {
+            code: $code.to_owned(),
+            params: [$((stringify!($name).to_owned(), $name.to_string())),*]
+                .into_iter()
+                .collect(),
+            message: format!($fmt $(, $name = $name)*),
+        });
+    }};
 }
 
 impl Mutation for CheckoutRevision {
@@ -45,7 +77,11 @@ impl Mutation for CheckoutRevision {
         let edited = ws.resolve_single_change(&self.id)?;
 
         if ws.check_immutable(vec![edited.id().clone()])? {
-            precondition!("Revision is immutable");
+            precondition!(
+                "revision-immutable",
+                "Revision {id} is immutable",
+                id = &self.id.change.prefix
+            );
         }
 
         if edited.id() == ws.wc_id() {
@@ -118,11 +154,12 @@ impl Mutation for InsertRevision {
             .context("resolve after_id")?;
 
         if ws.check_immutable(vec![target.id().clone(), before.id().clone()])? {
-            precondition!("Some revisions are immutable");
+            precondition!("revisions-immutable", "Some revisions are immutable");
         }
 
         // rebase the target's children
-        let rebased_children = ws.disinherit_children(&mut tx, &target)?;
+        let rebased_children =
+            ws.disinherit_children(&mut tx, &target, &RebaseOptions::default())?;
 
         // update after, which may have been a descendant of target
         let after = rebased_children
@@ -150,17 +187,53 @@ impl Mutation for DescribeRevision {
         let described = ws.resolve_single_change(&self.id)?;
 
         if ws.check_immutable(vec![described.id().clone()])? {
-            precondition!("Revision {} is immutable", self.id.change.prefix);
+            precondition!(
+                "revision-immutable",
+                "Revision {id} is immutable",
+                id = &self.id.change.prefix
+            );
+        }
+
+        let mut new_description = self.new_description;
+        if let Some(co_authors) = &self.co_authors {
+            if let Some(invalid) = co_authors
+                .iter()
+                .find(|co_author| !trailers::is_valid_identity(co_author))
+            {
+                precondition!(
+                    "co-author-invalid",
+                    "{name} <{email}> is not a valid co-author",
+                    name = &invalid.name,
+                    email = &invalid.email
+                );
+            }
+
+            // the author is credited already, and forges count each email once
+            let author_email = described.author().email.to_lowercase();
+            let co_authors = co_authors
+                .iter()
+                .filter(|co_author| co_author.email.trim().to_lowercase() != author_email)
+                .unique_by(|co_author| co_author.email.trim().to_lowercase())
+                .cloned()
+                .collect_vec();
+            new_description = trailers::set_co_authors(&new_description, &co_authors);
         }
 
-        if self.new_description == described.description() && !self.reset_author {
+        if new_description == described.description() && !self.reset_author {
             return Ok(MutationResult::Unchanged);
         }
 
+        // git would run commit hooks for the commit its working tree becomes
+        if described.id() == ws.wc_id() {
+            if let Some(git_repo) = ws.git_repo()? {
+                new_description = hooks::run_commit_hooks(ws, &git_repo, new_description)?;
+            }
+        }
+
         let mut commit_builder = tx
             .mut_repo()
             .rewrite_commit(&ws.settings, &described)
-            .set_description(self.new_description);
+            .set_description(new_description);
 
         if self.reset_author {
             let new_author = commit_builder.committer().clone();
@@ -176,6 +249,298 @@ impl Mutation for DescribeRevision {
     }
 }
 
+impl Mutation for DescribeRevisions {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let mut new_descriptions = HashMap::new();
+        for revision in self.revisions {
+            let described = ws.resolve_single_change(&revision.id)?;
+            new_descriptions.insert(described.id().clone(), revision.new_description);
+        }
+
+        if new_descriptions.is_empty() {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        if ws.check_immutable(new_descriptions.keys().cloned().collect_vec())? {
+            precondition!("revisions-immutable", "Some revisions are immutable");
+        }
+
+        // parents are rewritten first, so that each descendant is rebased before it's described
+        let described_ids = ws
+            .evaluate_revset_expr(RevsetExpression::commits(
+                new_descriptions.keys().cloned().collect_vec(),
+            ))?
+            .iter()
+            .collect_vec();
+
+        let mut rewritten: HashMap<CommitId, CommitId> = HashMap::new();
+        let mut changed_ids = Vec::new();
+        for described_id in described_ids.into_iter().rev() {
+            let mut new_description = new_descriptions
+                .remove(&described_id)
+                .expect("described id was resolved");
+
+            let current = tx
+                .repo()
+                .store()
+                .get_commit(&follow_rewrites(&rewritten, &described_id))?;
+
+            if new_description == current.description() {
+                continue;
+            }
+
+            // git would run commit hooks for the commit its working tree becomes
+            if &described_id == ws.wc_id() {
+                if let Some(git_repo) = ws.git_repo()? {
+                    new_description = hooks::run_commit_hooks(ws, &git_repo, new_description)?;
+                }
+            }
+
+            tx.mut_repo()
+                .rewrite_commit(&ws.settings, &current)
+                .set_description(new_description)
+                .write()?;
+            rewritten.extend(tx.mut_repo().rebase_descendants_return_map(&ws.settings)?);
+            changed_ids.push(described_id);
+        }
+
+        if changed_ids.is_empty() {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        let transaction_description = if changed_ids.len() == 1 {
+            format!("describe commit {}", changed_ids[0].hex())
+        } else {
+            format!(
+                "describe commit {} and {} more",
+                changed_ids[0].hex(),
+                changed_ids.len() - 1
+            )
+        };
+
+        match ws.finish_transaction(tx, transaction_description)? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for EditRevisionMetadata {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let edited = ws.resolve_single_change(&self.id)?;
+
+        if ws.check_immutable(vec![edited.id().clone()])? {
+            precondition!(
+                "revision-immutable",
+                "Revision {id} is immutable",
+                id = &self.id.change.prefix
+            );
+        }
+
+        let mut new_author = edited.author().clone();
+        if self.author_name.is_some() || self.author_email.is_some() {
+            let identity = CoAuthor {
+                name: self.author_name.unwrap_or(new_author.name),
+                email: self.author_email.unwrap_or(new_author.email),
+            };
+            if !trailers::is_valid_identity(&identity) {
+                precondition!(
+                    "author-invalid",
+                    "{name} <{email}> is not a valid author",
+                    name = &identity.name,
+                    email = &identity.email
+                );
+            }
+            new_author.name = identity.name.trim().to_owned();
+            new_author.email = identity.email.trim().to_owned();
+        }
+        if let Some(author_timestamp) = &self.author_timestamp {
+            new_author.timestamp = timestamp_from_datetime(author_timestamp);
+        }
+
+        if &new_author == edited.author() && self.committer_timestamp.is_none() {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        let mut commit_builder = tx
+            .mut_repo()
+            .rewrite_commit(&ws.settings, &edited)
+            .set_author(new_author);
+
+        if let Some(committer_timestamp) = &self.committer_timestamp {
+            let mut new_committer = commit_builder.committer().clone();
+            new_committer.timestamp = timestamp_from_datetime(committer_timestamp);
+            commit_builder = commit_builder.set_committer(new_committer);
+        }
+
+        commit_builder.write()?;
+
+        match ws.finish_transaction(
+            tx,
+            format!("edit author and timestamps of commit {}", edited.id().hex()),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for AddWorkspace {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let destination = PathBuf::from(&self.path);
+        let name = self.name.filter(|name| !name.is_empty()).or_else(|| {
+            destination
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        });
+        let Some(name) = name else {
+            precondition!(
+                "workspace-unnamed",
+                "A name is required for {path}",
+                path = &self.path
+            );
+        };
+
+        let workspace_id = WorkspaceId::new(name.clone());
+        if ws.view().get_wc_commit_id(&workspace_id).is_some() {
+            precondition!(
+                "workspace-exists",
+                "Workspace {name} already exists",
+                name = &name
+            );
+        }
+        if destination.exists() && fs::read_dir(&destination)?.next().is_some() {
+            precondition!("path-not-empty", "{path} is not empty", path = &self.path);
+        }
+
+        let parents = match &self.revision_id {
+            Some(id) => vec![ws.resolve_single_change(id)?],
+            None => ws.get_commit(ws.wc_id())?.parents(),
+        };
+
+        fs::create_dir_all(&destination)
+            .with_context(|| format!("create {}", destination.display()))?;
+        let mut new_workspace = ws.add_workspace(&destination, workspace_id.clone())?;
+        ws.store
+            .set_workspace_path(&name, new_workspace.workspace_root())?;
+
+        // jj adds the workspace at the root commit, so it's moved onto a new commit like `jj workspace add` does
+        let mut tx = ws.start_transaction()?;
+        let tree = rewrite::merge_commit_trees(tx.repo(), &parents)?;
+        let new_wc_commit = tx
+            .mut_repo()
+            .new_commit(
+                &ws.settings,
+                parents.iter().map(|parent| parent.id().clone()).collect(),
+                tree.id(),
+            )
+            .write()?;
+        tx.mut_repo().edit(workspace_id, &new_wc_commit)?;
+
+        let new_status = ws.finish_transaction(
+            tx,
+            format!("create initial working-copy commit in workspace {name}"),
+        )?;
+        new_workspace.check_out(ws.repo().op_id().clone(), None, &new_wc_commit)?;
+
+        match new_status {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for ForgetWorkspace {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let workspace_id = WorkspaceId::new(self.name.clone());
+        if &workspace_id == ws.id() {
+            precondition!(
+                "workspace-current",
+                "The workspace open in this window can't be forgotten"
+            );
+        }
+        if ws.view().get_wc_commit_id(&workspace_id).is_none() {
+            precondition!(
+                "workspace-missing",
+                "No workspace named {name}",
+                name = &self.name
+            );
+        }
+
+        // an empty working-copy commit is abandoned along with the workspace
+        tx.mut_repo().remove_wc_commit(&workspace_id)?;
+
+        let new_status = ws.finish_transaction(tx, format!("forget workspace {}", self.name))?;
+        ws.store.remove_workspace_path(&self.name)?;
+
+        match new_status {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for BreakStaleLock {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        // only known lock files may be deleted, so that the frontend can't be used to remove anything else
+        let Some(lock) = locks::find_locks(ws)?
+            .into_iter()
+            .find(|lock| lock.path == self.path)
+        else {
+            return Ok(MutationResult::Unchanged);
+        };
+
+        if !lock.is_stale {
+            precondition!(
+                "lock-held",
+                "{path} is still held by another process",
+                path = &self.path
+            );
+        }
+
+        fs::remove_file(&self.path).with_context(|| format!("remove {}", self.path))?;
+
+        Ok(MutationResult::Unchanged)
+    }
+}
+
+impl Mutation for SignRevision {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if ws.settings.signing_backend().is_none() {
+            precondition!("signing-unconfigured", "No signing backend is configured");
+        }
+
+        let mut tx = ws.start_transaction()?;
+
+        let signed = ws.resolve_single_change(&self.id)?;
+
+        if ws.check_immutable(vec![signed.id().clone()])? {
+            precondition!(
+                "revision-immutable",
+                "Revision {id} is immutable",
+                id = &self.id.change.prefix
+            );
+        }
+
+        // forced, because the default of signing only the user's own commits would skip co-authored work
+        tx.mut_repo()
+            .rewrite_commit(&ws.settings, &signed)
+            .set_sign_behavior(SignBehavior::Force)
+            .write()?;
+
+        match ws.finish_transaction(tx, format!("sign commit {}", signed.id().hex()))? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
 impl Mutation for DuplicateRevisions {
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
         let mut tx = ws.start_transaction()?;
@@ -246,7 +611,7 @@ impl Mutation for AbandonRevisions {
             .collect_vec();
 
         if ws.check_immutable(abandoned_ids.clone())? {
-            precondition!("Some revisions are immutable");
+            precondition!("revisions-immutable", "Some revisions are immutable");
         }
 
         for id in &abandoned_ids {
@@ -279,11 +644,18 @@ impl Mutation for MoveRevision {
         let parents = ws.resolve_multiple_changes(self.parent_ids)?;
 
         if ws.check_immutable(vec![target.id().clone()])? {
-            precondition!("Revision {} is immutable", self.id.change.prefix);
+            precondition!(
+                "revision-immutable",
+                "Revision {id} is immutable",
+                id = &self.id.change.prefix
+            );
         }
 
+        let options = rebase_options(ws, self.skip_empty);
+
         // rebase the target's children
-        let rebased_children = ws.disinherit_children(&mut tx, &target)?;
+        let rebased_children = ws.disinherit_children(&mut tx, &target, &options)?;
+        let mut abandoned_empty = count_abandoned(&tx, &rebased_children)?;
 
         // update parents, which may have been descendants of the target
         let parents: Vec<_> = parents
@@ -299,12 +671,14 @@ impl Mutation for MoveRevision {
 
         // rebase the target itself
         let rebased_id = target.id().hex();
-        rewrite::rebase_commit(&ws.settings, tx.mut_repo(), &target, &parents)?;
+        abandoned_empty += rebase_target(ws, &mut tx, &target, &parents, &options)?;
 
-        match ws.finish_transaction(tx, format!("rebase commit {}", rebased_id))? {
-            Some(new_status) => Ok(MutationResult::Updated { new_status }),
-            None => Ok(MutationResult::Unchanged),
-        }
+        rebase_result(
+            ws,
+            tx,
+            format!("rebase commit {}", rebased_id),
+            abandoned_empty,
+        )
     }
 }
 
@@ -316,17 +690,176 @@ impl Mutation for MoveSource {
         let parents = ws.resolve_multiple_commits(&self.parent_ids)?;
 
         if ws.check_immutable(vec![target.id().clone()])? {
-            precondition!("Revision {} is immutable", self.id.change.prefix);
+            precondition!(
+                "revision-immutable",
+                "Revision {id} is immutable",
+                id = &self.id.change.prefix
+            );
         }
 
-        // just rebase the target, which will also rebase its descendants
+        let options = rebase_options(ws, self.skip_empty);
+
+        // rebase the target, then its descendants, with the same options
         let rebased_id = target.id().hex();
-        rewrite::rebase_commit(&ws.settings, tx.mut_repo(), &target, &parents)?;
+        let mut abandoned_empty = rebase_target(ws, &mut tx, &target, &parents, &options)?;
+        let rebased_descendants = tx
+            .mut_repo()
+            .rebase_descendants_with_options_return_map(&ws.settings, options)?;
+        abandoned_empty += count_abandoned(&tx, &rebased_descendants)?;
 
-        match ws.finish_transaction(tx, format!("rebase commit {}", rebased_id))? {
-            Some(new_status) => Ok(MutationResult::Updated { new_status }),
-            None => Ok(MutationResult::Unchanged),
+        rebase_result(
+            ws,
+            tx,
+            format!("rebase commit {}", rebased_id),
+            abandoned_empty,
+        )
+    }
+}
+
+impl Mutation for MoveRevisions {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let revision_ids = ws
+            .resolve_multiple_changes(self.ids)?
+            .iter()
+            .map(|commit| commit.id().clone())
+            .collect_vec();
+        let parent_ids = ws
+            .resolve_multiple_changes(self.parent_ids)?
+            .iter()
+            .map(|commit| commit.id().clone())
+            .collect_vec();
+
+        // a branch is moved by its roots, the commits which aren't already descended from the new parents
+        let target_ids = match self.mode {
+            RebaseMode::Single | RebaseMode::WithDescendants => revision_ids,
+            RebaseMode::WholeBranch => ws
+                .evaluate_revset_expr(
+                    RevsetExpression::commits(parent_ids.clone())
+                        .range(&RevsetExpression::commits(revision_ids))
+                        .roots(),
+                )?
+                .iter()
+                .collect_vec(),
+        };
+
+        if target_ids.is_empty() || parent_ids.is_empty() {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        if ws.check_immutable(target_ids.clone())? {
+            precondition!("revisions-immutable", "Some revisions are immutable");
+        }
+
+        let targets: HashSet<CommitId> = target_ids.iter().cloned().collect();
+        let moved = match self.mode {
+            RebaseMode::Single => RevsetExpression::commits(target_ids.clone()),
+            _ => RevsetExpression::commits(target_ids.clone()).descendants(),
+        };
+        let onto_moved = moved.intersection(&RevsetExpression::commits(parent_ids.clone()));
+        if ws.evaluate_revset_expr(onto_moved)?.iter().next().is_some() {
+            precondition!(
+                "rebase-cycle",
+                "Revisions can't be rebased onto themselves or their descendants"
+            );
+        }
+
+        let options = rebase_options(ws, self.skip_empty);
+
+        // parents are rebased before their children; descendants which stay behind go before everything
+        // else, since the revisions may be moved onto them
+        let mut rebased_ids = ws
+            .evaluate_revset_expr(RevsetExpression::commits(target_ids.clone()).descendants())?
+            .iter()
+            .collect_vec();
+        rebased_ids.reverse();
+        if let RebaseMode::Single = self.mode {
+            rebased_ids.sort_by_key(|id| targets.contains(id));
+        }
+
+        let mut rewritten: HashMap<CommitId, CommitId> = HashMap::new();
+        let mut abandoned_empty = 0;
+        for rebased_id in rebased_ids {
+            let commit = tx.base_repo().store().get_commit(&rebased_id)?;
+
+            let new_parent_ids = match self.mode {
+                RebaseMode::Single if targets.contains(&rebased_id) => {
+                    let moved_ancestors = ws
+                        .evaluate_revset_expr(
+                            RevsetExpression::commits(target_ids.clone())
+                                .intersection(
+                                    &RevsetExpression::commit(rebased_id.clone())
+                                        .parents()
+                                        .ancestors(),
+                                )
+                                .heads(),
+                        )?
+                        .iter()
+                        .collect_vec();
+                    if moved_ancestors.is_empty() {
+                        parent_ids.clone()
+                    } else {
+                        moved_ancestors
+                    }
+                }
+                _ if targets.contains(&rebased_id) => parent_ids.clone(),
+                RebaseMode::Single if commit.parent_ids().iter().any(|id| targets.contains(id)) => {
+                    remaining_parents(ws, &commit, &targets)?
+                }
+                _ => commit.parent_ids().to_vec(),
+            };
+
+            let new_parents: Vec<Commit> = new_parent_ids
+                .iter()
+                .map(|id| {
+                    tx.repo()
+                        .store()
+                        .get_commit(&follow_rewrites(&rewritten, id))
+                })
+                .try_collect()?;
+            if new_parents
+                .iter()
+                .map(|parent| parent.id())
+                .eq(commit.parent_ids())
+            {
+                continue;
+            }
+
+            let rebased = rewrite::rebase_commit_with_options(
+                &ws.settings,
+                tx.mut_repo(),
+                &commit,
+                &new_parents,
+                &options,
+            )?;
+            let new_id = match rebased {
+                RebasedCommit::Rewritten(new_commit) => new_commit.id().clone(),
+                RebasedCommit::Abandoned { parent } => {
+                    abandoned_empty += 1;
+                    parent.id().clone()
+                }
+            };
+            rewritten.insert(rebased_id, new_id);
         }
+
+        // every descendant has been rebased already, but this also moves branches and working copies
+        let rebased_descendants = tx
+            .mut_repo()
+            .rebase_descendants_with_options_return_map(&ws.settings, options)?;
+        abandoned_empty += count_abandoned(&tx, &rebased_descendants)?;
+
+        let transaction_description = if target_ids.len() == 1 {
+            format!("rebase commit {}", target_ids[0].hex())
+        } else {
+            format!(
+                "rebase commit {} and {} more",
+                target_ids[0].hex(),
+                target_ids.len() - 1
+            )
+        };
+
+        rebase_result(ws, tx, transaction_description, abandoned_empty)
     }
 }
 
@@ -339,7 +872,7 @@ impl Mutation for MoveChanges {
         let matcher = build_matcher(&self.paths);
 
         if ws.check_immutable(vec![from.id().clone(), to.id().clone()])? {
-            precondition!("Revisions are immutable");
+            precondition!("revisions-immutable", "Revisions are immutable");
         }
 
         // construct a split tree and a remainder tree by copying changes from child to parent and from parent to child
@@ -391,61 +924,391 @@ impl Mutation for MoveChanges {
     }
 }
 
-impl Mutation for CopyChanges {
+impl Mutation for BackoutRevision {
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
         let mut tx = ws.start_transaction()?;
 
-        let from_tree = ws.resolve_single_commit(&self.from_id)?.tree()?;
-        let to = ws.resolve_single_change(&self.to_id)?;
-        let matcher = build_matcher(&self.paths);
-
-        if ws.check_immutable(vec![to.id().clone()])? {
-            precondition!("Revisions are immutable");
-        }
+        // immutable revisions can be backed out - that's the point
+        let reverted = ws.resolve_single_change(&self.id)?;
 
-        // construct a restore tree - the destination with some portions overwritten by the source
-        let to_tree = to.tree()?;
-        let new_to_tree_id = rewrite::restore_tree(&from_tree, &to_tree, matcher.as_ref())?;
-        if &new_to_tree_id == to.tree_id() {
-            Ok(MutationResult::Unchanged)
+        let parent_commits = if self.parent_ids.is_empty() {
+            vec![ws.get_commit(ws.wc_id())?]
         } else {
-            tx.mut_repo()
-                .rewrite_commit(&ws.settings, &to)
-                .set_tree_id(new_to_tree_id)
-                .write()?;
+            let parents_revset = ws.evaluate_revset_changes(
+                &self
+                    .parent_ids
+                    .into_iter()
+                    .map(|id| id.change)
+                    .collect_vec(),
+            )?;
+            ws.resolve_multiple(parents_revset)?
+        };
 
-            tx.mut_repo().rebase_descendants(&ws.settings)?;
+        let new_commit =
+            rewrite::back_out_commit(&ws.settings, tx.mut_repo(), &reverted, &parent_commits)?;
 
-            match ws.finish_transaction(tx, format!("restore into commit {}", to.id().hex()))? {
-                Some(new_status) => Ok(MutationResult::Updated { new_status }),
-                None => Ok(MutationResult::Unchanged),
+        match ws.finish_transaction(tx, format!("back out commit {}", reverted.id().hex()))? {
+            Some(new_status) => {
+                let new_selection = ws.format_header(&new_commit, None)?;
+                Ok(MutationResult::UpdatedSelection {
+                    new_status,
+                    new_selection,
+                })
             }
+            None => Ok(MutationResult::Unchanged),
         }
     }
 }
 
-impl Mutation for TrackBranch {
+impl Mutation for SplitRevision {
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
-        match self.name {
-            RefName::LocalBranch { branch_name, .. } => {
-                precondition!("{} is a local branch and cannot be tracked", branch_name);
-            }
-            RefName::RemoteBranch {
-                branch_name,
-                remote_name,
-                ..
-            } => {
-                let mut tx = ws.start_transaction()?;
+        let mut tx = ws.start_transaction()?;
 
-                let remote_ref: &jj_lib::op_store::RemoteRef =
-                    ws.view().get_remote_branch(&branch_name, &remote_name);
+        let target = ws.resolve_single_change(&self.id)?;
+        let matcher = build_matcher(&self.paths);
 
-                if remote_ref.is_tracking() {
-                    precondition!("{branch_name}@{remote_name} is already tracked");
-                }
+        if ws.check_immutable(vec![target.id().clone()])? {
+            precondition!("revisions-immutable", "Revisions are immutable");
+        }
 
-                tx.mut_repo()
-                    .track_remote_branch(&branch_name, &remote_name);
+        // the first commit gets the selected changes; the second gets the rest, by building on the first
+        let target_tree = target.tree()?;
+        let parent_tree = rewrite::merge_commit_trees(tx.repo(), &target.parents())?;
+        let first_tree_id = rewrite::restore_tree(&target_tree, &parent_tree, matcher.as_ref())?;
+        if first_tree_id == parent_tree.id() || &first_tree_id == target.tree_id() {
+            precondition!(
+                "split-one-sided",
+                "The selected paths must contain some but not all of the revision's changes"
+            );
+        }
+
+        // as in `jj split`, the second commit is a new change; descendants, branches and the working copy move to it
+        let first = tx
+            .mut_repo()
+            .rewrite_commit(&ws.settings, &target)
+            .set_tree_id(first_tree_id)
+            .set_description("")
+            .write()?;
+        tx.mut_repo()
+            .rewrite_commit(&ws.settings, &target)
+            .set_parents(vec![first.id().clone()])
+            .set_tree_id(target.tree_id().clone())
+            .generate_new_change_id()
+            .write()?;
+
+        tx.mut_repo().rebase_descendants(&ws.settings)?;
+
+        match ws.finish_transaction(tx, format!("split commit {}", target.id().hex()))? {
+            Some(new_status) => {
+                // the frontend prompts for a description of the new commit
+                let new_selection = ws.format_header(&first, None)?;
+                Ok(MutationResult::UpdatedSelection {
+                    new_status,
+                    new_selection,
+                })
+            }
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for ImportPatches {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        // patches are applied by libgit2, directly to trees in the git store
+        let Some(git_repo) = ws.git_repo()? else {
+            precondition!("no-git-backend", "No git backend");
+        };
+
+        let patches = patches::read_patches(Path::new(&self.path))?;
+        if patches.is_empty() {
+            precondition!("no-patches", "No patches found in {path}", path = self.path);
+        }
+
+        let base = ws.resolve_single_change(&self.base_id)?;
+        let Some(mut tree_id) = base.tree_id().to_merge().as_resolved().cloned() else {
+            precondition!(
+                "import-base-conflicted",
+                "Patches can't be applied to a conflicted revision"
+            );
+        };
+
+        // like `git am`, each patch becomes a child of the last
+        let mut parent_id = base.id().clone();
+        let mut last_commit = None;
+        for patch in &patches {
+            tree_id = match patches::apply_patch(&git_repo, &tree_id, patch) {
+                Ok(tree_id) => tree_id,
+                Err(err) => precondition!(
+                    "patch-failed",
+                    "Patch \"{subject}\" does not apply: {error}",
+                    subject = &patch.subject,
+                    error = err.message(),
+                ),
+            };
+
+            let mut builder = tx
+                .mut_repo()
+                .new_commit(
+                    &ws.settings,
+                    vec![parent_id],
+                    MergedTreeId::resolved(tree_id.clone()),
+                )
+                .set_description(patch.description());
+            if let Some(author) = patch.author() {
+                builder = builder.set_author(author);
+            }
+            let commit = builder.write()?;
+
+            parent_id = commit.id().clone();
+            last_commit = Some(commit);
+        }
+        let last_commit = last_commit.expect("at least one patch");
+
+        match ws.finish_transaction(tx, format!("import {} patches", patches.len()))? {
+            Some(new_status) => {
+                let new_selection = ws.format_header(&last_commit, None)?;
+                Ok(MutationResult::UpdatedSelection {
+                    new_status,
+                    new_selection,
+                })
+            }
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for CopyChanges {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let from_tree = ws.resolve_single_commit(&self.from_id)?.tree()?;
+        let to = ws.resolve_single_change(&self.to_id)?;
+        let matcher = build_matcher(&self.paths);
+
+        if ws.check_immutable(vec![to.id().clone()])? {
+            precondition!("revisions-immutable", "Revisions are immutable");
+        }
+
+        // construct a restore tree - the destination with some portions overwritten by the source
+        let to_tree = to.tree()?;
+        let new_to_tree_id = rewrite::restore_tree(&from_tree, &to_tree, matcher.as_ref())?;
+        if &new_to_tree_id == to.tree_id() {
+            Ok(MutationResult::Unchanged)
+        } else {
+            tx.mut_repo()
+                .rewrite_commit(&ws.settings, &to)
+                .set_tree_id(new_to_tree_id)
+                .write()?;
+
+            tx.mut_repo().rebase_descendants(&ws.settings)?;
+
+            match ws.finish_transaction(tx, format!("restore into commit {}", to.id().hex()))? {
+                Some(new_status) => Ok(MutationResult::Updated { new_status }),
+                None => Ok(MutationResult::Unchanged),
+            }
+        }
+    }
+}
+
+impl Mutation for CopyWorkspaceChanges {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let workspace_id = WorkspaceId::new(self.workspace_name.clone());
+        if &workspace_id == ws.id() {
+            precondition!(
+                "workspace-current",
+                "Changes can only be copied from another workspace"
+            );
+        }
+        let Some(from_id) = ws.view().get_wc_commit_id(&workspace_id).cloned() else {
+            precondition!(
+                "workspace-missing",
+                "No workspace named {name}",
+                name = &self.workspace_name
+            );
+        };
+
+        let from = ws.get_commit(&from_id)?;
+        let mut to = ws.get_commit(ws.wc_id())?;
+        let matcher = build_matcher(&self.paths);
+
+        let mut checked_ids = vec![to.id().clone()];
+        if self.remove_from_source {
+            checked_ids.push(from.id().clone());
+        }
+        if ws.check_immutable(checked_ids)? {
+            precondition!("revisions-immutable", "Revisions are immutable");
+        }
+
+        // the selected part of the other working copy's changes, relative to its parents
+        let from_tree = from.tree()?;
+        let parent_tree = rewrite::merge_commit_trees(tx.repo(), &from.parents())?;
+        let split_tree_id = rewrite::restore_tree(&from_tree, &parent_tree, matcher.as_ref())?;
+        if &split_tree_id == parent_tree.id() {
+            return Ok(MutationResult::Unchanged);
+        }
+        let split_tree = tx.repo().store().get_root_tree(&split_tree_id)?;
+
+        if self.remove_from_source {
+            let remainder_tree_id =
+                rewrite::restore_tree(&parent_tree, &from_tree, matcher.as_ref())?;
+            tx.mut_repo()
+                .rewrite_commit(&ws.settings, &from)
+                .set_tree_id(remainder_tree_id)
+                .write()?;
+
+            // the workspaces may be stacked, in which case this one has been rebased
+            if tx.repo().index().is_ancestor(from.id(), to.id()) {
+                let rebase_map = tx.mut_repo().rebase_descendants_return_map(&ws.settings)?;
+                let rebased_to_id = rebase_map
+                    .get(to.id())
+                    .ok_or(anyhow!("descendant working copy not found in rebase map"))?
+                    .clone();
+                to = tx.mut_repo().store().get_commit(&rebased_to_id)?;
+            }
+        }
+
+        let new_to_tree = to.tree()?.merge(&parent_tree, &split_tree)?;
+        tx.mut_repo()
+            .rewrite_commit(&ws.settings, &to)
+            .set_tree_id(new_to_tree.id().clone())
+            .write()?;
+
+        match ws.finish_transaction(
+            tx,
+            format!(
+                "{} changes from workspace {} into commit {}",
+                if self.remove_from_source {
+                    "move"
+                } else {
+                    "copy"
+                },
+                self.workspace_name,
+                to.id().hex()
+            ),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for WriteResolvedFile {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let tx = ws.start_transaction()?;
+
+        let commit = ws.resolve_single_change(&self.id)?;
+        if ws.check_immutable(vec![commit.id().clone()])? {
+            precondition!(
+                "revision-immutable",
+                "Revision {id} is immutable",
+                id = self.id.change.prefix
+            );
+        }
+
+        let repo_path = RepoPath::from_internal_string(&self.path.repo_path);
+        write_resolution(ws, tx, &commit, repo_path, self.content.as_bytes())
+    }
+}
+
+impl Mutation for ResolveConflict {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let tx = ws.start_transaction()?;
+
+        let commit = ws.resolve_single_change(&self.id)?;
+        if ws.check_immutable(vec![commit.id().clone()])? {
+            precondition!(
+                "revision-immutable",
+                "Revision {id} is immutable",
+                id = self.id.change.prefix
+            );
+        }
+
+        let repo_path = RepoPath::from_internal_string(&self.path.repo_path);
+        let value = commit.tree()?.path_value(repo_path);
+        let Some(file_merge) = value
+            .to_file_merge()
+            .filter(|file_merge| !value.is_resolved() && file_merge.num_sides() == 2)
+        else {
+            precondition!(
+                "conflict-unsupported",
+                "{path} is not a two-sided conflict between files",
+                path = &self.path.repo_path
+            );
+        };
+
+        // the choices were made against a query which may be out of date
+        let merge = queries::merge_two_sided(ws, repo_path, &file_merge)?;
+        let num_conflicts = merge
+            .hunks
+            .iter()
+            .filter(|hunk| !hunk.is_resolved())
+            .count();
+        if num_conflicts != self.choices.len() {
+            precondition!(
+                "conflict-changed",
+                "{path} has {num_conflicts} conflicts, but {num_choices} were resolved",
+                path = &self.path.repo_path,
+                num_conflicts = num_conflicts,
+                num_choices = self.choices.len()
+            );
+        }
+
+        let mut choices = self.choices.iter();
+        let mut content = Vec::new();
+        for hunk in &merge.hunks {
+            let sides = match hunk.as_resolved() {
+                Some(resolved) => vec![Some(resolved)],
+                None => match choices.next().expect("one choice per conflict") {
+                    ConflictSide::Base => vec![hunk.get_remove(0)],
+                    ConflictSide::Left => vec![hunk.get_add(0)],
+                    ConflictSide::Right => vec![hunk.get_add(1)],
+                    ConflictSide::Both => vec![hunk.get_add(0), hunk.get_add(1)],
+                },
+            };
+            for ContentHunk(text) in sides.into_iter().flatten() {
+                content.extend_from_slice(text);
+            }
+        }
+
+        write_resolution(ws, tx, &commit, repo_path, &content)
+    }
+}
+
+impl Mutation for TrackBranch {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        match self.name {
+            RefName::LocalBranch { branch_name, .. } => {
+                precondition!(
+                    "branch-local",
+                    "{branch} is a local branch and cannot be tracked",
+                    branch = branch_name
+                );
+            }
+            RefName::RemoteBranch {
+                branch_name,
+                remote_name,
+                ..
+            } => {
+                let mut tx = ws.start_transaction()?;
+
+                let remote_ref: &jj_lib::op_store::RemoteRef =
+                    ws.view().get_remote_branch(&branch_name, &remote_name);
+
+                if remote_ref.is_tracking() {
+                    precondition!(
+                        "branch-tracked",
+                        "{branch}@{remote} is already tracked",
+                        branch = branch_name,
+                        remote = remote_name
+                    );
+                }
+
+                tx.mut_repo()
+                    .track_remote_branch(&branch_name, &remote_name);
 
                 match ws.finish_transaction(tx, format!("track remote branch {}", branch_name))? {
                     Some(new_status) => Ok(MutationResult::Updated { new_status }),
@@ -483,7 +1346,12 @@ impl Mutation for UntrackBranch {
                     ws.view().get_remote_branch(&branch_name, &remote_name);
 
                 if !remote_ref.is_tracking() {
-                    precondition!("{branch_name}@{remote_name} is not tracked");
+                    precondition!(
+                        "branch-untracked",
+                        "{branch}@{remote} is not tracked",
+                        branch = branch_name,
+                        remote = remote_name
+                    );
                 }
 
                 tx.mut_repo()
@@ -513,14 +1381,23 @@ impl Mutation for MoveBranch {
                 remote_name,
                 ..
             } => {
-                precondition!("Branch is remote: {branch_name}@{remote_name}")
+                precondition!(
+                    "branch-remote",
+                    "Branch is remote: {branch}@{remote}",
+                    branch = branch_name,
+                    remote = remote_name
+                )
             }
             RefName::LocalBranch { branch_name, .. } => {
                 let to = ws.resolve_single_change(&self.to_id)?;
 
                 let old_target = ws.view().get_local_branch(&branch_name);
                 if old_target.is_absent() {
-                    precondition!("No such branch: {branch_name}");
+                    precondition!(
+                        "branch-not-found",
+                        "No such branch: {branch}",
+                        branch = branch_name
+                    );
                 }
 
                 tx.mut_repo()
@@ -540,42 +1417,223 @@ impl Mutation for MoveBranch {
 
 impl Mutation for PushRemote {
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
-        todo!("PushRemote")
+        let mut tx = ws.start_transaction()?;
+
+        let Some(git_repo) = ws.git_repo()? else {
+            precondition!("no-git-backend", "No git backend");
+        };
+
+        // like `jj git push --all`
+        let mut branch_updates = Vec::new();
+        for (branch_name, targets) in ws.view().local_remote_branches(&self.remote_name) {
+            match jj_lib::git::classify_branch_push_action(targets) {
+                BranchPushAction::Update(update) => {
+                    branch_updates.push((branch_name.to_owned(), update))
+                }
+                BranchPushAction::AlreadyMatches => (),
+                BranchPushAction::LocalConflicted => {
+                    precondition!(
+                        "branch-conflicted",
+                        "Branch {branch} is conflicted",
+                        branch = branch_name
+                    )
+                }
+                BranchPushAction::RemoteConflicted => {
+                    precondition!(
+                        "remote-branch-conflicted",
+                        "Branch {branch}@{remote} is conflicted",
+                        branch = branch_name,
+                        remote = &self.remote_name
+                    )
+                }
+            }
+        }
+
+        if branch_updates.is_empty() {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        let mut new_heads = Vec::new();
+        let mut force_pushed_branches = HashSet::new();
+        for (branch_name, update) in &branch_updates {
+            if let Some(new_target) = &update.new_target {
+                new_heads.push(new_target.clone());
+                if let Some(old_target) = &update.old_target {
+                    if !tx.repo().index().is_ancestor(old_target, new_target) {
+                        force_pushed_branches.insert(branch_name.clone());
+                    }
+                }
+            }
+        }
+
+        // validate the commits which would become visible on the remote, skipping any that some remote
+        // already has - otherwise the first push to a new remote would check all of history
+        let published = RevsetExpression::remote_branches(
+            StringPattern::everything(),
+            StringPattern::everything(),
+        );
+        let outgoing = ws.resolve_multiple(
+            ws.evaluate_revset_expr(published.range(&RevsetExpression::commits(new_heads)))?,
+        )?;
+
+        if let Some(max_size) = ws.settings.push_max_file_size() {
+            let large_files = find_large_files(ws, &outgoing, max_size)?;
+            if !large_files.is_empty() {
+                precondition!(
+                    "push-large-files",
+                    "Some files are larger than the push limit of {max_size} bytes: {files}",
+                    max_size = max_size,
+                    files = large_files.join(", ")
+                );
+            }
+        }
+
+        if ws.settings.push_scan_secrets() && !self.allow_secrets {
+            let findings = secrets::scan_commits(ws, &outgoing)?;
+            if !findings.is_empty() {
+                return Ok(MutationResult::SecretsFound {
+                    remote_name: self.remote_name.clone(),
+                    findings,
+                });
+            }
+        }
+
+        let hook_updates = branch_updates
+            .iter()
+            .map(|(branch_name, update)| {
+                let format_sha = |id: &Option<CommitId>| {
+                    id.as_ref().map_or_else(|| "0".repeat(40), |id| id.hex())
+                };
+                let local_ref = if update.new_target.is_some() {
+                    format!("refs/heads/{branch_name}")
+                } else {
+                    "(delete)".to_owned()
+                };
+                (
+                    local_ref,
+                    format_sha(&update.new_target),
+                    format!("refs/heads/{branch_name}"),
+                    format_sha(&update.old_target),
+                )
+            })
+            .collect_vec();
+        hooks::run_push_hook(ws, &git_repo, &self.remote_name, &hook_updates)?;
+
+        let targets = GitBranchPushTargets {
+            branch_updates,
+            force_pushed_branches,
+        };
+
+        let mut callbacks = RemoteCallbacks::default();
+        let mut get_ssh_keys_fn = get_ssh_keys;
+        let mut get_password_fn = |url: &str, username: &str| {
+            ws.request_credentials(url, Some(username))
+                .map(|(_, password)| password)
+        };
+        let mut get_username_password_fn = |url: &str| ws.request_credentials(url, None);
+        callbacks.get_ssh_keys = Some(&mut get_ssh_keys_fn);
+        callbacks.get_password = Some(&mut get_password_fn);
+        callbacks.get_username_password = Some(&mut get_username_password_fn);
+
+        jj_lib::git::push_branches(
+            tx.mut_repo(),
+            &git_repo,
+            &self.remote_name,
+            &targets,
+            callbacks,
+        )?;
+        tx.set_tag(PUSH_TAG.to_owned(), self.remote_name.clone());
+
+        match ws.finish_transaction(
+            tx,
+            format!("push all branches to git remote {}", self.remote_name),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
     }
 }
 
 impl Mutation for FetchRemote {
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut branch_patterns = Vec::new();
+        for pattern in &self.branch_patterns {
+            match StringPattern::parse(pattern) {
+                Ok(branch_pattern) => branch_patterns.push(branch_pattern),
+                Err(err) => precondition!(
+                    "invalid-branch-pattern",
+                    "Invalid branch pattern {pattern}: {err}",
+                    pattern = pattern,
+                    err = err
+                ),
+            }
+        }
+        if branch_patterns.is_empty() {
+            branch_patterns.push(StringPattern::everything());
+        }
+
         let mut tx = ws.start_transaction()?;
 
         match ws.git_repo()? {
-            None => precondition!("No git backend"),
+            None => precondition!("no-git-backend", "No git backend"),
             Some(git_repo) => {
-                // XXX this would limit it to known branches
-                // let branch_names = ws
-                //     .view()
-                //     .remote_branches(&self.remote_name)
-                //     .map(|b| StringPattern::Exact(b.0.to_owned()))
-                //     .collect_vec();
-
-                let mut callbacks = RemoteCallbacks::default();
-                let mut get_ssh_keys_fn = get_ssh_keys;
-                callbacks.get_ssh_keys = Some(&mut get_ssh_keys_fn);
-
-                jj_lib::git::fetch(
-                    tx.mut_repo(),
-                    &git_repo,
-                    &self.remote_name,
-                    &[StringPattern::everything()],
-                    callbacks,
-                    &ws.settings.git_settings(),
-                )?;
+                let remote_names = if self.all_remotes {
+                    git_repo
+                        .remotes()?
+                        .iter()
+                        .flatten()
+                        .map(|name| name.to_owned())
+                        .collect_vec()
+                } else {
+                    vec![self.remote_name.clone()]
+                };
+
+                let old_heads = ws.view().heads().iter().cloned().collect_vec();
+
+                for remote_name in &remote_names {
+                    let mut callbacks = RemoteCallbacks::default();
+                    let mut get_ssh_keys_fn = get_ssh_keys;
+                    let mut get_password_fn = |url: &str, username: &str| {
+                        ws.request_credentials(url, Some(username))
+                            .map(|(_, password)| password)
+                    };
+                    let mut get_username_password_fn =
+                        |url: &str| ws.request_credentials(url, None);
+                    callbacks.get_ssh_keys = Some(&mut get_ssh_keys_fn);
+                    callbacks.get_password = Some(&mut get_password_fn);
+                    callbacks.get_username_password = Some(&mut get_username_password_fn);
+
+                    jj_lib::git::fetch(
+                        tx.mut_repo(),
+                        &git_repo,
+                        remote_name,
+                        &branch_patterns,
+                        callbacks,
+                        &ws.settings.git_settings(),
+                    )?;
+                }
 
-                match ws.finish_transaction(
+                let result = ws.finish_transaction(
                     tx,
-                    format!("fetch from git remote(s) {}", self.remote_name),
-                )? {
-                    Some(new_status) => Ok(MutationResult::Updated { new_status }),
+                    format!("fetch from git remote(s) {}", remote_names.join(", ")),
+                )?;
+
+                // a fetch whose refs weren't committed shouldn't count as recent
+                for remote_name in &remote_names {
+                    if let Err(err) = ws.store.set_last_fetch(remote_name, Utc::now()) {
+                        log::warn!("failed to record fetch time: {err:#}");
+                    }
+                }
+
+                match result {
+                    Some(new_status) => {
+                        let (new_commits, incoming) = find_incoming(ws, &remote_names, old_heads)?;
+                        Ok(MutationResult::Fetched {
+                            new_status,
+                            new_commits,
+                            incoming,
+                        })
+                    }
                     None => Ok(MutationResult::Unchanged),
                 }
             }
@@ -583,6 +1641,161 @@ impl Mutation for FetchRemote {
     }
 }
 
+// commits which weren't visible before a fetch, in total and by the remote branches which brought them in
+fn find_incoming(
+    ws: &WorkspaceSession,
+    remote_names: &[String],
+    old_heads: Vec<CommitId>,
+) -> Result<(usize, Vec<IncomingCommits>)> {
+    let old_visible = RevsetExpression::commits(old_heads);
+    let new_commits = ws
+        .evaluate_revset_expr(old_visible.range(&RevsetExpression::visible_heads()))?
+        .iter()
+        .count();
+
+    let mut incoming = Vec::new();
+    for remote_name in remote_names {
+        for (branch_name, remote_ref) in ws.view().remote_branches(remote_name) {
+            let Some(id) = remote_ref.target.as_normal() else {
+                continue;
+            };
+
+            let count = ws
+                .evaluate_revset_expr(old_visible.range(&RevsetExpression::commit(id.clone())))?
+                .iter()
+                .count();
+            if count == 0 {
+                continue;
+            }
+
+            let branch = ws.branches_index().get(id).iter().find(|ref_name| {
+                matches!(ref_name, RefName::RemoteBranch { branch_name: name, remote_name: remote, .. } if name == branch_name && remote == remote_name)
+            });
+            if let Some(branch) = branch {
+                incoming.push(IncomingCommits {
+                    branch: branch.clone(),
+                    count,
+                    head: ws.format_header(&ws.get_commit(id)?, None)?,
+                });
+            }
+        }
+    }
+
+    Ok((new_commits, incoming))
+}
+
+impl Mutation for AddGitRemote {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let Some(git_repo) = ws.git_repo()? else {
+            precondition!("no-git-backend", "No git backend");
+        };
+
+        if self.remote_name == REMOTE_NAME_FOR_LOCAL_GIT_REPO {
+            precondition!(
+                "remote-reserved",
+                "The remote name {remote} is reserved",
+                remote = &self.remote_name
+            );
+        }
+
+        if git_repo.find_remote(&self.remote_name).is_ok() {
+            precondition!(
+                "remote-exists",
+                "Remote {remote} already exists",
+                remote = &self.remote_name
+            );
+        }
+
+        // no operation is recorded, because nothing in the repo has changed yet
+        jj_lib::git::add_remote(&git_repo, &self.remote_name, &self.url)?;
+        ws.notify_remotes_changed()?;
+
+        Ok(MutationResult::Unchanged)
+    }
+}
+
+impl Mutation for RemoveGitRemote {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let Some(git_repo) = ws.git_repo()? else {
+            precondition!("no-git-backend", "No git backend");
+        };
+
+        if git_repo.find_remote(&self.remote_name).is_err() {
+            precondition!(
+                "remote-missing",
+                "No remote named {remote}",
+                remote = &self.remote_name
+            );
+        }
+
+        jj_lib::git::remove_remote(tx.mut_repo(), &git_repo, &self.remote_name)?;
+
+        let result =
+            ws.finish_transaction(tx, format!("remove git remote {}", self.remote_name))?;
+        ws.notify_remotes_changed()?;
+
+        match result {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for RenameGitRemote {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let Some(git_repo) = ws.git_repo()? else {
+            precondition!("no-git-backend", "No git backend");
+        };
+
+        if git_repo.find_remote(&self.old_name).is_err() {
+            precondition!(
+                "remote-missing",
+                "No remote named {remote}",
+                remote = &self.old_name
+            );
+        }
+
+        if self.new_name == REMOTE_NAME_FOR_LOCAL_GIT_REPO {
+            precondition!(
+                "remote-reserved",
+                "The remote name {remote} is reserved",
+                remote = &self.new_name
+            );
+        }
+
+        if git_repo.find_remote(&self.new_name).is_ok() {
+            precondition!(
+                "remote-exists",
+                "Remote {remote} already exists",
+                remote = &self.new_name
+            );
+        }
+
+        jj_lib::git::rename_remote(tx.mut_repo(), &git_repo, &self.old_name, &self.new_name)?;
+
+        let result = ws.finish_transaction(
+            tx,
+            format!("rename git remote {} to {}", self.old_name, self.new_name),
+        )?;
+
+        if let Some(last_fetch) = ws.store.last_fetch(&self.old_name) {
+            if let Err(err) = ws.store.set_last_fetch(&self.new_name, last_fetch) {
+                log::warn!("failed to record fetch time: {err:#}");
+            }
+        }
+        ws.notify_remotes_changed()?;
+
+        match result {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
 // this is another case where it would be nice if we could reuse jj-cli's error messages
 impl Mutation for UndoOperation {
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
@@ -590,13 +1803,26 @@ impl Mutation for UndoOperation {
         let mut parent_ops = head_op.parents();
 
         let Some(parent_op) = parent_ops.next().transpose()? else {
-            precondition!("Cannot undo repo initialization");
+            precondition!("undo-initialization", "Cannot undo repo initialization");
         };
 
         if parent_ops.next().is_some() {
-            precondition!("Cannot undo a merge operation");
+            precondition!("undo-merge", "Cannot undo a merge operation");
         };
 
+        // the remote still has whatever was pushed, so local branches will no longer match it
+        let pushed_remote = head_op.metadata().tags.get(PUSH_TAG).cloned();
+        if let Some(remote_name) = &pushed_remote {
+            if !self.allow_remote {
+                precondition!(
+                    "undo-pushed",
+                    "Operation {operation} pushed to {remote}, which won't be undone. After undoing it, push to {remote} again to revert the remote, or fetch to restore what was pushed.",
+                    operation = short_operation_hash(head_op.id()),
+                    remote = remote_name
+                );
+            }
+        }
+
         let mut tx = ws.start_transaction()?;
         let repo_loader = tx.base_repo().loader();
         let head_repo = repo_loader.load_at(&head_op)?;
@@ -605,8 +1831,28 @@ impl Mutation for UndoOperation {
         let restored_view = tx.repo().view().store_view().clone();
         tx.mut_repo().set_view(restored_view);
 
+        // keep tracking the remote's actual state, so that the next push can force it back
+        if let Some(remote_name) = &pushed_remote {
+            let branch_names = head_repo
+                .view()
+                .remote_branches(remote_name)
+                .chain(parent_repo.view().remote_branches(remote_name))
+                .map(|(branch_name, _)| branch_name.to_owned())
+                .unique()
+                .collect_vec();
+            for branch_name in branch_names {
+                let remote_ref = head_repo
+                    .view()
+                    .get_remote_branch(&branch_name, remote_name)
+                    .clone();
+                tx.mut_repo()
+                    .set_remote_branch(&branch_name, remote_name, remote_ref);
+            }
+        }
+
         match ws.finish_transaction(tx, format!("undo operation {}", head_op.id().hex()))? {
             Some(new_status) => {
+                ws.redo_target = Some((head_op.id().clone(), ws.repo().op_id().clone()));
                 let working_copy = ws.get_commit(ws.wc_id())?;
                 let new_selection = ws.format_header(&working_copy, None)?;
                 Ok(MutationResult::UpdatedSelection {
@@ -619,6 +1865,560 @@ impl Mutation for UndoOperation {
     }
 }
 
+impl Mutation for RedoOperation {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        // any other operation since the undo, including a snapshot, means there's nothing to redo
+        let Some((undone_id, _)) = ws
+            .redo_target
+            .take()
+            .filter(|(_, undo_id)| undo_id == ws.repo().op_id())
+        else {
+            precondition!("redo-unavailable", "Nothing to redo");
+        };
+
+        let undone_op = op_walk::resolve_op_with_repo(ws.repo(), &undone_id.hex())?;
+        restore_view(
+            ws,
+            &undone_op,
+            format!("redo operation {}", undone_id.hex()),
+        )
+    }
+}
+
+impl Mutation for CreateCheckpoint {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let label = self.label.trim();
+        if label.is_empty() {
+            precondition!("checkpoint-unlabelled", "Checkpoints must have a label");
+        }
+
+        if ws.store.checkpoint(label).is_some() {
+            precondition!(
+                "checkpoint-exists",
+                "Checkpoint {label} already exists",
+                label = label
+            );
+        }
+
+        ws.store.add_checkpoint(Checkpoint {
+            label: label.to_owned(),
+            op_id: ws.repo().op_id().hex(),
+            created: Utc::now(),
+            autosave: false,
+        })?;
+
+        Ok(MutationResult::Unchanged)
+    }
+}
+
+impl Mutation for SetAnnotation {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let Some(commit) = ws.resolve_optional_change(&self.id.change)? else {
+            precondition!(
+                "revision-missing",
+                "Revision {change} not found",
+                change = &self.id.change.prefix
+            );
+        };
+        // the id may have been a prefix, which could become ambiguous later
+        let change_id = ws.format_id(&commit).change.hex;
+
+        let mut links = Vec::new();
+        for link in self.links {
+            let url = link.url.trim();
+            if url.is_empty() {
+                precondition!("annotation-link-empty", "Links must have a URL");
+            }
+            let label = match link.label.trim() {
+                "" => url,
+                label => label,
+            };
+            links.push(store::AnnotationLink {
+                label: label.to_owned(),
+                url: url.to_owned(),
+            });
+        }
+
+        let note = self.note.trim();
+        let annotation = (!note.is_empty() || !links.is_empty()).then(|| Annotation {
+            note: note.to_owned(),
+            links,
+            updated: Utc::now(),
+        });
+        ws.store.set_annotation(&change_id, annotation)?;
+
+        Ok(MutationResult::Unchanged)
+    }
+}
+
+impl Mutation for SetReviewState {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let Some(commit) = ws.resolve_optional_change(&self.id.change)? else {
+            precondition!(
+                "revision-missing",
+                "Revision {change} not found",
+                change = &self.id.change.prefix
+            );
+        };
+        let change_id = ws.format_id(&commit).change.hex;
+
+        let url = self
+            .url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty());
+        if let Some(url) = url {
+            if forge::parse_review_url(url).is_none() {
+                precondition!(
+                    "review-url-unsupported",
+                    "{url} is not a pull request, merge request or Gerrit change",
+                    url = url
+                );
+            }
+        }
+
+        let review = self.state.map(|state| Review {
+            state,
+            url: url.map(str::to_owned),
+            updated: Utc::now(),
+        });
+        ws.store.set_review(&change_id, review)?;
+
+        Ok(MutationResult::Unchanged)
+    }
+}
+
+impl Mutation for SyncReviewStates {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut updates = Vec::new();
+        let mut errors = Vec::new();
+
+        // landed is final, so there's no need to ask about those again
+        let pending: Vec<_> = ws
+            .store
+            .reviews()
+            .iter()
+            .filter(|(_, review)| review.state != ReviewState::Landed)
+            .filter_map(|(change_id, review)| {
+                let url = review.url.as_deref()?;
+                Some((change_id, review, url, forge::parse_review_url(url)?))
+            })
+            .collect();
+
+        // the requests are made together, so the writer waits for the slowest rather than their sum
+        let statuses = future::join_all(
+            pending
+                .iter()
+                .map(|(_, _, _, review_url)| forge::query_review_status(review_url)),
+        )
+        .block_on();
+
+        for ((change_id, review, url, _), status) in pending.iter().zip(statuses) {
+            match status {
+                Ok(status) => {
+                    // closed without merging says nothing about our review, and todo is still open
+                    let state = if status.is_merged {
+                        ReviewState::Landed
+                    } else if !status.is_open {
+                        continue;
+                    } else if status.is_approved {
+                        ReviewState::Approved
+                    } else if review.state == ReviewState::Todo {
+                        continue;
+                    } else {
+                        ReviewState::InReview
+                    };
+
+                    if state != review.state {
+                        updates.push((
+                            (*change_id).clone(),
+                            Review {
+                                state,
+                                url: review.url.clone(),
+                                updated: Utc::now(),
+                            },
+                        ));
+                    }
+                }
+                Err(err) => errors.push(format!("{url}: {err:#}")),
+            }
+        }
+
+        for (change_id, review) in updates {
+            ws.store.set_review(&change_id, Some(review))?;
+        }
+
+        if !errors.is_empty() {
+            precondition!(
+                "review-sync-failed",
+                "Failed to sync {count} reviews:\n{errors}",
+                count = errors.len(),
+                errors = errors.join("\n")
+            );
+        }
+
+        Ok(MutationResult::Unchanged)
+    }
+}
+
+impl Mutation for SetTrunk {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let revset = self.revset.trim();
+
+        match ws.evaluate_revset_str(revset) {
+            Ok(evaluated) => {
+                if evaluated.iter().next().is_none() {
+                    precondition!(
+                        "trunk-empty",
+                        "{revset} doesn't resolve to any revisions",
+                        revset = revset
+                    );
+                }
+            }
+            Err(err) => {
+                precondition!(
+                    "trunk-invalid",
+                    "Invalid revset {revset}: {err}",
+                    revset = revset,
+                    err = err
+                );
+            }
+        }
+
+        // takes effect when the workspace is reloaded, along with the rest of its config
+        config::write_config_value(
+            &ws.repo_config_path(),
+            &["revset-aliases", "trunk()"],
+            revset,
+        )?;
+
+        Ok(MutationResult::Unchanged)
+    }
+}
+
+impl Mutation for SetDefaultQuery {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let query = self
+            .query
+            .map(|query| query.trim().to_owned())
+            .filter(|query| !query.is_empty());
+
+        if let Some(query) = &query {
+            if let Err(err) = ws.evaluate_revset_str(query) {
+                precondition!(
+                    "default-query-invalid",
+                    "Invalid query {query}: {err}",
+                    query = query,
+                    err = err
+                );
+            }
+        }
+
+        ws.store.set_default_query(query)?;
+
+        Ok(MutationResult::Unchanged)
+    }
+}
+
+impl Mutation for SaveNamedQuery {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            precondition!("query-name-empty", "Queries must have a name");
+        }
+
+        let revset = self.revset.trim();
+        if let Err(err) = ws.evaluate_revset_str(revset) {
+            precondition!(
+                "named-query-invalid",
+                "Invalid query {query}: {err}",
+                query = revset,
+                err = err
+            );
+        }
+
+        ws.store.save_named_query(store::NamedQuery {
+            name: name.to_owned(),
+            revset: revset.to_owned(),
+        })?;
+
+        Ok(MutationResult::Unchanged)
+    }
+}
+
+impl Mutation for DeleteNamedQuery {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if !ws.store.delete_named_query(&self.name)? {
+            precondition!("query-missing", "No query named {name}", name = &self.name);
+        }
+
+        Ok(MutationResult::Unchanged)
+    }
+}
+
+impl Mutation for RestoreCheckpoint {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let Some(checkpoint) = ws.store.checkpoint(&self.label).cloned() else {
+            precondition!(
+                "checkpoint-missing",
+                "Checkpoint {label} not found",
+                label = &self.label
+            );
+        };
+
+        // the operation may have been garbage-collected since the checkpoint was made
+        let Ok(target_op) = op_walk::resolve_op_with_repo(ws.repo(), &checkpoint.op_id) else {
+            precondition!(
+                "checkpoint-unavailable",
+                "Operation for checkpoint {label} is no longer available",
+                label = &checkpoint.label
+            );
+        };
+
+        restore_view(
+            ws,
+            &target_op,
+            format!("restore checkpoint {}", checkpoint.label),
+        )
+    }
+}
+
+impl Mutation for RestoreOperation {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let Ok(target_op) = op_walk::resolve_op_with_repo(ws.repo(), &self.operation_id) else {
+            precondition!(
+                "operation-missing",
+                "Operation {id} not found",
+                id = &self.operation_id
+            );
+        };
+
+        // unlike undo, this works across merges and any number of steps
+        restore_view(
+            ws,
+            &target_op,
+            format!("restore to operation {}", target_op.id().hex()),
+        )
+    }
+}
+
+impl Mutation for RestoreFileFromOperation {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let Ok(target_op) = op_walk::resolve_op_with_repo(ws.repo(), &self.operation_id) else {
+            precondition!(
+                "operation-missing",
+                "Operation {id} not found",
+                id = &self.operation_id
+            );
+        };
+
+        // the file as this workspace's working copy had it, which may never have been committed
+        let Some(old_wc_id) = target_op.view()?.get_wc_commit_id(ws.id()).cloned() else {
+            precondition!(
+                "workspace-missing",
+                "This workspace didn't exist at operation {id}",
+                id = short_operation_hash(target_op.id())
+            );
+        };
+
+        let wc = ws.get_commit(ws.wc_id())?;
+        if ws.check_immutable(vec![wc.id().clone()])? {
+            precondition!(
+                "revision-immutable",
+                "Revision {id} is immutable",
+                id = ws.format_id(&wc).change.prefix
+            );
+        }
+
+        // a directory is restored along with everything in it
+        let matcher = PrefixMatcher::new([RepoPath::from_internal_string(&self.path.repo_path)]);
+        let old_tree = ws.get_commit(&old_wc_id)?.tree()?;
+        let new_tree_id = rewrite::restore_tree(&old_tree, &wc.tree()?, &matcher)?;
+        if &new_tree_id == wc.tree_id() {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        tx.mut_repo()
+            .rewrite_commit(&ws.settings, &wc)
+            .set_tree_id(new_tree_id)
+            .write()?;
+        tx.mut_repo().rebase_descendants(&ws.settings)?;
+
+        match ws.finish_transaction(
+            tx,
+            format!(
+                "restore {} from operation {}",
+                self.path.repo_path,
+                short_operation_hash(target_op.id())
+            ),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+fn restore_view(
+    ws: &mut WorkspaceSession,
+    target_op: &Operation,
+    description: String,
+) -> Result<MutationResult> {
+    let mut tx = ws.start_transaction()?;
+    tx.mut_repo()
+        .set_view(target_op.view()?.store_view().clone());
+
+    match ws.finish_transaction(tx, description)? {
+        Some(new_status) => {
+            let working_copy = ws.get_commit(ws.wc_id())?;
+            let new_selection = ws.format_header(&working_copy, None)?;
+            Ok(MutationResult::UpdatedSelection {
+                new_status,
+                new_selection,
+            })
+        }
+        None => Ok(MutationResult::Unchanged),
+    }
+}
+
+// replaces a conflicted path with a file, keeping the executable bit if any side had it
+fn write_resolution(
+    ws: &mut WorkspaceSession,
+    mut tx: Transaction,
+    commit: &Commit,
+    repo_path: &RepoPath,
+    content: &[u8],
+) -> Result<MutationResult> {
+    let executable = commit.tree()?.path_value(repo_path).adds().any(|value| {
+        matches!(
+            value,
+            Some(TreeValue::File {
+                executable: true,
+                ..
+            })
+        )
+    });
+    let id = ws.repo().store().write_file(repo_path, &mut &content[..])?;
+
+    let mut tree_builder = MergedTreeBuilder::new(commit.tree_id().clone());
+    tree_builder.set_or_remove(
+        repo_path.to_owned(),
+        Merge::normal(TreeValue::File { id, executable }),
+    );
+    let new_tree_id = tree_builder.write_tree(ws.repo().store())?;
+
+    tx.mut_repo()
+        .rewrite_commit(&ws.settings, commit)
+        .set_tree_id(new_tree_id)
+        .write()?;
+
+    tx.mut_repo().rebase_descendants(&ws.settings)?;
+
+    match ws.finish_transaction(
+        tx,
+        format!(
+            "resolve {} in commit {}",
+            repo_path.as_internal_file_string(),
+            commit.id().hex()
+        ),
+    )? {
+        Some(new_status) => Ok(MutationResult::Updated { new_status }),
+        None => Ok(MutationResult::Unchanged),
+    }
+}
+
+fn rebase_options(ws: &WorkspaceSession, skip_empty: Option<bool>) -> RebaseOptions {
+    let skip_empty = skip_empty.unwrap_or_else(|| ws.settings.rebase_skip_empty());
+    RebaseOptions {
+        empty: if skip_empty {
+            EmptyBehaviour::AbandonNewlyEmpty
+        } else {
+            EmptyBehaviour::Keep
+        },
+        ..Default::default()
+    }
+}
+
+// 1 if the target was abandoned for being empty, otherwise 0
+fn rebase_target(
+    ws: &WorkspaceSession,
+    tx: &mut Transaction,
+    target: &Commit,
+    parents: &[Commit],
+    options: &RebaseOptions,
+) -> Result<usize> {
+    let rebased =
+        rewrite::rebase_commit_with_options(&ws.settings, tx.mut_repo(), target, parents, options)?;
+    Ok(match rebased {
+        RebasedCommit::Rewritten(_) => 0,
+        RebasedCommit::Abandoned { .. } => 1,
+    })
+}
+
+// the nearest ancestors of a commit which aren't being moved, which its moved parents are replaced by
+fn remaining_parents(
+    ws: &WorkspaceSession,
+    commit: &Commit,
+    targets: &HashSet<CommitId>,
+) -> Result<Vec<CommitId>> {
+    let mut remaining_ids = Vec::new();
+    let mut parent_ids = commit.parent_ids().to_vec();
+    while !parent_ids.is_empty() {
+        let parent_id = parent_ids.remove(0);
+        if targets.contains(&parent_id) {
+            parent_ids.extend_from_slice(ws.get_commit(&parent_id)?.parent_ids());
+        } else if !remaining_ids.contains(&parent_id) {
+            remaining_ids.push(parent_id);
+        }
+    }
+
+    // some of the remaining parents may be ancestors of others
+    let remaining = RevsetExpression::commits(remaining_ids.clone()).minus(
+        &RevsetExpression::commits(remaining_ids)
+            .parents()
+            .ancestors(),
+    );
+    Ok(ws.evaluate_revset_expr(remaining)?.iter().collect_vec())
+}
+
+// a commit may have been rewritten several times in one transaction
+fn follow_rewrites(rewritten: &HashMap<CommitId, CommitId>, id: &CommitId) -> CommitId {
+    let mut current_id = id;
+    while let Some(next_id) = rewritten.get(current_id) {
+        current_id = next_id;
+    }
+    current_id.clone()
+}
+
+// abandoned commits are replaced by their parents, which have a different change id
+fn count_abandoned(tx: &Transaction, rebased: &HashMap<CommitId, CommitId>) -> Result<usize> {
+    let store = tx.repo().store();
+    let mut count = 0;
+    for (old_id, new_id) in rebased {
+        if store.get_commit(old_id)?.change_id() != store.get_commit(new_id)?.change_id() {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+fn rebase_result(
+    ws: &mut WorkspaceSession,
+    tx: Transaction,
+    description: String,
+    abandoned_empty: usize,
+) -> Result<MutationResult> {
+    match ws.finish_transaction(tx, description)? {
+        Some(new_status) if abandoned_empty > 0 => Ok(MutationResult::Rebased {
+            new_status,
+            abandoned_empty,
+        }),
+        Some(new_status) => Ok(MutationResult::Updated { new_status }),
+        None => Ok(MutationResult::Unchanged),
+    }
+}
+
 fn combine_messages(source: &Commit, destination: &Commit, abandon_source: bool) -> String {
     if abandon_source {
         if source.description().is_empty() {
@@ -640,6 +2440,38 @@ fn combine_branches(branch_names: &[impl Display]) -> String {
     }
 }
 
+// lists paths added or modified by the given commits which exceed a size limit
+fn find_large_files(
+    ws: &WorkspaceSession,
+    commits: &[Commit],
+    max_size: u64,
+) -> Result<Vec<String>> {
+    let git_repo = ws.git_repo()?;
+    let odb = git_repo.as_ref().map(|repo| repo.odb()).transpose()?;
+
+    let mut large_files = Vec::new();
+    for commit in commits {
+        let parent_tree = rewrite::merge_commit_trees(ws.repo(), &commit.parents())?;
+        let tree = commit.tree()?;
+        for (repo_path, diff) in parent_tree.diff(&tree, &EverythingMatcher) {
+            let (_, after) = diff?;
+            if let Some(Some(TreeValue::File { id, .. })) = after.as_resolved() {
+                let size = queries::file_size(ws, odb.as_ref(), &repo_path, id)?;
+                if size > max_size {
+                    large_files.push(format!(
+                        "{} ({} bytes, in commit {})",
+                        repo_path.as_internal_file_string(),
+                        size,
+                        &commit.id().hex()[..12]
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(large_files)
+}
+
 fn build_matcher(paths: &Vec<TreePath>) -> Box<dyn Matcher> {
     if paths.is_empty() {
         Box::new(EverythingMatcher)
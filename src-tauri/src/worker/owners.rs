@@ -0,0 +1,60 @@
+//! CODEOWNERS parsing, using gitignore-style patterns where the last matching rule wins
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use jj_lib::{gitignore::GitIgnoreFile, repo_path::RepoPath};
+
+/// Paths that github, gitlab and others look for, in precedence order
+pub const CODEOWNERS_PATHS: [&str; 4] = [
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+pub struct CodeOwners {
+    rules: Vec<(Arc<GitIgnoreFile>, Vec<String>)>,
+}
+
+impl CodeOwners {
+    pub fn parse(text: &str) -> Result<CodeOwners> {
+        let mut rules = Vec::new();
+
+        for line in text.lines() {
+            let line = line.trim();
+            // gitlab section headers are skipped rather than interpreted, and so is negation, which
+            // CODEOWNERS doesn't have; as a gitignore pattern, it would make the rule match nothing
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with('[')
+                || line.starts_with('!')
+            {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let Some(pattern) = words.next() else {
+                continue;
+            };
+            let owners = words
+                .take_while(|word| !word.starts_with('#'))
+                .map(|word| word.to_owned())
+                .collect();
+
+            let matcher = GitIgnoreFile::empty().chain("", pattern.as_bytes())?;
+            rules.push((matcher, owners));
+        }
+
+        Ok(CodeOwners { rules })
+    }
+
+    pub fn owners_of(&self, repo_path: &RepoPath) -> &[String] {
+        let path = repo_path.as_internal_file_string();
+        self.rules
+            .iter()
+            .rev()
+            .find(|(matcher, _)| matcher.matches(path))
+            .map_or(&[], |(_, owners)| owners.as_slice())
+    }
+}
@@ -0,0 +1,144 @@
+//! The commands which can currently be run, so that a command palette offers the same choices as the rules enforced by mutations
+
+use anyhow::Result;
+
+use crate::{
+    config::GGSettings,
+    gui_util::WorkspaceSession,
+    messages::{AvailableCommand, RefName, RevHeader, RevId},
+};
+
+pub fn list_available_commands(
+    ws: &WorkspaceSession,
+    ids: Vec<RevId>,
+) -> Result<Vec<AvailableCommand>> {
+    let mut headers = Vec::new();
+    for commit in ws.resolve_multiple_changes(ids)? {
+        headers.push(ws.format_header(&commit, None)?);
+    }
+
+    let mut commands = Vec::new();
+    match headers.as_slice() {
+        [] => (),
+        [header] => list_revision_commands(ws, header, &mut commands),
+        headers => {
+            commands.push(AvailableCommand::new("create_revision", "New merge"));
+            commands.push(AvailableCommand::new("duplicate_revisions", "Duplicate"));
+            if headers.iter().all(|header| !header.is_immutable) {
+                commands.push(AvailableCommand::new("abandon_revisions", "Abandon"));
+            }
+        }
+    }
+
+    list_repo_commands(ws, &mut commands)?;
+
+    Ok(commands)
+}
+
+// the same rules as the revision context menu, plus mutations which only the palette offers
+fn list_revision_commands(
+    ws: &WorkspaceSession,
+    header: &RevHeader,
+    commands: &mut Vec<AvailableCommand>,
+) {
+    let is_mutable = !header.is_immutable;
+    let has_single_parent = header.parent_ids.len() == 1;
+
+    commands.push(AvailableCommand::new("create_revision", "New child"));
+    if is_mutable && !header.is_working_copy {
+        commands.push(AvailableCommand::new(
+            "checkout_revision",
+            "Edit as working copy",
+        ));
+    }
+    if is_mutable {
+        commands.push(AvailableCommand::new("describe_revision", "Describe"));
+    }
+    commands.push(AvailableCommand::new("duplicate_revisions", "Duplicate"));
+    if is_mutable {
+        commands.push(AvailableCommand::new("abandon_revisions", "Abandon"));
+    }
+    if is_mutable && has_single_parent {
+        commands.push(AvailableCommand::new("move_changes", "Squash into parent"));
+        commands.push(AvailableCommand::new("copy_changes", "Restore from parent"));
+    }
+    if is_mutable {
+        commands.push(AvailableCommand::new("split_revision", "Split"));
+    }
+    commands.push(AvailableCommand::new("backout_revision", "Back out"));
+    if is_mutable && ws.settings.signing_backend().is_some() {
+        commands.push(AvailableCommand::new("sign_revision", "Sign"));
+    }
+    commands.push(AvailableCommand::new("set_annotation", "Annotate"));
+    commands.push(AvailableCommand::new(
+        "set_review_state",
+        "Set review state",
+    ));
+
+    for branch in &header.branches {
+        match branch {
+            RefName::RemoteBranch {
+                branch_name,
+                remote_name,
+                is_tracked: false,
+                ..
+            } => commands.push(
+                AvailableCommand::new("track_branch", format!("Track {branch_name}@{remote_name}"))
+                    .with_branch(branch),
+            ),
+            RefName::RemoteBranch {
+                branch_name,
+                remote_name,
+                is_tracked: true,
+                ..
+            } => commands.push(
+                AvailableCommand::new(
+                    "untrack_branch",
+                    format!("Untrack {branch_name}@{remote_name}"),
+                )
+                .with_branch(branch),
+            ),
+            RefName::LocalBranch {
+                branch_name,
+                is_tracking: true,
+                ..
+            } => commands.push(
+                AvailableCommand::new("untrack_branch", format!("Untrack {branch_name}"))
+                    .with_branch(branch),
+            ),
+            _ => (),
+        }
+    }
+}
+
+fn list_repo_commands(ws: &WorkspaceSession, commands: &mut Vec<AvailableCommand>) -> Result<()> {
+    for remote in ws.format_git_remotes()? {
+        commands.push(
+            AvailableCommand::new("fetch_remote", format!("Fetch from {}", remote.name))
+                .with_remote(&remote.name),
+        );
+        commands.push(
+            AvailableCommand::new("push_remote", format!("Push to {}", remote.name))
+                .with_remote(&remote.name),
+        );
+    }
+
+    // merge operations and the root operation can't be undone
+    if ws.repo().operation().parent_ids().len() == 1 {
+        commands.push(AvailableCommand::new("undo_operation", "Undo"));
+    }
+    commands.push(AvailableCommand::new(
+        "create_checkpoint",
+        "Create checkpoint",
+    ));
+    if ws
+        .store
+        .reviews()
+        .values()
+        .any(|review| review.url.is_some())
+    {
+        commands.push(AvailableCommand::new("sync_review_states", "Sync reviews"));
+    }
+
+    Ok(())
+}
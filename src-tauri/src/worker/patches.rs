@@ -0,0 +1,310 @@
+//! Import of patches exchanged by email, as written by `git format-patch` or collected in an mbox
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset};
+use git2::{Diff, Oid, Repository};
+use jj_lib::{
+    backend::{Signature, Timestamp, TreeId},
+    object_id::ObjectId,
+};
+use regex::Regex;
+
+use crate::messages::timestamp_from_datetime;
+
+/// one message from a patch series
+pub struct EmailPatch {
+    pub subject: String,
+    pub body: String,
+    pub diff: String,
+    author_name: Option<String>,
+    author_email: Option<String>,
+    date: Option<DateTime<FixedOffset>>,
+    number: Option<usize>,
+}
+
+impl EmailPatch {
+    /// the commit message, as `git am` would write it
+    pub fn description(&self) -> String {
+        if self.body.is_empty() {
+            format!("{}\n", self.subject)
+        } else {
+            format!("{}\n\n{}\n", self.subject, self.body)
+        }
+    }
+
+    /// None if the sender couldn't be parsed, in which case the user is the author
+    pub fn author(&self) -> Option<Signature> {
+        let email = self.author_email.clone()?;
+        let timestamp = match self.date {
+            Some(date) => timestamp_from_datetime(&date),
+            None => Timestamp::now(),
+        };
+
+        Some(Signature {
+            name: self.author_name.clone().unwrap_or_else(|| email.clone()),
+            email,
+            timestamp,
+        })
+    }
+}
+
+/// reads an mbox file, a maildir, or a directory of `git format-patch` output, in series order
+pub fn read_patches(path: &Path) -> Result<Vec<EmailPatch>> {
+    let mut files = Vec::new();
+    if path.is_dir() {
+        // maildirs keep messages in subdirectories; format-patch writes them directly
+        let maildir = ["cur", "new"].map(|name| path.join(name));
+        let dirs = if maildir.iter().any(|dir| dir.is_dir()) {
+            maildir.into_iter().filter(|dir| dir.is_dir()).collect()
+        } else {
+            vec![path.to_owned()]
+        };
+
+        for dir in dirs {
+            for entry in fs::read_dir(&dir).with_context(|| format!("read {}", dir.display()))? {
+                let entry = entry?;
+                let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+                if entry.file_type()?.is_file() && !is_hidden {
+                    files.push(entry.path());
+                }
+            }
+        }
+        files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    } else {
+        files.push(path.to_owned());
+    }
+
+    let mut patches = Vec::new();
+    for file in files {
+        let bytes = fs::read(&file).with_context(|| format!("read {}", file.display()))?;
+        let text = String::from_utf8_lossy(&bytes).replace("\r\n", "\n");
+        patches.extend(split_mbox(&text).into_iter().filter_map(parse_message));
+    }
+
+    // maildir filenames don't follow the series, but subjects like [PATCH 2/3] do
+    if patches.iter().all(|patch| patch.number.is_some()) {
+        patches.sort_by_key(|patch| patch.number);
+    }
+
+    Ok(patches)
+}
+
+/// applies a patch to a tree in the git store, returning the resulting tree
+pub fn apply_patch(
+    git_repo: &Repository,
+    base_tree_id: &TreeId,
+    patch: &EmailPatch,
+) -> Result<TreeId, git2::Error> {
+    let base_tree = git_repo.find_tree(Oid::from_bytes(base_tree_id.as_bytes())?)?;
+    let diff = Diff::from_buffer(patch.diff.as_bytes())?;
+    let mut index = git_repo.apply_to_tree(&base_tree, &diff, None)?;
+    let new_tree_id = index.write_tree_to(git_repo)?;
+    Ok(TreeId::new(new_tree_id.as_bytes().to_vec()))
+}
+
+// messages are separated by "From " lines; a file with none is a single message
+fn split_mbox(text: &str) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current: Option<String> = None;
+    let mut previous_blank = true;
+
+    for line in text.lines() {
+        if line.starts_with("From ") && previous_blank {
+            messages.extend(current.take());
+            current = Some(String::new());
+        } else {
+            let message = current.get_or_insert_with(String::new);
+            // mboxrd escapes body lines which would look like separators
+            let unescaped = line
+                .strip_prefix('>')
+                .filter(|rest| rest.trim_start_matches('>').starts_with("From "));
+            message.push_str(unescaped.unwrap_or(line));
+            message.push('\n');
+        }
+        previous_blank = line.is_empty();
+    }
+    messages.extend(current);
+
+    messages
+}
+
+// messages without a diff, such as cover letters, are skipped
+fn parse_message(message: String) -> Option<EmailPatch> {
+    let (header_text, body) = message.split_once("\n\n").unwrap_or((message.as_str(), ""));
+    let mut headers = parse_headers(header_text);
+
+    // git puts the real author at the top of the body when it differs from the sender
+    let mut body = body;
+    if body.starts_with("From: ") || body.starts_with("Date: ") {
+        if let Some((in_body_text, rest)) = body.split_once("\n\n") {
+            headers.extend(parse_headers(in_body_text));
+            body = rest;
+        }
+    }
+
+    let diff_start = if body.starts_with("diff --git ") {
+        0
+    } else {
+        body.find("\ndiff --git ")? + 1
+    };
+    let (message_text, diff_text) = body.split_at(diff_start);
+
+    // everything from the "---" line to the diff is a diffstat or commentary
+    let message_text = match message_text.find("\n---\n") {
+        Some(end) => &message_text[..end],
+        None if message_text.starts_with("---\n") => "",
+        None => message_text,
+    };
+
+    // a trailing "-- " line begins the signature, usually the git version
+    let diff = match diff_text.find("\n-- \n") {
+        Some(end) => &diff_text[..end + 1],
+        None => diff_text,
+    };
+
+    let mut subject = String::new();
+    let mut from = None;
+    let mut date = None;
+    for (name, value) in headers {
+        match name.as_str() {
+            "subject" => subject = value,
+            "from" => from = Some(value),
+            "date" => date = DateTime::parse_from_rfc2822(&value).ok(),
+            _ => (),
+        }
+    }
+
+    let (number, subject) = strip_subject_tags(&subject);
+    let (author_name, author_email) = match from {
+        Some(from) => parse_address(&from),
+        None => (None, None),
+    };
+
+    Some(EmailPatch {
+        subject,
+        body: message_text.trim().to_owned(),
+        diff: diff.to_owned(),
+        author_name,
+        author_email,
+        date,
+        number,
+    })
+}
+
+// names are lowercased; folded lines are joined and encoded words decoded
+fn parse_headers(text: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_owned()));
+        }
+    }
+
+    headers
+        .into_iter()
+        .map(|(name, value)| (name, decode_words(&value)))
+        .collect()
+}
+
+// "[PATCH v2 3/5] subject" => (Some(3), "subject")
+fn strip_subject_tags(subject: &str) -> (Option<usize>, String) {
+    let numbering = Regex::new(r"(\d+)/\d+").expect("valid regex");
+    let mut number = None;
+    let mut rest = subject.trim();
+
+    while let Some(tag_end) = rest.strip_prefix('[').and_then(|tag| tag.find(']')) {
+        let tag = &rest[1..tag_end + 1];
+        if let Some(captures) = numbering.captures(tag) {
+            number = captures[1].parse().ok();
+        }
+        rest = rest[tag_end + 2..].trim_start();
+    }
+
+    (number, rest.to_owned())
+}
+
+// "Name <email>", "\"Name\" <email>" or a bare address
+fn parse_address(from: &str) -> (Option<String>, Option<String>) {
+    match from.rsplit_once('<') {
+        Some((name, email)) => {
+            let name = name.trim().trim_matches('"').trim();
+            let email = email.trim_end_matches('>').trim();
+            (
+                (!name.is_empty()).then(|| name.to_owned()),
+                (!email.is_empty()).then(|| email.to_owned()),
+            )
+        }
+        None => (None, (!from.is_empty()).then(|| from.to_owned())),
+    }
+}
+
+// RFC 2047 encoded words, which git uses for non-ascii names and subjects
+fn decode_words(value: &str) -> String {
+    let encoded_word = Regex::new(r"=\?([^?]+)\?([QqBb])\?([^?]*)\?=").expect("valid regex");
+    // whitespace between adjacent encoded words is not part of the text
+    let adjacent = Regex::new(r"\?=\s+=\?").expect("valid regex");
+    let value = adjacent.replace_all(value, "?==?");
+
+    encoded_word
+        .replace_all(&value, |captures: &regex::Captures| -> String {
+            let bytes = if captures[2].eq_ignore_ascii_case("q") {
+                decode_q(&captures[3])
+            } else {
+                decode_base64(&captures[3])
+            };
+            match bytes {
+                Some(bytes) if captures[1].eq_ignore_ascii_case("iso-8859-1") => {
+                    bytes.into_iter().map(char::from).collect()
+                }
+                Some(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                None => captures[0].to_owned(),
+            }
+        })
+        .into_owned()
+}
+
+fn decode_q(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut input = text.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'_' => bytes.push(b' '),
+            b'=' => {
+                let hex = [input.next()?, input.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    Some(bytes)
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in text.bytes().take_while(|byte| *byte != b'=') {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
@@ -0,0 +1,136 @@
+//! Timing of log queries, so that users can find out why their default view is slow
+
+use std::{collections::HashMap, time::Duration};
+
+use itertools::Itertools;
+use regex::Regex;
+
+use crate::messages::{RevsetFunctionTiming, RevsetTiming, SlowQueries};
+
+// a first page slower than this makes the UI feel broken
+const SLOW_QUERY_THRESHOLD: Duration = Duration::from_secs(1);
+
+// filters which have to read every commit in their input
+const COMMIT_FILTERS: [&str; 7] = [
+    "description",
+    "author",
+    "committer",
+    "file",
+    "diff_contains",
+    "empty",
+    "conflict",
+];
+
+#[derive(Default)]
+struct Timing {
+    evaluations: usize,
+    total: Duration,
+    slowest: Duration,
+}
+
+/// how long each revset took to produce its first page, aggregated over a session
+#[derive(Default)]
+pub struct QueryProfile {
+    timings: HashMap<String, Timing>,
+}
+
+impl QueryProfile {
+    pub fn record(&mut self, revset_str: &str, elapsed: Duration) {
+        let timing = self.timings.entry(revset_str.to_owned()).or_default();
+        timing.evaluations += 1;
+        timing.total += elapsed;
+        timing.slowest = timing.slowest.max(elapsed);
+    }
+
+    pub fn format(&self, default_query: &str) -> SlowQueries {
+        let queries = self
+            .timings
+            .iter()
+            .map(|(query, timing)| RevsetTiming {
+                query: query.clone(),
+                evaluations: timing.evaluations,
+                average_ms: as_millis(timing.total / timing.evaluations as u32),
+                slowest_ms: as_millis(timing.slowest),
+                is_default: query == default_query,
+            })
+            .sorted_by(|a, b| b.average_ms.cmp(&a.average_ms))
+            .collect();
+
+        // a function's share of the time can't be measured, so each is charged for the whole query
+        let mut function_timings: HashMap<String, (usize, Duration)> = HashMap::new();
+        for (query, timing) in &self.timings {
+            for name in function_names(query) {
+                let function_timing = function_timings.entry(name).or_default();
+                function_timing.0 += timing.evaluations;
+                function_timing.1 += timing.total;
+            }
+        }
+        let functions = function_timings
+            .into_iter()
+            .map(|(name, (evaluations, total))| RevsetFunctionTiming {
+                name,
+                evaluations,
+                total_ms: as_millis(total),
+            })
+            .sorted_by(|a, b| b.total_ms.cmp(&a.total_ms))
+            .collect();
+
+        let suggestions = match self.timings.get(default_query) {
+            Some(timing) if timing.total / timing.evaluations as u32 >= SLOW_QUERY_THRESHOLD => {
+                suggest_improvements(default_query)
+            }
+            _ => Vec::new(),
+        };
+
+        SlowQueries {
+            queries,
+            functions,
+            suggestions,
+        }
+    }
+}
+
+pub fn function_names(revset_str: &str) -> Vec<String> {
+    let call = Regex::new(r"([A-Za-z_][A-Za-z0-9_]*)\s*\(").expect("valid regex");
+    call.captures_iter(revset_str)
+        .map(|captures| captures[1].to_owned())
+        .unique()
+        .collect()
+}
+
+/// rules of thumb for revsets which are slow on large repos
+pub fn suggest_improvements(revset_str: &str) -> Vec<String> {
+    let functions = function_names(revset_str);
+    let mut suggestions = Vec::new();
+
+    if functions.iter().any(|name| name == "all") {
+        suggestions.push(
+            "all() includes the whole history; try a bounded set such as ancestors(@, 100)"
+                .to_owned(),
+        );
+    }
+
+    let unbounded_ancestors =
+        Regex::new(r"(^|[^:A-Za-z0-9_)])::[^:]|ancestors\([^,()]*\)").expect("valid regex");
+    if unbounded_ancestors.is_match(revset_str) {
+        suggestions.push(
+            "ancestors are unbounded; limit their depth, e.g. ancestors(x, 20) instead of ::x"
+                .to_owned(),
+        );
+    }
+
+    if let Some(filter) = functions
+        .iter()
+        .find(|name| COMMIT_FILTERS.contains(&name.as_str()))
+    {
+        suggestions.push(format!(
+            "{filter}() has to read every commit it is given; intersect it with a smaller set, such as mutable()"
+        ));
+    }
+
+    suggestions
+}
+
+fn as_millis(duration: Duration) -> u32 {
+    duration.as_millis().try_into().unwrap_or(u32::MAX)
+}
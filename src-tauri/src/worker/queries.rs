@@ -1,24 +1,59 @@
-use std::iter::{Peekable, Skip};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Read},
+    iter::{Peekable, Skip},
+    mem,
+    sync::{Arc, Mutex},
+};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
+use chrono::Local;
 use futures_util::StreamExt;
+use git2::{Odb, Oid};
+use itertools::Itertools;
+use jj_cli::cli_util::short_operation_hash;
 use jj_lib::{
-    backend::{BackendError, CommitId},
+    backend::{CommitId, FileId, TreeValue},
+    commit::Commit,
+    files::{self, ContentHunk, MergeResult},
+    hex_util::to_reverse_hex,
     matchers::EverythingMatcher,
-    merged_tree::TreeDiffStream,
-    revset::Revset,
+    merge::Merge,
+    merged_tree::{MergedTree, TreeDiffStream},
+    object_id::ObjectId,
+    op_store::{self, OperationId, WorkspaceId},
+    op_walk,
+    operation::Operation,
+    repo::Repo,
+    repo_path::{RepoPath, RepoPathBuf},
+    revset::{Revset, RevsetExpression, RevsetResolutionError},
     revset_graph::{RevsetGraphEdge, RevsetGraphEdgeType, TopoGroupedRevsetGraphIterator},
     rewrite,
+    signing::SigStatus,
+    str_util::StringPattern,
 };
 use pollster::FutureExt;
+use thiserror::Error;
 
 use crate::messages::{
-    ChangeKind, LogCoordinates, LogLine, LogPage, LogRow, RevChange, RevHeader, RevId, RevResult,
-    TreePath,
+    self, AnnotationLink, ChangeAnnotation, ChangeHistory, ChangeKind, ChangeMapping, ChangeReview,
+    Checkpoint, ConflictHunk, DragPayload, DragResolution, DragTarget, FileConflict, LargePath,
+    LargePaths, LogCoordinates, LogCount, LogDelta, LogFilter, LogLine, LogPage, LogRow, LogTotal,
+    MergeSegment, OperationPage, OperationRow, PathOwners, PrefixResolution, RevAuthor, RevChange,
+    RevContext, RevHeader, RevId, RevOwners, RevResult, RevSignature, SignatureStatus,
+    TemplateContext, ThreeWayMerge, TreePath, WebTarget, WorkspaceInfo,
+};
+use crate::{
+    config::GGSettings,
+    forge,
+    gui_util::{warning, RevsetError, SNAPSHOT_DESCRIPTION},
 };
 
-use super::WorkspaceSession;
+use super::{diff::sniff_kind, owners::CodeOwners, WorkspaceSession, DEFAULT_PANE};
+
+// each is checked for immutability, which is slow
+const MAX_CANDIDATES: usize = 10;
 
 struct LogStem {
     source: LogCoordinates,
@@ -30,56 +65,395 @@ struct LogStem {
 
 /// state used for init or restart of a query
 pub struct LogQueryState {
+    /// the frontend pane this query's pages are sent to
+    pane: String,
     /// max number of rows per page
     page_size: usize,
     /// number of rows already yielded
     next_row: usize,
     /// ongoing vertical lines; nodes will be placed on or around these
     stems: Vec<Option<LogStem>>,
+    /// rows already yielded, kept so that a refresh can send only what changed
+    sent_rows: Vec<LogRow>,
+    /// minimum size of the next page, used to re-yield everything at once after a refresh
+    refresh_size: usize,
+    /// graph nodes evaluated by this or a previous query
+    cache: Option<RevsetCache>,
+    /// requested row count, which is sent with the next page
+    count: Option<LogCount>,
 }
 
 impl LogQueryState {
-    pub fn new(page_size: usize) -> LogQueryState {
+    pub fn new(pane: &str, page_size: usize) -> LogQueryState {
         LogQueryState {
+            pane: pane.to_owned(),
             page_size,
             next_row: 0,
             stems: Vec::new(),
+            sent_rows: Vec::new(),
+            refresh_size: 0,
+            cache: None,
+            count: None,
+        }
+    }
+
+    pub fn with_count(self, count: Option<LogCount>) -> LogQueryState {
+        LogQueryState { count, ..self }
+    }
+
+    pub fn pane(&self) -> &str {
+        &self.pane
+    }
+
+    /// takes over the evaluated nodes of a query being replaced, in case it was the same revset
+    pub fn reuse_cache(&mut self, previous: Option<LogQueryState>) {
+        if let Some(previous) = previous {
+            self.cache = previous.cache;
+        }
+    }
+
+    /// a row as the frontend last saw it
+    pub fn sent_row(&self, index: usize) -> Option<&LogRow> {
+        self.sent_rows.get(index)
+    }
+
+    /// number of graph nodes which were carried over from before the latest operation
+    #[cfg(test)]
+    pub fn reused_nodes(&self) -> usize {
+        self.cache.as_ref().map_or(0, |cache| cache.reused)
+    }
+
+    /// restarts the query, with a first page covering all the rows yielded so far
+    pub fn refresh(self) -> (LogQueryState, Vec<LogRow>) {
+        let state = LogQueryState {
+            refresh_size: self.next_row,
+            cache: self.cache,
+            ..LogQueryState::new(&self.pane, self.page_size)
+        };
+        (state, self.sent_rows)
+    }
+}
+
+/// topologically-sorted graph nodes of a revset, keyed by query and operation
+pub struct RevsetCache {
+    query: String,
+    op_id: OperationId,
+    view: op_store::View,
+    nodes: Vec<(CommitId, Vec<RevsetGraphEdge>)>,
+    /// whether the nodes extend to the end of the revset
+    complete: bool,
+    /// nodes from before an operation which will follow unchanged once the query reaches the first
+    tail: Vec<(CommitId, Vec<RevsetGraphEdge>)>,
+    /// number of nodes carried over from before an operation
+    reused: usize,
+}
+
+impl RevsetCache {
+    fn new(ws: &WorkspaceSession, query: &str) -> RevsetCache {
+        RevsetCache {
+            query: query.to_owned(),
+            op_id: ws.repo().op_id().clone(),
+            view: ws.view().store_view().clone(),
+            nodes: Vec::new(),
+            complete: false,
+            tail: Vec::new(),
+            reused: 0,
         }
     }
+
+    /// keeps whichever nodes are unaffected by operations since the cache was filled
+    fn revalidate(self, ws: &WorkspaceSession, query: &str, revset: &dyn Revset) -> RevsetCache {
+        if self.query != query {
+            return RevsetCache::new(ws, query);
+        }
+
+        if &self.op_id == ws.repo().op_id() {
+            return self;
+        }
+
+        let valid_len = match self.count_unaffected(ws) {
+            Ok(len) => len,
+            Err(err) => {
+                log::warn!("discarding cached revset: {err:#}");
+                0
+            }
+        };
+
+        // a tail can only be checked against the whole revset, which an unfinished cache hasn't reached;
+        // the walk would find commits below it and discard the tail anyway
+        let mut nodes = self.nodes;
+        let tail = if !self.complete {
+            Vec::new()
+        } else {
+            match find_tail(ws, revset, &nodes[valid_len..]) {
+                Ok(Some(start)) => nodes.split_off(valid_len + start),
+                Ok(None) => Vec::new(),
+                Err(err) => {
+                    log::warn!("discarding cached revset tail: {err:#}");
+                    Vec::new()
+                }
+            }
+        };
+        nodes.truncate(valid_len);
+
+        RevsetCache {
+            reused: nodes.len(),
+            nodes,
+            tail,
+            ..RevsetCache::new(ws, query)
+        }
+    }
+
+    // conservative: ref changes can alter membership anywhere and new commits can be grouped
+    // anywhere, but if commits were only hidden, the nodes sorted before them are still valid
+    fn count_unaffected(&self, ws: &WorkspaceSession) -> Result<usize> {
+        let new_view = ws.view().store_view();
+
+        let mut old_refs = self.view.clone();
+        old_refs.head_ids = new_view.head_ids.clone();
+        if &old_refs != new_view {
+            return Ok(0);
+        }
+
+        let old_heads = RevsetExpression::commits(self.view.head_ids.iter().cloned().collect());
+        let new_heads = RevsetExpression::commits(new_view.head_ids.iter().cloned().collect());
+
+        let mut added = ws.evaluate_revset_expr(old_heads.range(&new_heads))?.iter();
+        if added.next().is_some() {
+            return Ok(0);
+        }
+
+        let hidden: HashSet<CommitId> = ws
+            .evaluate_revset_expr(new_heads.range(&old_heads))?
+            .iter()
+            .collect();
+
+        Ok(self
+            .nodes
+            .iter()
+            .take_while(|(commit_id, edges)| {
+                !hidden.contains(commit_id)
+                    && edges.iter().all(|edge| !hidden.contains(&edge.target))
+            })
+            .count())
+    }
+}
+
+// rewrites put new commits at the front of the graph, but leave older history in place. the nodes
+// from some point on can be reused if they're all ancestors of the first, and every other commit in
+// the new revset descends from it - then nothing else can be sorted among them, and their edges only
+// depend on revset membership below that point, which is checked directly
+fn find_tail(
+    ws: &WorkspaceSession,
+    revset: &dyn Revset,
+    nodes: &[(CommitId, Vec<RevsetGraphEdge>)],
+) -> Result<Option<usize>> {
+    let positions: HashMap<&CommitId, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(position, (commit_id, _))| (commit_id, position))
+        .collect();
+
+    // children are sorted before their parents, so this ends as the last child of each node
+    let mut last_child: Vec<Option<usize>> = vec![None; nodes.len()];
+    for (position, (_, edges)) in nodes.iter().enumerate() {
+        for edge in edges {
+            if edge.edge_type != RevsetGraphEdgeType::Missing {
+                if let Some(&target) = positions.get(&edge.target) {
+                    last_child[target] = Some(position);
+                }
+            }
+        }
+    }
+
+    // a suffix is reached from its first node if every later node has a child at or after it
+    let mut start = None;
+    let mut min_last_child = Some(usize::MAX);
+    for position in (0..nodes.len()).rev() {
+        if min_last_child.is_some_and(|child| child >= position) {
+            start = Some(position);
+        }
+        min_last_child = min_last_child.min(last_child[position]);
+    }
+
+    let Some(start) = start else {
+        return Ok(None);
+    };
+
+    let first = &nodes[start].0;
+    let tail: HashSet<&CommitId> = nodes[start..]
+        .iter()
+        .map(|(commit_id, _)| commit_id)
+        .collect();
+    let index = ws.repo().index();
+    let mut found = 0;
+    for commit_id in revset.iter() {
+        if tail.contains(&commit_id) {
+            found += 1;
+        } else if !index.is_ancestor(first, &commit_id) {
+            return Ok(None);
+        }
+    }
+
+    Ok((found == tail.len()).then_some(start))
+}
+
+/// a query was abandoned in favour of a newer one, so its results aren't wanted
+#[derive(Debug, Error)]
+#[error("Query cancelled")]
+pub struct QueryCancelledError;
+
+/// shared by a worker and the commands which send it queries, which can abandon a pane's query while the
+/// worker is too busy evaluating it to read its event queue
+#[derive(Clone, Default)]
+pub struct QueryCancellation(Arc<Mutex<HashMap<String, u64>>>);
+
+impl QueryCancellation {
+    /// stops any query in progress for the pane; later queries are unaffected
+    pub fn cancel(&self, pane: &str) {
+        *self
+            .0
+            .lock()
+            .expect("cancellation mutex poisoned")
+            .entry(pane.to_owned())
+            .or_default() += 1;
+    }
+
+    pub fn token(&self, pane: &str) -> CancelToken {
+        CancelToken {
+            cancellation: self.clone(),
+            pane: pane.to_owned(),
+            generation: self.generation(pane),
+        }
+    }
+
+    fn generation(&self, pane: &str) -> u64 {
+        self.0
+            .lock()
+            .expect("cancellation mutex poisoned")
+            .get(pane)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// checked by a running query, which is cancelled if its pane has been cancelled since the token was taken
+#[derive(Clone)]
+pub struct CancelToken {
+    cancellation: QueryCancellation,
+    pane: String,
+    generation: u64,
+}
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.generation(&self.pane) != self.generation
+    }
 }
 
 /// live instance of a query
 pub struct LogQuery<'a, 'b: 'a> {
     pub ws: &'a WorkspaceSession<'b>,
-    iter: Peekable<
+    revset: &'a dyn Revset,
+    /// created on demand, once the query runs past the cached nodes
+    iter: Option<
         Skip<
             TopoGroupedRevsetGraphIterator<
                 Box<dyn Iterator<Item = (CommitId, Vec<RevsetGraphEdge>)> + 'a>,
             >,
         >,
     >,
+    cache: RevsetCache,
+    cancel: CancelToken,
     pub state: LogQueryState,
 }
 
 impl<'a, 'b> LogQuery<'a, 'b> {
     pub fn new(
         ws: &'a WorkspaceSession<'b>,
+        revset_str: &str,
         revset: &'a dyn Revset,
-        state: LogQueryState,
+        mut state: LogQueryState,
     ) -> LogQuery<'a, 'b> {
-        let iter = TopoGroupedRevsetGraphIterator::new(revset.iter_graph())
-            .skip(state.next_row)
-            .peekable();
+        let cache = match state.cache.take() {
+            Some(cache) => cache.revalidate(ws, revset_str, revset),
+            None => RevsetCache::new(ws, revset_str),
+        };
+        let cancel = ws.session.cancellation.token(state.pane());
 
-        LogQuery { ws, iter, state }
+        LogQuery {
+            ws,
+            revset,
+            iter: None,
+            cache,
+            cancel,
+            state,
+        }
+    }
+
+    /// returns the state needed to resume this query later
+    pub fn into_state(self) -> LogQueryState {
+        LogQueryState {
+            cache: Some(self.cache),
+            ..self.state
+        }
+    }
+
+    fn next_node(&mut self, row: usize) -> Option<(CommitId, Vec<RevsetGraphEdge>)> {
+        if self.cancel.is_cancelled() {
+            return None;
+        }
+
+        if let Some(node) = self.cache.nodes.get(row) {
+            return Some(node.clone());
+        }
+
+        if self.cache.complete {
+            return None;
+        }
+
+        let cached_len = self.cache.nodes.len();
+        let revset = self.revset;
+        let cancel = self.cancel.clone();
+        let iter = self.iter.get_or_insert_with(|| {
+            // topological grouping can read far ahead before yielding anything, so its input is what gets cut off
+            let nodes: Box<dyn Iterator<Item = _>> = Box::new(
+                revset
+                    .iter_graph()
+                    .take_while(move |_| !cancel.is_cancelled()),
+            );
+            TopoGroupedRevsetGraphIterator::new(nodes).skip(cached_len)
+        });
+
+        match iter.next() {
+            // the rest of the revset is the same as before an operation
+            Some(node) if self.cache.tail.first() == Some(&node) => {
+                let tail = mem::take(&mut self.cache.tail);
+                self.cache.reused += tail.len();
+                self.cache.nodes.extend(tail);
+                self.cache.complete = true;
+                log::debug!("reused {} cached log nodes", self.cache.reused);
+                Some(node)
+            }
+            Some(node) => {
+                self.cache.nodes.push(node.clone());
+                Some(node)
+            }
+            // a cancelled query's nodes are discarded along with it, so it doesn't matter that they're incomplete
+            None => {
+                self.cache.complete = true;
+                None
+            }
+        }
     }
 
     pub fn get_page(&mut self) -> Result<LogPage> {
         let mut rows: Vec<LogRow> = Vec::with_capacity(self.state.page_size); // output rows to draw
         let mut row = self.state.next_row;
-        let max = row + self.state.page_size;
+        let max = row + self.state.page_size.max(self.state.refresh_size);
+        self.state.refresh_size = 0;
 
-        while let Some((commit_id, commit_edges)) = self.iter.next() {
+        while let Some((commit_id, commit_edges)) = self.next_node(row) {
             // output lines to draw for the current row
             let mut lines: Vec<LogLine> = Vec::new();
 
@@ -207,21 +581,160 @@ impl<'a, 'b> LogQuery<'a, 'b> {
             }
         }
 
+        let has_more = self.next_node(row).is_some();
+        let total = self.state.count.take().map(|count| self.count_rows(count));
+        if self.cancel.is_cancelled() {
+            return Err(QueryCancelledError.into());
+        }
+
         self.state.next_row = row;
+        self.state.sent_rows.extend(rows.iter().cloned());
         Ok(LogPage {
             rows,
-            has_more: self.iter.peek().is_some(),
+            has_more,
+            total,
+            warnings: self.ws.take_warnings(),
         })
     }
+
+    /// counts the revset without laying out its graph, which is much cheaper than paging through it
+    fn count_rows(&self, count: LogCount) -> LogTotal {
+        if self.cache.complete {
+            return LogTotal {
+                rows: self.cache.nodes.len(),
+                is_estimate: false,
+            };
+        }
+
+        let limit = match count {
+            LogCount::Estimate => self
+                .ws
+                .settings
+                .query_large_repo_heuristic()
+                .try_into()
+                .unwrap_or(usize::MAX),
+            LogCount::Exact => usize::MAX,
+        };
+        let rows = self
+            .revset
+            .iter()
+            .take(limit)
+            .take_while(|_| !self.cancel.is_cancelled())
+            .count();
+
+        LogTotal {
+            rows,
+            is_estimate: rows == limit,
+        }
+    }
+}
+
+/// evaluates the first page of the latest query ahead of time, caching its nodes and commits
+pub fn warm_up(ws: &WorkspaceSession) -> Result<LogQueryState> {
+    let (revset_str, filter) = match ws.session.pane_queries.get(DEFAULT_PANE) {
+        Some((revset_str, filter)) => (revset_str.clone(), filter.clone()),
+        None => (
+            ws.session
+                .latest_query
+                .clone()
+                .unwrap_or_else(|| ws.default_query()),
+            LogFilter::default(),
+        ),
+    };
+    ws.session
+        .publish_load_stage(messages::LoadStage::EvaluatingQuery);
+    let revset = ws.evaluate_filtered_revset_str(&revset_str, &filter)?;
+
+    let page_size = ws.session.log_page_size;
+    let query_key = query_key(&revset_str, &filter);
+    let state = LogQueryState::new(DEFAULT_PANE, page_size);
+    let mut query = LogQuery::new(ws, &query_key, &*revset, state);
+    for row in 0..page_size {
+        match query.next_node(row) {
+            Some((commit_id, _)) => {
+                ws.get_commit(&commit_id)?;
+            }
+            None => break,
+        }
+    }
+
+    if query.cancel.is_cancelled() {
+        return Err(QueryCancelledError.into());
+    }
+
+    Ok(query.into_state())
+}
+
+/// identifies a query's results for caching, so that differently-filtered queries don't share nodes
+pub fn query_key(revset_str: &str, filter: &LogFilter) -> String {
+    format!("{revset_str}\n{filter:?}")
+}
+
+/// compares the rows of a refreshed page to those previously sent, keyed by commit id - divergent
+/// changes have a row for each commit, and a rewritten commit's row is replaced
+pub fn diff_rows(old_rows: Vec<LogRow>, page: LogPage) -> LogDelta {
+    let mut old_rows: HashMap<String, LogRow> = old_rows
+        .into_iter()
+        .map(|row| (row.revision.id.commit.hex.clone(), row))
+        .collect();
+
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for row in page.rows {
+        match old_rows.remove(&row.revision.id.commit.hex) {
+            None => added.push(row),
+            Some(old_row) if old_row != row => changed.push(row),
+            Some(_) => (),
+        }
+    }
+
+    LogDelta {
+        added,
+        changed,
+        removed: old_rows
+            .into_values()
+            .map(|row| row.revision.id.commit)
+            .collect(),
+        has_more: page.has_more,
+    }
 }
 
 // XXX this is reloading the header, which the client already has
 pub fn query_revision(ws: &WorkspaceSession, id: RevId) -> Result<RevResult> {
-    let commit = match ws.resolve_optional_id(&id)? {
-        Some(commit) => commit,
-        None => return Ok(RevResult::NotFound { id }),
-    };
+    match ws.resolve_optional_id(&id)? {
+        Some(commit) => format_revision(ws, &commit),
+        None => Ok(RevResult::NotFound { id }),
+    }
+}
+
+/// every workspace of the repo, including the one which is open
+pub fn query_workspaces(ws: &WorkspaceSession) -> Result<Vec<WorkspaceInfo>> {
+    ws.view()
+        .wc_commit_ids()
+        .iter()
+        .sorted_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()))
+        .map(|(workspace_id, commit_id)| {
+            Ok(WorkspaceInfo {
+                name: workspace_id.as_str().to_owned(),
+                working_copy: ws.format_header(&ws.get_commit(commit_id)?, None)?,
+                is_current: workspace_id == ws.id(),
+            })
+        })
+        .collect()
+}
+
+/// the working-copy commit of another workspace, as of its last snapshot - it isn't snapshotted
+/// from here, because the files belong to whoever is using that checkout
+pub fn query_workspace(ws: &WorkspaceSession, name: String) -> Result<RevResult> {
+    let commit_id = ws
+        .view()
+        .get_wc_commit_id(&WorkspaceId::new(name.clone()))
+        .ok_or(anyhow!(r#"No workspace named "{name}""#))?;
+
+    format_revision(ws, &ws.get_commit(commit_id)?)
+}
 
+fn format_revision(ws: &WorkspaceSession, commit: &Commit) -> Result<RevResult> {
     let parent_tree = rewrite::merge_commit_trees(ws.repo(), &commit.parents())?;
     let tree = commit.tree()?;
 
@@ -236,7 +749,10 @@ pub fn query_revision(ws: &WorkspaceSession, id: RevId) -> Result<RevResult> {
     let tree_diff = parent_tree.diff_stream(&tree, &EverythingMatcher);
     format_tree_changes(ws, &mut changes, tree_diff).block_on()?;
 
-    let header = ws.format_header(&commit, None)?;
+    let mut header = ws.format_header(commit, None)?;
+    if header.signature.is_some() {
+        header.signature = Some(verify_signature(ws, commit));
+    }
 
     let parents: Result<Vec<RevHeader>> = commit
         .parents()
@@ -259,16 +775,796 @@ pub fn query_revision(ws: &WorkspaceSession, id: RevId) -> Result<RevResult> {
         parents,
         changes,
         conflicts,
+        warnings: ws.take_warnings(),
+    })
+}
+
+// an unusable signing program isn't fatal; the signature is just left unverified
+fn verify_signature(ws: &WorkspaceSession, commit: &Commit) -> RevSignature {
+    match commit.verification() {
+        Ok(Some(verification)) => RevSignature {
+            status: match verification.status {
+                SigStatus::Good => SignatureStatus::Good,
+                SigStatus::Unknown => SignatureStatus::Unknown,
+                SigStatus::Bad => SignatureStatus::Bad,
+            },
+            key: verification.key,
+            display: verification.display,
+        },
+        Ok(None) => RevSignature {
+            status: SignatureStatus::Unverified,
+            key: None,
+            display: None,
+        },
+        Err(err) => {
+            warning!(
+                ws,
+                "signature-unverified",
+                "The signature of {commit} could not be verified: {error}",
+                commit = commit.id().hex(),
+                error = err
+            );
+            RevSignature {
+                status: SignatureStatus::Unverified,
+                key: None,
+                display: None,
+            }
+        }
+    }
+}
+
+/// visible children, found without parsing or evaluating a user revset
+pub fn query_children(ws: &WorkspaceSession, id: RevId) -> Result<Vec<RevHeader>> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+
+    let children = RevsetExpression::commit(commit.id().clone()).children();
+    let children = ws.resolve_multiple(ws.evaluate_revset_expr(children)?)?;
+
+    children
+        .iter()
+        .map(|child| ws.format_header(child, None))
+        .collect()
+}
+
+pub fn query_template_context(ws: &WorkspaceSession, id: RevId) -> Result<TemplateContext> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+    let id = ws.format_id(&commit);
+    let id_length = ws.settings.template_id_length();
+    let author = RevAuthor::new(commit.author(), &ws.date_display);
+
+    // clock skew can put timestamps in the future; those are as new as it gets
+    let age = (Local::now() - author.timestamp)
+        .to_std()
+        .unwrap_or_default();
+    let age_bucket = ws
+        .settings
+        .template_age_buckets()
+        .into_iter()
+        .find(|(_, limit)| age < *limit)
+        .map_or_else(|| "older".to_owned(), |(name, _)| name);
+
+    Ok(TemplateContext {
+        change_id: short_id(&id.change.hex, id.change.prefix.len(), id_length),
+        commit_id: short_id(&id.commit.hex, id.commit.prefix.len(), id_length),
+        author_initials: author_initials(&author.name, &author.email),
+        age_bucket,
+    })
+}
+
+// ids are never shortened past their unique prefix
+fn short_id(hex: &str, prefix_len: usize, id_length: usize) -> String {
+    hex.chars().take(prefix_len.max(id_length)).collect()
+}
+
+/// first and last names, falling back to the email address for nameless authors
+pub fn author_initials(name: &str, email: &str) -> String {
+    let words = name.split_whitespace().collect_vec();
+    let initials: String = match words.as_slice() {
+        [] => email.chars().take(1).collect(),
+        [only] => only.chars().take(1).collect(),
+        [first, .., last] => first.chars().take(1).chain(last.chars().take(1)).collect(),
+    };
+    initials.to_uppercase()
+}
+
+pub fn query_context(ws: &WorkspaceSession, id: RevId) -> Result<RevContext> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+    let index = ws.branches_index();
+
+    // the closest branches are the roots of the branched descendants, which may include this commit
+    let branched = RevsetExpression::commit(commit.id().clone())
+        .descendants()
+        .intersection(
+            &RevsetExpression::branches(StringPattern::everything()).union(
+                &RevsetExpression::remote_branches(
+                    StringPattern::everything(),
+                    StringPattern::everything(),
+                ),
+            ),
+        )
+        .roots();
+    let mut nearest_branches = Vec::new();
+    for commit_id in ws.evaluate_revset_expr(branched)?.iter() {
+        nearest_branches.extend(index.get(&commit_id).iter().cloned());
+    }
+
+    let (in_trunk, trunk_branches) = match ws.find_trunk() {
+        Some(trunk_id) => (
+            ws.repo().index().is_ancestor(commit.id(), &trunk_id),
+            index.get(&trunk_id).to_vec(),
+        ),
+        None => (false, Vec::new()),
+    };
+
+    Ok(RevContext {
+        nearest_branches,
+        in_trunk,
+        trunk_branches,
+    })
+}
+
+/// uses the index directly, so it's cheap enough to call for every potential drop target
+pub fn is_ancestor(
+    ws: &WorkspaceSession,
+    ancestor_id: &messages::CommitId,
+    descendant_id: &messages::CommitId,
+) -> Result<bool> {
+    let index = ws.repo().index();
+    let resolve = |id: &messages::CommitId| {
+        CommitId::try_from_hex(&id.hex)
+            .filter(|commit_id| index.has_id(commit_id))
+            .ok_or_else(|| anyhow!("commit {} not found", id.prefix))
+    };
+
+    Ok(index.is_ancestor(&resolve(ancestor_id)?, &resolve(descendant_id)?))
+}
+
+/// resolves an id the same way mutations do, listing the candidates if it's ambiguous
+pub fn resolve_id_prefix(ws: &WorkspaceSession, prefix: &str) -> Result<PrefixResolution> {
+    let prefix = prefix.trim().to_lowercase();
+    let is_id = |c: char| c.is_ascii_hexdigit() || ('k'..='z').contains(&c);
+    if prefix.is_empty() || !prefix.chars().all(is_id) {
+        return Ok(PrefixResolution::NotFound { prefix });
+    }
+
+    let commits = match ws.evaluate_revset_expr(RevsetExpression::symbol(prefix.clone())) {
+        Ok(revset) => ws.resolve_multiple(revset)?,
+        Err(RevsetError::Resolution(RevsetResolutionError::NoSuchRevision { .. })) => {
+            return Ok(PrefixResolution::NotFound { prefix });
+        }
+        Err(RevsetError::Resolution(
+            RevsetResolutionError::AmbiguousCommitIdPrefix(_)
+            | RevsetResolutionError::AmbiguousChangeIdPrefix(_),
+        )) => {
+            return Ok(PrefixResolution::Ambiguous {
+                candidates: format_candidates(ws, &find_candidates(ws, &prefix)?)?,
+            });
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    match commits.as_slice() {
+        [] => Ok(PrefixResolution::NotFound { prefix }),
+        [commit] => Ok(PrefixResolution::Resolved {
+            id: ws.format_id(commit),
+        }),
+        divergent => Ok(PrefixResolution::Ambiguous {
+            candidates: format_candidates(ws, divergent)?,
+        }),
+    }
+}
+
+/// finds drag endpoints at the latest operation, following changes which were rewritten since the
+/// frontend rendered them, so that a drop can't act on stale ids
+pub fn resolve_drag_payload(
+    ws: &WorkspaceSession,
+    query_state: Option<&LogQueryState>,
+    op_id: &str,
+    payloads: Vec<DragPayload>,
+) -> Result<DragResolution> {
+    let targets = payloads
+        .into_iter()
+        .map(|payload| {
+            let row = payload
+                .row
+                .and_then(|index| query_state.and_then(|state| state.sent_row(index)));
+            resolve_drag_target(ws, payload, row)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let current_op_id = ws.repo().op_id().hex();
+    Ok(DragResolution {
+        is_stale: op_id.is_empty() || !current_op_id.starts_with(op_id),
+        op_id: current_op_id,
+        targets,
     })
 }
 
+fn resolve_drag_target(
+    ws: &WorkspaceSession,
+    payload: DragPayload,
+    row: Option<&LogRow>,
+) -> Result<DragTarget> {
+    let commit_hex = payload
+        .commit_hex
+        .or_else(|| row.map(|row| row.revision.id.commit.hex.clone()));
+    let change_prefix = payload
+        .change_prefix
+        .or_else(|| row.map(|row| row.revision.id.change.hex.clone()));
+
+    // a commit identifies its change even after it's been rewritten and hidden
+    let change_prefix = match (change_prefix, &commit_hex) {
+        (Some(prefix), _) => prefix,
+        (None, Some(hex)) => {
+            let index = ws.repo().index();
+            match CommitId::try_from_hex(hex).filter(|commit_id| index.has_id(commit_id)) {
+                Some(commit_id) => ws.format_id(&ws.get_commit(&commit_id)?).change.hex,
+                None => return Ok(DragTarget::Missing),
+            }
+        }
+        (None, None) => return Ok(DragTarget::Missing),
+    };
+
+    let is_dragged = |id: &RevId| {
+        commit_hex
+            .as_ref()
+            .map_or(true, |hex| id.commit.hex.starts_with(hex.as_str()))
+    };
+
+    match resolve_id_prefix(ws, &change_prefix)? {
+        PrefixResolution::Resolved { id } => {
+            let header = ws.format_header(&ws.resolve_single_commit(&id.commit)?, None)?;
+            if is_dragged(&id) {
+                Ok(DragTarget::Current { header })
+            } else {
+                Ok(DragTarget::Rewritten { header })
+            }
+        }
+        // a divergent change can still be told apart by its commit
+        PrefixResolution::Ambiguous { mut candidates } => {
+            match candidates
+                .iter()
+                .position(|header| commit_hex.is_some() && is_dragged(&header.id))
+            {
+                Some(index) => Ok(DragTarget::Current {
+                    header: candidates.swap_remove(index),
+                }),
+                None => Ok(DragTarget::Ambiguous { candidates }),
+            }
+        }
+        PrefixResolution::NotFound { .. } => Ok(DragTarget::Missing),
+    }
+}
+
+// jj reports ambiguity without saying what matched, so visible commits are searched instead
+pub(super) fn find_candidates(ws: &WorkspaceSession, prefix: &str) -> Result<Vec<Commit>> {
+    let revset = ws.evaluate_revset_expr(RevsetExpression::all())?;
+    revset
+        .commit_change_ids()
+        .filter(|(commit_id, change_id)| {
+            commit_id.hex().starts_with(prefix)
+                || to_reverse_hex(&change_id.hex()).is_some_and(|hex| hex.starts_with(prefix))
+        })
+        .take(MAX_CANDIDATES)
+        .map(|(commit_id, _)| ws.get_commit(&commit_id))
+        .collect()
+}
+
+pub(super) fn format_candidates(
+    ws: &WorkspaceSession,
+    commits: &[Commit],
+) -> Result<Vec<RevHeader>> {
+    commits
+        .iter()
+        .take(MAX_CANDIDATES)
+        .map(|commit| ws.format_header(commit, None))
+        .collect()
+}
+
+/// None if no forge was detected and no templates are configured
+pub fn get_commit_url(ws: &WorkspaceSession, target: WebTarget) -> Result<Option<String>> {
+    let upstream = forge::select_upstream(&ws.format_git_remotes()?);
+    Ok(forge::format_web_url(
+        &ws.settings,
+        upstream.as_ref(),
+        &target,
+    ))
+}
+
+pub fn query_large_paths(ws: &WorkspaceSession, id: RevId, limit: usize) -> Result<LargePaths> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+
+    let git_repo = ws.git_repo()?;
+    let odb = git_repo.as_ref().map(|repo| repo.odb()).transpose()?;
+
+    let mut total_size = 0;
+    let mut file_sizes: Vec<(RepoPathBuf, u64)> = Vec::new();
+    let mut dir_sizes: HashMap<RepoPathBuf, u64> = HashMap::new();
+    for (repo_path, value) in commit.tree()?.entries() {
+        let Some(Some(TreeValue::File { id, .. })) = value.as_resolved() else {
+            continue;
+        };
+
+        let size = file_size(ws, odb.as_ref(), &repo_path, id)?;
+
+        total_size += size;
+        let mut parent = repo_path.parent();
+        while let Some(dir) = parent.filter(|dir| !dir.is_root()) {
+            *dir_sizes.entry(dir.to_owned()).or_default() += size;
+            parent = dir.parent();
+        }
+        file_sizes.push((repo_path, size));
+    }
+
+    let format_largest = |sizes: Vec<(RepoPathBuf, u64)>| -> Vec<LargePath> {
+        sizes
+            .into_iter()
+            .sorted_by(|a, b| b.1.cmp(&a.1))
+            .take(limit)
+            .map(|(path, size)| LargePath {
+                path: ws.format_path(path),
+                size,
+            })
+            .collect()
+    };
+
+    Ok(LargePaths {
+        total_size,
+        files: format_largest(file_sizes),
+        directories: format_largest(dir_sizes.into_iter().collect()),
+    })
+}
+
+pub fn query_owners(ws: &WorkspaceSession, id: RevId) -> Result<RevOwners> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+    let tree = commit.tree()?;
+
+    let mut found = None;
+    for path in super::owners::CODEOWNERS_PATHS {
+        let repo_path = RepoPath::from_internal_string(path);
+        if let Some(content) = read_file(ws, &tree, repo_path)? {
+            found = Some((
+                repo_path,
+                CodeOwners::parse(&String::from_utf8_lossy(&content))?,
+            ));
+            break;
+        }
+    }
+
+    let Some((source, code_owners)) = found else {
+        return Ok(RevOwners {
+            source: None,
+            paths: Vec::new(),
+        });
+    };
+
+    let parent_tree = rewrite::merge_commit_trees(ws.repo(), &commit.parents())?;
+    let paths = parent_tree
+        .diff(&tree, &EverythingMatcher)
+        .map(|(repo_path, _)| PathOwners {
+            owners: code_owners.owners_of(&repo_path).to_vec(),
+            path: ws.format_path(repo_path),
+        })
+        .collect();
+
+    Ok(RevOwners {
+        source: Some(ws.format_path(source)),
+        paths,
+    })
+}
+
+/// operations newest first, continuing after the given id if there is one
+pub fn query_operations(
+    ws: &WorkspaceSession,
+    after: Option<String>,
+    page_size: usize,
+) -> Result<OperationPage> {
+    let policy = ws.settings.operations_snapshots();
+    let mut ops = op_walk::walk_ancestors(&[ws.repo().operation().clone()]).peekable();
+
+    // ancestors are walked from the head each time, so that merged operations aren't repeated
+    if let Some(after) = after {
+        for op in ops.by_ref() {
+            let op = op?;
+            if short_operation_hash(op.id()) == after {
+                // the rest of a collapsed run was part of the previous page
+                if policy == "collapse" && is_snapshot(&op) {
+                    skip_snapshots(&mut ops);
+                }
+                break;
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    while rows.len() < page_size {
+        let Some(op) = ops.next() else {
+            break;
+        };
+        let op = op?;
+
+        let collapsed_snapshots = if is_snapshot(&op) {
+            match policy.as_str() {
+                "hide" => continue,
+                "collapse" => skip_snapshots(&mut ops),
+                _ => 0,
+            }
+        } else {
+            0
+        };
+
+        let metadata = op.metadata();
+        rows.push(OperationRow {
+            id: short_operation_hash(op.id()),
+            description: metadata.description.clone(),
+            timestamp: messages::datetime_from_timestamp(&metadata.start_time)
+                .expect("convert timestamp to datetime")
+                .with_timezone(&Local),
+            formatted_timestamp: ws.date_display.format(&metadata.start_time),
+            tags: metadata.tags.clone(),
+            collapsed_snapshots,
+        });
+    }
+
+    if policy == "hide" {
+        skip_snapshots(&mut ops);
+    }
+
+    Ok(OperationPage {
+        rows,
+        has_more: ops.peek().is_some(),
+    })
+}
+
+/// the commits a change has pointed to, newest first, as of each of the last `limit` operations
+pub fn query_change_history(
+    ws: &WorkspaceSession,
+    id: RevId,
+    limit: usize,
+) -> Result<ChangeHistory> {
+    // an abandoned change can still be found through one of its commits
+    let commit = match ws.resolve_optional_id(&id)? {
+        Some(commit) => commit,
+        None => match CommitId::try_from_hex(&id.commit.hex)
+            .filter(|commit_id| ws.repo().index().has_id(commit_id))
+        {
+            Some(commit_id) => ws.get_commit(&commit_id)?,
+            None => {
+                return Err(anyhow!(
+                    r#""{}" didn't resolve to any revisions"#,
+                    id.change.prefix
+                ))
+            }
+        },
+    };
+    let change_id = commit.change_id();
+
+    let mut ops = op_walk::walk_ancestors(&[ws.repo().operation().clone()]).peekable();
+    let mut entries: Vec<ChangeMapping> = Vec::new();
+    let mut previous: Option<Vec<CommitId>> = None;
+
+    for _ in 0..limit {
+        let Some(op) = ops.next() else {
+            break;
+        };
+        let op = op?;
+
+        // visibility depends on each operation's heads, so its own view of the repo is needed
+        let repo = ws.repo().loader().load_at(&op)?;
+        let mut commit_ids = repo.resolve_change_id(change_id).unwrap_or_default();
+        commit_ids.sort();
+
+        // the change didn't exist yet, or had been abandoned
+        if commit_ids.is_empty() {
+            previous = None;
+            continue;
+        }
+
+        let metadata = op.metadata();
+        let operation_id = short_operation_hash(op.id());
+        let timestamp = messages::datetime_from_timestamp(&metadata.start_time)
+            .expect("convert timestamp to datetime")
+            .with_timezone(&Local);
+        let formatted_timestamp = ws.date_display.format(&metadata.start_time);
+
+        // walking backwards, an unchanged mapping must have begun at an earlier operation
+        if previous.as_ref() == Some(&commit_ids) {
+            if let Some(entry) = entries.last_mut() {
+                entry.operation_id = operation_id;
+                entry.timestamp = timestamp;
+                entry.formatted_timestamp = formatted_timestamp;
+            }
+            continue;
+        }
+
+        entries.push(ChangeMapping {
+            commits: commit_ids
+                .iter()
+                .map(|id| ws.format_commit_id(id))
+                .collect(),
+            operation_id,
+            timestamp,
+            formatted_timestamp,
+        });
+        previous = Some(commit_ids);
+    }
+
+    Ok(ChangeHistory {
+        entries,
+        has_more: ops.peek().is_some(),
+    })
+}
+
+fn is_snapshot(op: &Operation) -> bool {
+    op.metadata().description == SNAPSHOT_DESCRIPTION
+}
+
+// consumes the snapshots which immediately follow, returning how many there were
+fn skip_snapshots(
+    ops: &mut Peekable<impl Iterator<Item = op_store::OpStoreResult<Operation>>>,
+) -> usize {
+    let mut skipped = 0;
+    while let Some(Ok(op)) = ops.peek() {
+        if !is_snapshot(op) {
+            break;
+        }
+        ops.next();
+        skipped += 1;
+    }
+    skipped
+}
+
+/// every annotated change, most recently annotated first, whether or not it's still visible
+pub fn query_annotations(ws: &WorkspaceSession) -> Result<Vec<ChangeAnnotation>> {
+    let mut annotations: Vec<_> = ws
+        .store
+        .annotations()
+        .iter()
+        .map(|(change_id, annotation)| ChangeAnnotation {
+            change_id: change_id.clone(),
+            note: annotation.note.clone(),
+            links: annotation
+                .links
+                .iter()
+                .map(|link| AnnotationLink {
+                    label: link.label.clone(),
+                    url: link.url.clone(),
+                })
+                .collect(),
+            updated: annotation.updated.with_timezone(&Local),
+        })
+        .collect();
+    annotations.sort_by(|a, b| b.updated.cmp(&a.updated));
+
+    Ok(annotations)
+}
+
+/// every change with a tracked review state, most recently updated first
+pub fn query_review_states(ws: &WorkspaceSession) -> Result<Vec<ChangeReview>> {
+    let mut reviews: Vec<_> = ws
+        .store
+        .reviews()
+        .iter()
+        .map(|(change_id, review)| ChangeReview {
+            change_id: change_id.clone(),
+            state: review.state,
+            url: review.url.clone(),
+            updated: review.updated.with_timezone(&Local),
+        })
+        .collect();
+    reviews.sort_by(|a, b| b.updated.cmp(&a.updated));
+
+    Ok(reviews)
+}
+
+pub fn query_checkpoints(ws: &WorkspaceSession) -> Result<Vec<Checkpoint>> {
+    Ok(ws
+        .store
+        .checkpoints()
+        .iter()
+        .map(|checkpoint| Checkpoint {
+            label: checkpoint.label.clone(),
+            operation_id: short_operation_hash(&OperationId::from_hex(&checkpoint.op_id)),
+            created: checkpoint.created.with_timezone(&Local),
+            is_autosave: checkpoint.autosave,
+        })
+        .collect())
+}
+
+pub fn query_conflict(ws: &WorkspaceSession, id: RevId, path: TreePath) -> Result<ThreeWayMerge> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+    let repo_path = RepoPath::from_internal_string(&path.repo_path);
+
+    let value = commit.tree()?.path_value(repo_path);
+    if value.is_resolved() {
+        return Err(anyhow!("{} is not conflicted", path.repo_path));
+    }
+    let file_merge = value.to_file_merge().ok_or(anyhow!(
+        "{} is not a conflict between files",
+        path.repo_path
+    ))?;
+    if file_merge.num_sides() != 2 {
+        return Err(anyhow!(
+            "{} has a {}-sided conflict; only 2-sided conflicts can be edited",
+            path.repo_path,
+            file_merge.num_sides()
+        ));
+    }
+
+    let merge = merge_two_sided(ws, repo_path, &file_merge)?;
+    let segments = merge
+        .hunks
+        .iter()
+        .map(|hunk| match hunk.as_resolved() {
+            Some(resolved) => MergeSegment::Resolved {
+                text: hunk_text(Some(resolved)),
+            },
+            None => MergeSegment::Conflict {
+                base: hunk_text(hunk.get_remove(0)),
+                left: hunk_text(hunk.get_add(0)),
+                right: hunk_text(hunk.get_add(1)),
+            },
+        })
+        .collect();
+
+    Ok(ThreeWayMerge {
+        path: ws.format_path(repo_path),
+        base: String::from_utf8_lossy(&merge.base).into_owned(),
+        left: String::from_utf8_lossy(&merge.left).into_owned(),
+        right: String::from_utf8_lossy(&merge.right).into_owned(),
+        segments,
+    })
+}
+
+/// every conflicted path in a revision, with the regions where two-sided file conflicts disagree
+pub fn query_conflicts(ws: &WorkspaceSession, id: RevId) -> Result<Vec<FileConflict>> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+
+    let mut conflicts = Vec::new();
+    for (repo_path, value) in commit.tree()?.conflicts() {
+        let hunks = match value.to_file_merge() {
+            Some(file_merge) if file_merge.num_sides() == 2 => {
+                merge_two_sided(ws, &repo_path, &file_merge)?
+                    .hunks
+                    .iter()
+                    .filter(|hunk| !hunk.is_resolved())
+                    .map(|hunk| ConflictHunk {
+                        base: hunk_text(hunk.get_remove(0)),
+                        left: hunk_text(hunk.get_add(0)),
+                        right: hunk_text(hunk.get_add(1)),
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        conflicts.push(FileConflict {
+            path: ws.format_path(&repo_path),
+            num_sides: value.num_sides(),
+            hunks,
+        });
+    }
+
+    Ok(conflicts)
+}
+
+/// the contents of a two-sided file conflict, and the result of merging them with conflicts left in place
+pub struct TwoSidedMerge {
+    pub base: Vec<u8>,
+    pub left: Vec<u8>,
+    pub right: Vec<u8>,
+    pub hunks: Vec<Merge<ContentHunk>>,
+}
+
+pub fn merge_two_sided(
+    ws: &WorkspaceSession,
+    repo_path: &RepoPath,
+    file_merge: &Merge<Option<FileId>>,
+) -> Result<TwoSidedMerge> {
+    let read_side = |file_id: Option<&Option<FileId>>| -> Result<Vec<u8>> {
+        let mut content = Vec::new();
+        if let Some(Some(file_id)) = file_id {
+            ws.repo()
+                .store()
+                .read_file(repo_path, file_id)?
+                .read_to_end(&mut content)?;
+        }
+        Ok(content)
+    };
+    let base = read_side(file_merge.get_remove(0))?;
+    let left = read_side(file_merge.get_add(0))?;
+    let right = read_side(file_merge.get_add(1))?;
+
+    let slices = Merge::from_removes_adds(
+        vec![base.as_slice()],
+        vec![left.as_slice(), right.as_slice()],
+    );
+    let hunks = match files::merge(&slices) {
+        MergeResult::Resolved(hunk) => vec![Merge::resolved(hunk)],
+        MergeResult::Conflict(hunks) => hunks,
+    };
+
+    Ok(TwoSidedMerge {
+        base,
+        left,
+        right,
+        hunks,
+    })
+}
+
+fn hunk_text(hunk: Option<&ContentHunk>) -> String {
+    hunk.map(|ContentHunk(text)| String::from_utf8_lossy(text).into_owned())
+        .unwrap_or_default()
+}
+
+/// reads a file's content if the path is a resolved file in the tree
+pub fn read_file(
+    ws: &WorkspaceSession,
+    tree: &MergedTree,
+    repo_path: &RepoPath,
+) -> Result<Option<Vec<u8>>> {
+    let Some(Some(TreeValue::File { id, .. })) = tree.path_value(repo_path).as_resolved().cloned()
+    else {
+        return Ok(None);
+    };
+
+    let mut content = Vec::new();
+    ws.repo()
+        .store()
+        .read_file(repo_path, &id)?
+        .read_to_end(&mut content)?;
+    Ok(Some(content))
+}
+
+/// the git odb can report sizes without reading content; other backends have to read the blob
+pub fn file_size(
+    ws: &WorkspaceSession,
+    odb: Option<&Odb>,
+    repo_path: &RepoPath,
+    id: &FileId,
+) -> Result<u64> {
+    match odb {
+        Some(odb) => Ok(odb.read_header(Oid::from_bytes(id.as_bytes())?)?.0 as u64),
+        None => {
+            let mut reader = ws.repo().store().read_file(repo_path, id)?;
+            Ok(io::copy(&mut reader, &mut io::sink())?)
+        }
+    }
+}
+
 async fn format_tree_changes(
     ws: &WorkspaceSession<'_>,
     changes: &mut Vec<RevChange>,
     mut tree_diff: TreeDiffStream<'_>,
-) -> Result<(), BackendError> {
+) -> Result<()> {
     while let Some((repo_path, entry)) = tree_diff.next().await {
         let (before, after) = entry?;
+        let file_kind = sniff_kind(
+            ws,
+            &repo_path,
+            if after.is_present() { &after } else { &before },
+        )?;
         changes.push(RevChange {
             path: ws.format_path(repo_path),
             kind: if before.is_present() && after.is_present() {
@@ -279,6 +1575,7 @@ async fn format_tree_changes(
                 ChangeKind::Deleted
             },
             has_conflict: !after.is_resolved(),
+            file_kind,
         });
     }
     Ok(())
@@ -0,0 +1,104 @@
+//! Find-in-log, which searches a pane's query in the same order as its rows are laid out
+
+use anyhow::{anyhow, Context, Result};
+use jj_lib::{
+    commit::Commit, hex_util::to_reverse_hex, object_id::ObjectId,
+    revset_graph::TopoGroupedRevsetGraphIterator,
+};
+use regex::{Regex, RegexBuilder};
+
+use crate::{
+    gui_util::WorkspaceSession,
+    messages::{LogSearch, RepoEvent},
+};
+
+use super::queries::QueryCancelledError;
+
+// small enough that the first matches are highlighted quickly, large enough not to flood the frontend with events
+const BATCH_SIZE: usize = 100;
+
+enum SearchPattern {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl SearchPattern {
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            SearchPattern::Substring(needle) => text.to_lowercase().contains(needle),
+            SearchPattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+
+    fn is_commit_match(&self, commit: &Commit) -> bool {
+        let author = commit.author();
+        let change_id = to_reverse_hex(&commit.change_id().hex()).unwrap_or_default();
+
+        self.is_match(commit.description())
+            || self.is_match(&author.name)
+            || self.is_match(&author.email)
+            || self.is_match(&change_id)
+    }
+}
+
+/// matching row indices are published in batches as they're found; the result is sent once the search is complete
+pub fn search_log(
+    ws: &WorkspaceSession,
+    pane: &str,
+    text: &str,
+    is_regex: bool,
+) -> Result<LogSearch> {
+    let Some((revset_str, filter)) = ws.session.pane_queries.get(pane) else {
+        return Err(anyhow!("search requested without query in progress"));
+    };
+
+    // searches are case-insensitive either way, like find-in-page
+    let pattern = if is_regex {
+        SearchPattern::Regex(
+            RegexBuilder::new(text)
+                .case_insensitive(true)
+                .build()
+                .context("parse search pattern")?,
+        )
+    } else {
+        SearchPattern::Substring(text.to_lowercase())
+    };
+
+    // a new query for the pane renumbers its rows, which makes these results meaningless
+    let cancel = ws.session.cancellation.token(pane);
+    let revset = ws.evaluate_filtered_revset_str(revset_str, filter)?;
+    let store = ws.repo().store();
+
+    let mut batch = Vec::new();
+    let mut match_count = 0;
+    for (row, (commit_id, _)) in
+        TopoGroupedRevsetGraphIterator::new(revset.iter_graph()).enumerate()
+    {
+        if cancel.is_cancelled() {
+            return Err(QueryCancelledError.into());
+        }
+
+        let commit = store.get_commit(&commit_id)?;
+        if pattern.is_commit_match(&commit) {
+            batch.push(row);
+        }
+
+        if batch.len() == BATCH_SIZE {
+            match_count += batch.len();
+            ws.publish(RepoEvent::SearchMatches {
+                pane: pane.to_owned(),
+                rows: std::mem::take(&mut batch),
+            });
+        }
+    }
+
+    if !batch.is_empty() {
+        match_count += batch.len();
+        ws.publish(RepoEvent::SearchMatches {
+            pane: pane.to_owned(),
+            rows: batch,
+        });
+    }
+
+    Ok(LogSearch { match_count })
+}
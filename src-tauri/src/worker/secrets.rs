@@ -0,0 +1,103 @@
+//! Pre-push scan for credentials accidentally committed alongside code
+
+use std::{collections::HashSet, io::Read, sync::OnceLock};
+
+use anyhow::Result;
+use jj_lib::{
+    backend::{FileId, TreeValue},
+    commit::Commit,
+    matchers::EverythingMatcher,
+    repo::Repo,
+    repo_path::RepoPath,
+    rewrite,
+};
+use regex::Regex;
+
+use crate::{gui_util::WorkspaceSession, messages::SecretFinding};
+
+// files bigger than this are unlikely to be hand-written, and scanning them would make pushes slow
+const MAX_SCANNED_SIZE: usize = 1024 * 1024;
+
+fn patterns() -> &'static [(&'static str, Regex)] {
+    static PATTERNS: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            ("AWS access key", r"\b(AKIA|ASIA)[0-9A-Z]{16}\b"),
+            (
+                "Private key",
+                r"-----BEGIN ((RSA|DSA|EC|OPENSSH|PGP) )?PRIVATE KEY( BLOCK)?-----",
+            ),
+            (
+                "GitHub token",
+                r"\b(gh[pousr]_[A-Za-z0-9]{36}|github_pat_[A-Za-z0-9_]{82})\b",
+            ),
+            ("GitLab token", r"\bglpat-[A-Za-z0-9_-]{20}\b"),
+            ("Slack token", r"\bxox[abposr]-[A-Za-z0-9-]{10,}\b"),
+        ]
+        .into_iter()
+        .map(|(kind, pattern)| (kind, Regex::new(pattern).expect("valid secret pattern")))
+        .collect()
+    })
+}
+
+/// checks the lines added by each commit against known secret formats
+pub fn scan_commits(ws: &WorkspaceSession, commits: &[Commit]) -> Result<Vec<SecretFinding>> {
+    let mut findings = Vec::new();
+
+    for commit in commits {
+        let parent_tree = rewrite::merge_commit_trees(ws.repo(), &commit.parents())?;
+        let tree = commit.tree()?;
+        for (repo_path, diff) in parent_tree.diff(&tree, &EverythingMatcher) {
+            let (before, after) = diff?;
+            let Some(Some(TreeValue::File { id, .. })) = after.as_resolved() else {
+                continue;
+            };
+            let Some(after_text) = read_text(ws, &repo_path, id)? else {
+                continue;
+            };
+
+            let before_text = match before.as_resolved() {
+                Some(Some(TreeValue::File { id, .. })) => read_text(ws, &repo_path, id)?,
+                _ => None,
+            };
+            let before_lines: HashSet<&str> = before_text
+                .as_deref()
+                .map(|text| text.lines().collect())
+                .unwrap_or_default();
+
+            for (index, line) in after_text.lines().enumerate() {
+                if before_lines.contains(line) {
+                    continue;
+                }
+                for (kind, pattern) in patterns() {
+                    if pattern.is_match(line) {
+                        findings.push(SecretFinding {
+                            commit: ws.format_commit_id(commit.id()),
+                            path: ws.format_path(&repo_path),
+                            line: index + 1,
+                            kind: (*kind).to_owned(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(findings)
+}
+
+// binary and oversized files are skipped
+fn read_text(ws: &WorkspaceSession, repo_path: &RepoPath, id: &FileId) -> Result<Option<String>> {
+    let mut content = Vec::new();
+    ws.repo()
+        .store()
+        .read_file(repo_path, id)?
+        .take(MAX_SCANNED_SIZE as u64 + 1)
+        .read_to_end(&mut content)?;
+
+    if content.len() > MAX_SCANNED_SIZE || content.contains(&0) {
+        return Ok(None);
+    }
+
+    Ok(String::from_utf8(content).ok())
+}
@@ -0,0 +1,105 @@
+//! Description suggestions from a user-configured command, which reads a diff and prints a message
+
+use std::{path::Path, time::Duration};
+
+use anyhow::{anyhow, Result};
+use jj_lib::{
+    diff::{Diff, DiffHunk},
+    matchers::EverythingMatcher,
+    merge::MergedTreeValue,
+    repo_path::RepoPath,
+    rewrite,
+};
+
+use crate::{config::GGSettings, gui_util::WorkspaceSession, messages::RevId};
+
+use super::diff::read_content;
+
+// the suggestion blocks the worker, so it gets a shorter default than interactive tools
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+// summarizers don't need the whole of a huge change, and may reject oversized input
+const MAX_DIFF_SIZE: usize = 256 * 1024;
+
+/// returns None if no command is configured
+pub fn suggest_description(ws: &WorkspaceSession, id: RevId) -> Result<Option<String>> {
+    let Some(mut command) = ws.settings.describe_suggest_command() else {
+        return Ok(None);
+    };
+    let program = command.remove(0);
+
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+    let parent_tree = rewrite::merge_commit_trees(ws.repo(), &commit.parents())?;
+    let tree = commit.tree()?;
+
+    let mut diff = String::new();
+    for (repo_path, values) in parent_tree.diff(&tree, &EverythingMatcher) {
+        let (before, after) = values?;
+        format_file_diff(ws, &mut diff, &repo_path, &before, &after)?;
+        if diff.len() > MAX_DIFF_SIZE {
+            diff.push_str("... diff truncated\n");
+            break;
+        }
+    }
+
+    let output = ws.run_tool(
+        "suggest-command",
+        Path::new(&program),
+        command,
+        diff.into_bytes(),
+        ws.settings.tool_timeout().unwrap_or(DEFAULT_TIMEOUT),
+    )?;
+
+    let suggestion = output.trim();
+    Ok((!suggestion.is_empty()).then(|| suggestion.to_owned()))
+}
+
+// unified-diff style, without context lines or line numbers
+fn format_file_diff(
+    ws: &WorkspaceSession,
+    diff: &mut String,
+    repo_path: &RepoPath,
+    before: &MergedTreeValue,
+    after: &MergedTreeValue,
+) -> Result<()> {
+    let path = repo_path.as_internal_file_string();
+    let (Some(before_content), Some(after_content)) = (
+        read_content(ws, repo_path, before)?,
+        read_content(ws, repo_path, after)?,
+    ) else {
+        diff.push_str(&format!(
+            "--- {path}\n+++ {path}\nconflict or non-file change\n"
+        ));
+        return Ok(());
+    };
+
+    let old_path = if before.is_absent() {
+        "/dev/null"
+    } else {
+        path
+    };
+    let new_path = if after.is_absent() { "/dev/null" } else { path };
+    diff.push_str(&format!("--- {old_path}\n+++ {new_path}\n"));
+
+    if before_content.contains(&0) || after_content.contains(&0) {
+        diff.push_str("binary file changed\n");
+        return Ok(());
+    }
+
+    for hunk in Diff::by_line(&[before_content.as_slice(), after_content.as_slice()]).hunks() {
+        if let DiffHunk::Different(sides) = hunk {
+            diff.push_str("@@\n");
+            for (prefix, side) in ["-", "+"].into_iter().zip(sides) {
+                for line in String::from_utf8_lossy(side).lines() {
+                    diff.push_str(prefix);
+                    diff.push_str(line);
+                    diff.push('\n');
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
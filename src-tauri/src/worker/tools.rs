@@ -0,0 +1,344 @@
+//! Discovery of external merge and diff tools, so that settings can offer a choice of them, and launching of editors
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use jj_lib::{backend::TreeValue, merged_tree::MergedTree, repo_path::RepoPath, rewrite};
+
+use crate::{
+    config::GGSettings,
+    gui_util::WorkspaceSession,
+    messages::{MergeTool, MergeToolSource, RevId, TreePath},
+};
+
+/// Well-known tools which may be installed without being configured; jj's own defaults cover others
+const KNOWN_TOOLS: [(&str, &str, &[&str], &[&str]); 4] = [
+    (
+        "vscode",
+        "code",
+        &["--wait", "--merge", "$left", "$right", "$base", "$output"],
+        &["--wait", "--diff", "$left", "$right"],
+    ),
+    (
+        "p4merge",
+        "p4merge",
+        &["$base", "$left", "$right", "$output"],
+        &["$left", "$right"],
+    ),
+    (
+        "bcompare",
+        "bcompare",
+        &["$left", "$right", "$base", "$output"],
+        &["$left", "$right"],
+    ),
+    (
+        "opendiff",
+        "opendiff",
+        &["$left", "$right", "-ancestor", "$base", "-merge", "$output"],
+        &["$left", "$right"],
+    ),
+];
+
+/// Places GUI installers put programs without adding them to PATH
+const EXTRA_DIRS: [&str; 5] = [
+    "/opt/homebrew/bin",
+    "/usr/local/bin",
+    "/Applications/Visual Studio Code.app/Contents/Resources/app/bin",
+    "/Applications/p4merge.app/Contents/MacOS",
+    "/Applications/Beyond Compare.app/Contents/MacOS",
+];
+
+/// jj configuration takes precedence over git configuration, which takes precedence over known tools
+pub fn discover(ws: &WorkspaceSession) -> Result<Vec<MergeTool>> {
+    let mut tools = BTreeMap::new();
+
+    for (name, program, merge_args, diff_args) in KNOWN_TOOLS {
+        tools.insert(
+            name.to_owned(),
+            MergeTool {
+                name: name.to_owned(),
+                source: MergeToolSource::Known,
+                program: program.to_owned(),
+                path: None,
+                merge_args: merge_args.iter().map(|arg| (*arg).to_owned()).collect(),
+                diff_args: diff_args.iter().map(|arg| (*arg).to_owned()).collect(),
+            },
+        );
+    }
+
+    read_git_tools(ws, &mut tools)?;
+    read_jj_tools(ws, &mut tools);
+
+    Ok(tools
+        .into_values()
+        .map(|tool| MergeTool {
+            path: find_program(&tool.program).map(|path| path.into()),
+            ..tool
+        })
+        .collect())
+}
+
+/// opens a revision's change to one file in a diff tool, returning the pid of the tool's process
+pub fn launch_diff_tool(
+    ws: &WorkspaceSession,
+    id: RevId,
+    path: TreePath,
+    tool_name: &str,
+) -> Result<u32> {
+    let tool = discover(ws)?
+        .into_iter()
+        .find(|tool| tool.name == tool_name)
+        .ok_or(anyhow!("no diff tool named {tool_name}"))?;
+    if tool.diff_args.is_empty() {
+        return Err(anyhow!("{tool_name} can't be used as a diff tool"));
+    }
+
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+    let repo_path = RepoPath::from_internal_string(&path.repo_path);
+    let file_name = path.repo_path.rsplit('/').next().unwrap_or("file");
+
+    let temp_dir = ws.session.tools.create_temp_dir()?;
+    let write_side = |side: &str, tree: &MergedTree| -> Result<PathBuf> {
+        let dir = temp_dir.join(side);
+        fs::create_dir_all(&dir)?;
+        let mut content = Vec::new();
+        if let Some(Some(TreeValue::File { id, .. })) = tree.path_value(repo_path).as_resolved() {
+            ws.repo()
+                .store()
+                .read_file(repo_path, id)?
+                .read_to_end(&mut content)?;
+        }
+        let file = dir.join(file_name);
+        fs::write(&file, content)?;
+        Ok(file)
+    };
+    let left = write_side(
+        "left",
+        &rewrite::merge_commit_trees(ws.repo(), &commit.parents())?,
+    )?;
+    let right = write_side("right", &commit.tree()?)?;
+
+    let (args, env) = diff_command(&tool, &left, &right);
+    let program = match find_program(&tool.program) {
+        Some(program) => program,
+        None if matches!(tool.source, MergeToolSource::Git) => {
+            return Err(anyhow!("{tool_name} is a git tool, which needs sh to run"));
+        }
+        None => PathBuf::from(&tool.program),
+    };
+
+    ws.spawn_tool(&tool.name, &program, args, env, Some(temp_dir))
+}
+
+/// the paths are file names from the repo, so they can't be pasted into a shell command; git tools
+/// read them from variables, as they would when run by git, and only other tools get them as arguments
+pub fn diff_command(
+    tool: &MergeTool,
+    left: &Path,
+    right: &Path,
+) -> (Vec<String>, Vec<(String, PathBuf)>) {
+    match tool.source {
+        MergeToolSource::Git => (
+            tool.diff_args.clone(),
+            vec![
+                ("LOCAL".to_owned(), left.to_owned()),
+                ("REMOTE".to_owned(), right.to_owned()),
+            ],
+        ),
+        MergeToolSource::Jj | MergeToolSource::Known => (
+            tool.diff_args
+                .iter()
+                .map(|arg| {
+                    arg.replace("$left", &left.to_string_lossy())
+                        .replace("$right", &right.to_string_lossy())
+                })
+                .collect(),
+            Vec::new(),
+        ),
+    }
+}
+
+/// opens a file from the working copy, at a line if the editor supports it
+pub fn reveal_in_editor(ws: &WorkspaceSession, path: TreePath, line: Option<usize>) -> Result<u32> {
+    let file = RepoPath::from_internal_string(&path.repo_path).to_fs_path(ws.workspace_root());
+    if !file.is_file() {
+        return Err(anyhow!("{} is not in the working copy", path.repo_path));
+    }
+    let file = file.to_string_lossy().into_owned();
+
+    let (program, args) = editor_command(ws.settings.editor_command(), file, line.unwrap_or(1));
+
+    ws.spawn_tool("editor", &program, args, Vec::new(), None)
+}
+
+/// the configured editor, else vscode, else whatever the OS opens the file with
+pub fn editor_command(
+    command: Option<Vec<String>>,
+    file: String,
+    line: usize,
+) -> (PathBuf, Vec<String>) {
+    let line = line.max(1).to_string();
+
+    match command {
+        Some(mut command) => {
+            let program = command.remove(0);
+            let mentions_path = command.iter().any(|arg| arg.contains("$path"));
+            let mut args: Vec<String> = command
+                .iter()
+                .map(|arg| arg.replace("$path", &file).replace("$line", &line))
+                .collect();
+            if !mentions_path {
+                args.push(file);
+            }
+            (
+                find_program(&program).unwrap_or_else(|| PathBuf::from(&program)),
+                args,
+            )
+        }
+        None => match find_program("code") {
+            Some(code) => (code, vec!["--goto".to_owned(), format!("{file}:{line}")]),
+            None => default_opener(file),
+        },
+    }
+}
+
+/// the handler the OS would use for the file, which can't be told a line; the file is always a single
+/// argument, since a program which re-parses its command line (like cmd) would run anything after a `&`
+pub fn default_opener(file: String) -> (PathBuf, Vec<String>) {
+    if cfg!(windows) {
+        (PathBuf::from("explorer"), vec![file])
+    } else if cfg!(target_os = "macos") {
+        // -t picks the default text editor rather than, say, a browser for html
+        (PathBuf::from("open"), vec!["-t".to_owned(), file])
+    } else {
+        (PathBuf::from("xdg-open"), vec![file])
+    }
+}
+
+fn read_jj_tools(ws: &WorkspaceSession, tools: &mut BTreeMap<String, MergeTool>) {
+    let Ok(table) = ws.settings.config().get_table("merge-tools") else {
+        return;
+    };
+
+    for (name, value) in table {
+        let Ok(tool) = value.into_table() else {
+            continue;
+        };
+
+        let args = |key: &str| -> Vec<String> {
+            tool.get(key)
+                .and_then(|value| value.clone().into_array().ok())
+                .map(|values| {
+                    values
+                        .into_iter()
+                        .filter_map(|value| value.into_string().ok())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let tool = MergeTool {
+            source: MergeToolSource::Jj,
+            program: tool
+                .get("program")
+                .and_then(|value| value.clone().into_string().ok())
+                .unwrap_or_else(|| name.clone()),
+            path: None,
+            merge_args: args("merge-args"),
+            diff_args: args("diff-args"),
+            name: name.clone(),
+        };
+        tools.insert(name, tool);
+    }
+}
+
+// git tools are shell commands using git's variable names, which are set when they're launched
+fn read_git_tools(ws: &WorkspaceSession, tools: &mut BTreeMap<String, MergeTool>) -> Result<()> {
+    let config = match ws.git_repo()? {
+        Some(repo) => repo.config()?,
+        None => git2::Config::open_default()?,
+    };
+
+    // without a shell, git tools are listed but not available
+    let shell = find_shell().map_or_else(|| "sh".to_owned(), |shell| shell.display().to_string());
+
+    let mut git_tools: BTreeMap<String, MergeTool> = BTreeMap::new();
+    config
+        .entries(Some(r"^(merge|diff)tool\..+\.cmd$"))?
+        .for_each(|entry| {
+            let (Some(key), Some(cmd)) = (entry.name(), entry.value()) else {
+                return;
+            };
+            let Some((section, name)) =
+                key.strip_suffix(".cmd").and_then(|key| key.split_once('.'))
+            else {
+                return;
+            };
+
+            let tool = git_tools
+                .entry(name.to_owned())
+                .or_insert_with(|| MergeTool {
+                    name: name.to_owned(),
+                    source: MergeToolSource::Git,
+                    program: shell.clone(),
+                    path: None,
+                    merge_args: Vec::new(),
+                    diff_args: Vec::new(),
+                });
+
+            let args = vec!["-c".to_owned(), cmd.to_owned()];
+            if section == "mergetool" {
+                tool.merge_args = args;
+            } else {
+                tool.diff_args = args;
+            }
+        })?;
+
+    tools.extend(git_tools);
+    Ok(())
+}
+
+// git for windows doesn't put its sh on PATH, but it can be found next to git itself
+fn find_shell() -> Option<PathBuf> {
+    find_program("sh").or_else(|| {
+        let git_root = find_program("git")?.parent()?.parent()?.to_owned();
+        ["bin", "usr/bin"]
+            .iter()
+            .map(|dir| {
+                git_root
+                    .join(dir)
+                    .join("sh")
+                    .with_extension(env::consts::EXE_EXTENSION)
+            })
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+fn find_program(program: &str) -> Option<PathBuf> {
+    let program_path = Path::new(program);
+    if program_path.components().count() > 1 {
+        return program_path.is_file().then(|| program_path.to_owned());
+    }
+
+    let dirs = env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    dirs.into_iter()
+        .chain(EXTRA_DIRS.iter().map(PathBuf::from))
+        .flat_map(|dir| {
+            [
+                dir.join(program),
+                dir.join(program).with_extension(env::consts::EXE_EXTENSION),
+            ]
+        })
+        .find(|candidate| candidate.is_file())
+}
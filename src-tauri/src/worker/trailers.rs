@@ -0,0 +1,76 @@
+//! Git trailers at the end of commit messages, such as the co-authors which forges credit
+
+use crate::messages::CoAuthor;
+
+const CO_AUTHOR_KEY: &str = "Co-authored-by";
+
+/// forges match co-authors by email, and a trailer can't hold line breaks or the address's brackets
+pub fn is_valid_identity(co_author: &CoAuthor) -> bool {
+    let is_plain = |text: &str| !text.is_empty() && !text.contains(['<', '>', '\n', '\r']);
+    let name = co_author.name.trim();
+    let email = co_author.email.trim();
+
+    is_plain(name)
+        && is_plain(email)
+        && !email.contains(char::is_whitespace)
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && !domain.is_empty())
+}
+
+/// replaces the description's co-author trailers, keeping any others in their place
+pub fn set_co_authors(description: &str, co_authors: &[CoAuthor]) -> String {
+    let lines: Vec<&str> = description.trim_end().lines().collect();
+
+    // trailers are the last paragraph, if every line of it is one - but never the subject
+    let block_start = lines
+        .iter()
+        .rposition(|line| line.trim().is_empty())
+        .map_or(0, |index| index + 1);
+    let has_trailers = block_start > 0
+        && block_start < lines.len()
+        && lines[block_start..].iter().all(|line| is_trailer(line));
+    let (body, trailers) = if has_trailers {
+        lines.split_at(block_start)
+    } else {
+        (lines.as_slice(), &[][..])
+    };
+
+    let trailers: Vec<String> = trailers
+        .iter()
+        .filter(|line| !is_co_author(line))
+        .map(|line| line.to_string())
+        .chain(co_authors.iter().map(|co_author| {
+            format!(
+                "{CO_AUTHOR_KEY}: {} <{}>",
+                co_author.name.trim(),
+                co_author.email.trim()
+            )
+        }))
+        .collect();
+
+    let mut new_description = body.join("\n").trim_end().to_owned();
+    if !trailers.is_empty() {
+        if !new_description.is_empty() {
+            new_description.push_str("\n\n");
+        }
+        new_description.push_str(&trailers.join("\n"));
+    }
+    if !new_description.is_empty() {
+        new_description.push('\n');
+    }
+
+    new_description
+}
+
+// "Key: value", where keys are words joined by hyphens
+fn is_trailer(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(key, _)| {
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+fn is_co_author(line: &str) -> bool {
+    line.split_once(':')
+        .is_some_and(|(key, _)| key.trim().eq_ignore_ascii_case(CO_AUTHOR_KEY))
+}
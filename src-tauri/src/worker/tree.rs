@@ -0,0 +1,174 @@
+//! Browsing the contents of a revision, one directory or file at a time
+
+use std::{collections::BTreeSet, io::Read};
+
+use anyhow::{anyhow, Result};
+use jj_lib::{
+    backend::TreeValue,
+    conflicts,
+    repo_path::{RepoPath, RepoPathBuf, RepoPathComponentBuf},
+};
+use pollster::FutureExt;
+
+use crate::{
+    gui_util::WorkspaceSession,
+    messages::{FileContents, FileKind, RevId, TreeEntry, TreeEntryKind, TreePath},
+};
+
+use super::{
+    diff::{detect_kind, preview},
+    queries::file_size,
+};
+
+// enough for any file a person would read through; the rest is left out rather than sent to the frontend
+const MAX_CONTENT_SIZE: usize = 1024 * 1024;
+
+/// lists the direct children of a directory, or of the root if none is given
+pub fn query_revision_tree(
+    ws: &WorkspaceSession,
+    id: RevId,
+    dir: Option<TreePath>,
+) -> Result<Vec<TreeEntry>> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+    let dir = match &dir {
+        Some(dir) => RepoPathBuf::from_internal_string(&dir.repo_path),
+        None => RepoPath::root().to_owned(),
+    };
+
+    let tree = commit.tree()?;
+    let store = ws.repo().store();
+
+    // a conflicted directory has several sides, which may each contain different names
+    let mut names = BTreeSet::<RepoPathComponentBuf>::new();
+    let mut is_dir = false;
+    for value in tree.path_value(&dir).iter().flatten() {
+        if let TreeValue::Tree(tree_id) = value {
+            is_dir = true;
+            for entry in store.get_tree(&dir, tree_id)?.entries_non_recursive() {
+                names.insert(entry.name().to_owned());
+            }
+        }
+    }
+    if !is_dir {
+        return Err(anyhow!(
+            "{} is not a directory",
+            dir.as_internal_file_string()
+        ));
+    }
+
+    let git_repo = ws.git_repo()?;
+    let odb = git_repo.as_ref().map(|repo| repo.odb()).transpose()?;
+
+    let mut entries = Vec::new();
+    for name in names {
+        let repo_path = dir.join(&name);
+
+        // the merged value, rather than any one side's, decides whether the entry is conflicted
+        let value = tree.path_value(&repo_path);
+        let kind = match value.as_resolved() {
+            None => TreeEntryKind::Conflict,
+            Some(None) => continue,
+            Some(Some(TreeValue::File { id, executable })) => TreeEntryKind::File {
+                size: file_size(ws, odb.as_ref(), &repo_path, id)?,
+                is_executable: *executable,
+            },
+            Some(Some(TreeValue::Symlink(id))) => TreeEntryKind::Symlink {
+                target: store.read_symlink(&repo_path, id)?,
+            },
+            Some(Some(TreeValue::Tree(_))) => TreeEntryKind::Directory,
+            Some(Some(TreeValue::GitSubmodule(_))) => TreeEntryKind::Submodule,
+            Some(Some(TreeValue::Conflict(_))) => TreeEntryKind::Conflict,
+        };
+
+        entries.push(TreeEntry {
+            path: ws.format_path(&repo_path),
+            name: name.as_str().to_owned(),
+            kind,
+        });
+    }
+
+    // directories first, like most file browsers
+    entries.sort_by_key(|entry| entry.kind != TreeEntryKind::Directory);
+
+    Ok(entries)
+}
+
+/// a file's content, up to a limit; symlinks show their targets
+pub fn query_file_contents(
+    ws: &WorkspaceSession,
+    id: RevId,
+    path: TreePath,
+) -> Result<FileContents> {
+    let commit = ws.resolve_optional_id(&id)?.ok_or(anyhow!(
+        r#""{}" didn't resolve to any revisions"#,
+        id.change.prefix
+    ))?;
+    let repo_path = RepoPath::from_internal_string(&path.repo_path);
+    let store = ws.repo().store();
+
+    let value = commit.tree()?.path_value(repo_path);
+    let (mut content, size) = match value.as_resolved() {
+        Some(Some(TreeValue::File { id, .. })) => {
+            let git_repo = ws.git_repo()?;
+            let odb = git_repo.as_ref().map(|repo| repo.odb()).transpose()?;
+            let size = file_size(ws, odb.as_ref(), repo_path, id)?;
+
+            let mut content = Vec::new();
+            store
+                .read_file(repo_path, id)?
+                .take(MAX_CONTENT_SIZE as u64)
+                .read_to_end(&mut content)?;
+            (content, size)
+        }
+        Some(Some(TreeValue::Symlink(id))) => {
+            let target = store.read_symlink(repo_path, id)?.into_bytes();
+            let size = target.len() as u64;
+            (target, size)
+        }
+        Some(_) => return Err(anyhow!("{} is not a file", path.repo_path)),
+        // conflicts are shown the way they'd be written to the working copy
+        None => {
+            let mut content = Vec::new();
+            conflicts::materialize(&value, store, repo_path, &mut content).block_on()?;
+            let size = content.len() as u64;
+            (content, size)
+        }
+    };
+    content.truncate(MAX_CONTENT_SIZE);
+
+    let has_conflict = !value.is_resolved();
+    let file_kind = if has_conflict {
+        FileKind::Text
+    } else {
+        detect_kind(repo_path, &content)
+    };
+    let is_truncated = size > content.len() as u64;
+
+    let (file_kind, text) = match file_kind {
+        FileKind::Text => (
+            FileKind::Text,
+            String::from_utf8_lossy(&content).into_owned(),
+        ),
+        FileKind::Image { mime, .. } => (
+            FileKind::Image {
+                mime,
+                before: None,
+                after: preview((!is_truncated).then_some(content.as_slice())),
+            },
+            String::new(),
+        ),
+        FileKind::Binary => (FileKind::Binary, String::new()),
+    };
+
+    Ok(FileContents {
+        path: ws.format_path(repo_path),
+        file_kind,
+        text,
+        size,
+        is_truncated,
+        has_conflict,
+    })
+}
@@ -0,0 +1,155 @@
+//! Watching of the workspace for changes made by other programs, filtered so that build output doesn't
+//! keep triggering snapshots, and of the repo for operations written by other processes
+
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::{
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use jj_lib::gitignore::GitIgnoreFile;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::SessionEvent;
+
+// jj and git write to their own directories whenever we do anything, which would cause a feedback loop
+const INTERNAL_PATTERNS: [&str; 2] = ["/.jj/", "/.git/"];
+
+// changes come in bursts, such as a build or a branch switch, which should be snapshotted once
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+// but a program which never stops writing mustn't postpone snapshots forever
+const MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// watches a workspace until the watcher is dropped, queueing FilesChanged after each burst of relevant changes
+pub fn watch(
+    root: &Path,
+    filter: WatchFilter,
+    queue: Sender<SessionEvent>,
+) -> Result<RecommendedWatcher> {
+    let (changes_tx, changes_rx) = channel();
+
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+            // snapshots read every file, which mustn't lead to another snapshot
+            Ok(event) if event.kind.is_access() => (),
+            Ok(event) => {
+                if filter.any_relevant(event.paths.iter().map(PathBuf::as_path)) {
+                    _ = changes_tx.send(());
+                }
+            }
+            Err(err) => log::warn!("watch workspace: {err}"),
+        })?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+
+    thread::spawn(move || debounce(changes_rx, queue, || SessionEvent::FilesChanged));
+
+    Ok(watcher)
+}
+
+/// watches a repo's op heads until the watcher is dropped, queueing OperationsChanged after each burst of changes
+pub fn watch_operations(
+    repo_dir: &Path,
+    queue: Sender<SessionEvent>,
+) -> Result<RecommendedWatcher> {
+    let (changes_tx, changes_rx) = channel();
+
+    // every operation replaces a file in this directory, including our own, which the session will recognise
+    let heads_dir = repo_dir.join("op_heads").join("heads");
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+            Ok(event) if event.kind.is_access() => (),
+            Ok(_) => _ = changes_tx.send(()),
+            Err(err) => log::warn!("watch operations: {err}"),
+        })?;
+    watcher.watch(&heads_dir, RecursiveMode::NonRecursive)?;
+
+    thread::spawn(move || debounce(changes_rx, queue, || SessionEvent::OperationsChanged));
+
+    Ok(watcher)
+}
+
+// ends when the watcher is dropped, or the worker stops
+fn debounce(changes: Receiver<()>, queue: Sender<SessionEvent>, event: fn() -> SessionEvent) {
+    while changes.recv().is_ok() {
+        let started = Instant::now();
+        loop {
+            match changes.recv_timeout(DEBOUNCE) {
+                Ok(()) if started.elapsed() < MAX_DELAY => continue,
+                Ok(()) | Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        if queue.send(event()).is_err() {
+            return;
+        }
+    }
+}
+
+/// decides which changes in a workspace are worth a snapshot, using gitignore syntax
+pub struct WatchFilter {
+    root: PathBuf,
+    ignores: Arc<GitIgnoreFile>,
+}
+
+impl WatchFilter {
+    pub fn new(root: &Path, patterns: &[String]) -> WatchFilter {
+        let input = INTERNAL_PATTERNS
+            .iter()
+            .copied()
+            .chain(patterns.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        WatchFilter {
+            root: root.to_owned(),
+            ignores: GitIgnoreFile::empty().chain("", input.as_bytes()),
+        }
+    }
+
+    /// changes outside the workspace, or in an ignored file or directory, are irrelevant
+    pub fn is_relevant(&self, path: &Path) -> bool {
+        let Ok(relative_path) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        let mut names = Vec::new();
+        for component in relative_path.components() {
+            match component {
+                Component::Normal(name) => match name.to_str() {
+                    Some(name) => names.push(name),
+                    None => return true,
+                },
+                _ => return false,
+            }
+        }
+
+        // a pattern like "node_modules/" only matches the directory, so check each ancestor too
+        let mut prefix = String::new();
+        for (index, name) in names.iter().enumerate() {
+            prefix.push_str(name);
+            if index == names.len() - 1 {
+                return !self.ignores.matches(&prefix);
+            }
+
+            prefix.push('/');
+            if self.ignores.matches(&prefix) {
+                return false;
+            }
+        }
+
+        // the root itself
+        false
+    }
+
+    /// true if any of a batch of changes should trigger a snapshot
+    pub fn any_relevant<'a>(&self, paths: impl IntoIterator<Item = &'a Path>) -> bool {
+        paths.into_iter().any(|path| self.is_relevant(path))
+    }
+}
@@ -99,7 +99,7 @@ export function mutate<T>(command: string, mutation: T) {
             let value = await fetch;
 
             // succeeded; dismiss modals
-            if (value.type == "Updated" || value.type == "UpdatedSelection" || value.type == "Unchanged") {
+            if (value.type == "Updated" || value.type == "UpdatedSelection" || value.type == "Fetched" || value.type == "Rebased" || value.type == "Unchanged") {
                 if (value.type != "Unchanged") {
                     repoStatusEvent.set(value.new_status);
                     if (value.type == "UpdatedSelection") {
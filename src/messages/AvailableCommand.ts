@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RefName } from "./RefName";
+
+export interface AvailableCommand { command: string, label: string, branch: RefName | null, remote_name: string | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface BackgroundPolicy { power_saving: boolean, metered: boolean, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevId } from "./RevId";
+
+export interface BackoutRevision { id: RevId, parent_ids: Array<RevId>, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevAuthor } from "./RevAuthor";
+import type { RevId } from "./RevId";
+
+export interface BlameRange { first_line: number, last_line: number, id: RevId, author: RevAuthor, summary: string, }
\ No newline at end of file
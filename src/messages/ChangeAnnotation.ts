@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AnnotationLink } from "./AnnotationLink";
+
+export interface ChangeAnnotation { change_id: string, note: string, links: Array<AnnotationLink>, updated: string, }
\ No newline at end of file
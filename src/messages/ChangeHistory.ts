@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeMapping } from "./ChangeMapping";
+
+export interface ChangeHistory { entries: Array<ChangeMapping>, has_more: boolean, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommitId } from "./CommitId";
+
+export interface ChangeMapping { commits: Array<CommitId>, operation_id: string, timestamp: string, formatted_timestamp: string, }
\ No newline at end of file
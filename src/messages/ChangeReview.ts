@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ReviewState } from "./ReviewState";
+
+export interface ChangeReview { change_id: string, state: ReviewState, url: string | null, updated: string, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface Checkpoint { label: string, operation_id: string, created: string, is_autosave: boolean, }
\ No newline at end of file
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CoAuthor } from "./CoAuthor";
 import type { RevId } from "./RevId";
 
-export interface DescribeRevision { id: RevId, new_description: string, reset_author: boolean, }
\ No newline at end of file
+export interface DescribeRevision { id: RevId, new_description: string, reset_author: boolean, co_authors: Array<CoAuthor> | null, }
\ No newline at end of file
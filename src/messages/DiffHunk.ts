@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiffLine } from "./DiffLine";
+import type { DiffRow } from "./DiffRow";
+
+export type DiffHunk = { "type": "Unified", lines: Array<DiffLine>, } | { "type": "SideBySide", rows: Array<DiffRow>, };
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiffLineKind } from "./DiffLineKind";
+import type { TextRange } from "./TextRange";
+
+export interface DiffLine { kind: DiffLineKind, old_number: number | null, new_number: number | null, text: string, highlights: Array<TextRange>, }
\ No newline at end of file
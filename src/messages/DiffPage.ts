@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiffCursor } from "./DiffCursor";
+import type { FileDiff } from "./FileDiff";
+
+export interface DiffPage { files: Array<FileDiff>, next: DiffCursor | null, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiffLine } from "./DiffLine";
+
+export interface DiffRow { left: DiffLine | null, right: DiffLine | null, }
\ No newline at end of file
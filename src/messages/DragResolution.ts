@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DragTarget } from "./DragTarget";
+
+export interface DragResolution { op_id: string, is_stale: boolean, targets: Array<DragTarget>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevHeader } from "./RevHeader";
+
+export type DragTarget = { "type": "Current", header: RevHeader, } | { "type": "Rewritten", header: RevHeader, } | { "type": "Ambiguous", candidates: Array<RevHeader>, } | { "type": "Missing" };
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevId } from "./RevId";
+
+export interface EditRevisionMetadata { id: RevId, author_name: string | null, author_email: string | null, author_timestamp: string | null, committer_timestamp: string | null, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HistoryFormat } from "./HistoryFormat";
+
+export interface ExportOperationHistory { path: string, format: HistoryFormat, since: string | null, until: string | null, username: string | null, }
\ No newline at end of file
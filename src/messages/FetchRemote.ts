@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface FetchRemote { remote_name: string, }
\ No newline at end of file
+export interface FetchRemote { remote_name: string, branch_patterns: Array<string>, all_remotes: boolean, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BlameRange } from "./BlameRange";
+import type { TreePath } from "./TreePath";
+
+export interface FileBlame { path: TreePath, lines: Array<string>, ranges: Array<BlameRange>, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConflictHunk } from "./ConflictHunk";
+import type { TreePath } from "./TreePath";
+
+export interface FileConflict { path: TreePath, num_sides: number, hunks: Array<ConflictHunk>, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FileKind } from "./FileKind";
+import type { TreePath } from "./TreePath";
+
+export interface FileContents { path: TreePath, file_kind: FileKind, text: string, size: bigint, is_truncated: boolean, has_conflict: boolean, }
\ No newline at end of file
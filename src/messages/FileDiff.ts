@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeKind } from "./ChangeKind";
+import type { DiffHunk } from "./DiffHunk";
+import type { FileKind } from "./FileKind";
+import type { TreePath } from "./TreePath";
+
+export interface FileDiff { path: TreePath, kind: ChangeKind, file_kind: FileKind, has_lines: boolean, continued: boolean, hunks: Array<DiffHunk>, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type FileKind = { "type": "Text" } | { "type": "Binary" } | { "type": "Image", mime: string, before: string | null, after: string | null, };
\ No newline at end of file
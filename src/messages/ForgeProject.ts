@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Forge } from "./Forge";
+
+export interface ForgeProject { forge: Forge, host: string, project: string, }
\ No newline at end of file
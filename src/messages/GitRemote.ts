@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ForgeProject } from "./ForgeProject";
+
+export interface GitRemote { name: string, fetch_url: string, push_url: string | null, last_fetch: string | null, project: ForgeProject | null, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RefName } from "./RefName";
+import type { RevHeader } from "./RevHeader";
+
+export interface IncomingCommits { branch: RefName, count: number, head: RevHeader, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LargePath } from "./LargePath";
+
+export interface LargePaths { total_size: bigint, files: Array<LargePath>, directories: Array<LargePath>, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommitId } from "./CommitId";
+import type { LogRow } from "./LogRow";
+
+export interface LogDelta { added: Array<LogRow>, changed: Array<LogRow>, removed: Array<CommitId>, has_more: boolean, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TreePath } from "./TreePath";
+
+export interface LogFilter { paths: Array<string>, tree_paths: Array<TreePath>, author: string | null, committer: string | null, after: string | null, before: string | null, }
\ No newline at end of file
@@ -1,4 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { LogRow } from "./LogRow";
+import type { LogTotal } from "./LogTotal";
+import type { Warning } from "./Warning";
 
-export interface LogPage { rows: Array<LogRow>, has_more: boolean, }
\ No newline at end of file
+export interface LogPage { rows: Array<LogRow>, has_more: boolean, total: LogTotal | null, warnings: Array<Warning>, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DisplayPath } from "./DisplayPath";
+import type { MergeToolSource } from "./MergeToolSource";
+
+export interface MergeTool { name: string, source: MergeToolSource, program: string, path: DisplayPath | null, merge_args: Array<string>, diff_args: Array<string>, }
\ No newline at end of file
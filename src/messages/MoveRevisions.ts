@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RebaseMode } from "./RebaseMode";
+import type { RevId } from "./RevId";
+
+export interface MoveRevisions { ids: Array<RevId>, parent_ids: Array<RevId>, mode: RebaseMode, skip_empty: boolean | null, }
\ No newline at end of file
@@ -2,4 +2,4 @@
 import type { CommitId } from "./CommitId";
 import type { RevId } from "./RevId";
 
-export interface MoveSource { id: RevId, parent_ids: Array<CommitId>, }
\ No newline at end of file
+export interface MoveSource { id: RevId, parent_ids: Array<CommitId>, skip_empty: boolean | null, }
\ No newline at end of file
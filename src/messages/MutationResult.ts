@@ -1,6 +1,10 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DisplayPath } from "./DisplayPath";
+import type { IncomingCommits } from "./IncomingCommits";
 import type { MultilineString } from "./MultilineString";
+import type { RepoLock } from "./RepoLock";
 import type { RepoStatus } from "./RepoStatus";
 import type { RevHeader } from "./RevHeader";
+import type { SecretFinding } from "./SecretFinding";
 
-export type MutationResult = { "type": "Unchanged" } | { "type": "Updated", new_status: RepoStatus, } | { "type": "UpdatedSelection", new_status: RepoStatus, new_selection: RevHeader, } | { "type": "PreconditionError", message: string, } | { "type": "InternalError", message: MultilineString, };
\ No newline at end of file
+export type MutationResult = { "type": "Unchanged" } | { "type": "Updated", new_status: RepoStatus, } | { "type": "UpdatedSelection", new_status: RepoStatus, new_selection: RevHeader, } | { "type": "PreconditionError", code: string, params: Record<string, string>, message: string, } | { "type": "SecretsFound", remote_name: string, findings: Array<SecretFinding>, } | { "type": "HookFailed", hook: string, output: MultilineString, } | { "type": "Rebased", new_status: RepoStatus, abandoned_empty: number, } | { "type": "Locked", message: MultilineString, locks: Array<RepoLock>, } | { "type": "Fetched", new_status: RepoStatus, new_commits: number, incoming: Array<IncomingCommits>, } | { "type": "InternalError", message: MultilineString, report: DisplayPath | null, };
\ No newline at end of file
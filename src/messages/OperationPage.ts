@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OperationRow } from "./OperationRow";
+
+export interface OperationPage { rows: Array<OperationRow>, has_more: boolean, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface OperationRow { id: string, description: string, timestamp: string, formatted_timestamp: string, tags: Record<string, string>, collapsed_snapshots: number, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevHeader } from "./RevHeader";
+import type { RevId } from "./RevId";
+
+export type PrefixResolution = { "type": "Resolved", id: RevId, } | { "type": "Ambiguous", candidates: Array<RevHeader>, } | { "type": "NotFound", prefix: string, };
\ No newline at end of file
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface PushRemote { remote_name: string, }
\ No newline at end of file
+export interface PushRemote { remote_name: string, allow_secrets: boolean, }
\ No newline at end of file
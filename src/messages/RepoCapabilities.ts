@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface RepoCapabilities { has_git_backend: boolean, has_remotes: boolean, is_signing_configured: boolean, has_watchman: boolean, is_colocated: boolean, }
\ No newline at end of file
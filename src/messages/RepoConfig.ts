@@ -1,5 +1,11 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DateDisplay } from "./DateDisplay";
 import type { DisplayPath } from "./DisplayPath";
+import type { ForgeProject } from "./ForgeProject";
+import type { GitRemote } from "./GitRemote";
+import type { NamedQuery } from "./NamedQuery";
+import type { RefName } from "./RefName";
+import type { RepoCapabilities } from "./RepoCapabilities";
 import type { RepoStatus } from "./RepoStatus";
 
-export type RepoConfig = { "type": "Initial" } | { "type": "Workspace", absolute_path: DisplayPath, git_remotes: Array<string>, default_query: string, latest_query: string, status: RepoStatus, theme: string | null, } | { "type": "TimeoutError" } | { "type": "LoadError", absolute_path: DisplayPath, message: string, } | { "type": "WorkerError", message: string, };
\ No newline at end of file
+export type RepoConfig = { "type": "Initial" } | { "type": "Workspace", absolute_path: DisplayPath, git_remotes: Array<GitRemote>, default_query: string, latest_query: string, named_queries: Array<NamedQuery>, capabilities: RepoCapabilities, status: RepoStatus, theme: string | null, upstream: ForgeProject | null, trunk: RefName | null, dates: DateDisplay, } | { "type": "TimeoutError" } | { "type": "LoadError", absolute_path: DisplayPath, message: string, } | { "type": "WorkerError", message: string, };
\ No newline at end of file
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GitRemote } from "./GitRemote";
+import type { InputRequest } from "./InputRequest";
+import type { LoadStage } from "./LoadStage";
+import type { RepoStatus } from "./RepoStatus";
+
+export type RepoEvent = { "type": "OperationApplied", status: RepoStatus, } | { "type": "BranchesChanged", names: Array<string>, } | { "type": "SnapshotTaken", status: RepoStatus, } | { "type": "RepoChangedExternally", status: RepoStatus, } | { "type": "SnapshotProgress", files_scanned: number, files_added: number | null, current_dir: string | null, } | { "type": "ToolExited", pid: number, name: string, exit_code: number | null, timed_out: boolean, } | { "type": "RemotesChanged", remotes: Array<GitRemote>, } | { "type": "InputRequested", request: InputRequest, } | { "type": "LoadProgress", stage: LoadStage, } | { "type": "SearchMatches", pane: string, rows: Array<number>, };
\ No newline at end of file
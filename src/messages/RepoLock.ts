@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LockHolder } from "./LockHolder";
+
+export interface RepoLock { path: string, holder: LockHolder | null, age_seconds: bigint, is_stale: boolean, }
\ No newline at end of file
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { CommitId } from "./CommitId";
+import type { Warning } from "./Warning";
 
-export interface RepoStatus { operation_description: string, working_copy: CommitId, }
\ No newline at end of file
+export interface RepoStatus { operation_description: string, operation_id: string, working_copy: CommitId, warnings: Array<Warning>, }
\ No newline at end of file
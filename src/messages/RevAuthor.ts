@@ -1,3 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LocalizedText } from "./LocalizedText";
 
-export interface RevAuthor { email: string, name: string, timestamp: string, }
\ No newline at end of file
+export interface RevAuthor { email: string, name: string, timestamp: string, formatted_timestamp: LocalizedText, }
\ No newline at end of file
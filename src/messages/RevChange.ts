@@ -1,5 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ChangeKind } from "./ChangeKind";
+import type { FileKind } from "./FileKind";
 import type { TreePath } from "./TreePath";
 
-export interface RevChange { kind: ChangeKind, path: TreePath, has_conflict: boolean, }
\ No newline at end of file
+export interface RevChange { kind: ChangeKind, path: TreePath, has_conflict: boolean, file_kind: FileKind, }
\ No newline at end of file
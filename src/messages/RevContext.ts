@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RefName } from "./RefName";
+
+export interface RevContext { nearest_branches: Array<RefName>, in_trunk: boolean, trunk_branches: Array<RefName>, }
\ No newline at end of file
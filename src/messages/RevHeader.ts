@@ -4,5 +4,6 @@ import type { MultilineString } from "./MultilineString";
 import type { RefName } from "./RefName";
 import type { RevAuthor } from "./RevAuthor";
 import type { RevId } from "./RevId";
+import type { RevSignature } from "./RevSignature";
 
-export interface RevHeader { id: RevId, description: MultilineString, author: RevAuthor, has_conflict: boolean, is_working_copy: boolean, is_immutable: boolean, branches: Array<RefName>, parent_ids: Array<CommitId>, }
\ No newline at end of file
+export interface RevHeader { id: RevId, description: MultilineString, author: RevAuthor, has_conflict: boolean, is_working_copy: boolean, is_immutable: boolean, branches: Array<RefName>, parent_ids: Array<CommitId>, signature: RevSignature | null, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PathOwners } from "./PathOwners";
+import type { TreePath } from "./TreePath";
+
+export interface RevOwners { source: TreePath | null, paths: Array<PathOwners>, }
\ No newline at end of file
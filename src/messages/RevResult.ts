@@ -3,5 +3,6 @@ import type { RevChange } from "./RevChange";
 import type { RevHeader } from "./RevHeader";
 import type { RevId } from "./RevId";
 import type { TreePath } from "./TreePath";
+import type { Warning } from "./Warning";
 
-export type RevResult = { "type": "NotFound", id: RevId, } | { "type": "Detail", header: RevHeader, parents: Array<RevHeader>, changes: Array<RevChange>, conflicts: Array<TreePath>, };
\ No newline at end of file
+export type RevResult = { "type": "NotFound", id: RevId, } | { "type": "Detail", header: RevHeader, parents: Array<RevHeader>, changes: Array<RevChange>, conflicts: Array<TreePath>, warnings: Array<Warning>, };
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SignatureStatus } from "./SignatureStatus";
+
+export interface RevSignature { status: SignatureStatus, key: string | null, display: string | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface RevsetTiming { query: string, evaluations: number, average_ms: number, slowest_ms: number, is_default: boolean, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommitId } from "./CommitId";
+import type { TreePath } from "./TreePath";
+
+export interface SecretFinding { commit: CommitId, path: TreePath, line: number, kind: string, }
\ No newline at end of file
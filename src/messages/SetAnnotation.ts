@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AnnotationLink } from "./AnnotationLink";
+import type { RevId } from "./RevId";
+
+export interface SetAnnotation { id: RevId, note: string, links: Array<AnnotationLink>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Forge } from "./Forge";
+
+export interface SetForgeToken { forge: Forge, host: string, token: string, }
\ No newline at end of file
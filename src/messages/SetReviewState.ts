@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ReviewState } from "./ReviewState";
+import type { RevId } from "./RevId";
+
+export interface SetReviewState { id: RevId, state: ReviewState | null, url: string | null, }
\ No newline at end of file
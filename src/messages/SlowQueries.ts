@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevsetFunctionTiming } from "./RevsetFunctionTiming";
+import type { RevsetTiming } from "./RevsetTiming";
+
+export interface SlowQueries { queries: Array<RevsetTiming>, functions: Array<RevsetFunctionTiming>, suggestions: Array<string>, }
\ No newline at end of file
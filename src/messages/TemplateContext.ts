@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface TemplateContext { change_id: string, commit_id: string, author_initials: string, age_bucket: string, }
\ No newline at end of file
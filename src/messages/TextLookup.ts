@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LookupSource } from "./LookupSource";
+import type { RevHeader } from "./RevHeader";
+
+export interface TextLookup { source: LookupSource | null, commits: Array<RevHeader>, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MergeSegment } from "./MergeSegment";
+import type { TreePath } from "./TreePath";
+
+export interface ThreeWayMerge { path: TreePath, base: string, left: string, right: string, segments: Array<MergeSegment>, }
\ No newline at end of file
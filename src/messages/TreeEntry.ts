@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TreeEntryKind } from "./TreeEntryKind";
+import type { TreePath } from "./TreePath";
+
+export interface TreeEntry { path: TreePath, name: string, kind: TreeEntryKind, }
\ No newline at end of file
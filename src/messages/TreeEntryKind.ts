@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TreeEntryKind = { "type": "File", size: bigint, is_executable: boolean, } | { "type": "Symlink", target: string, } | { "type": "Directory" } | { "type": "Submodule" } | { "type": "Conflict" };
\ No newline at end of file
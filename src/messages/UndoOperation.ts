@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type UndoOperation = null;
\ No newline at end of file
+export interface UndoOperation { allow_remote: boolean, }
\ No newline at end of file
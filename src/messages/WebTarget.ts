@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommitId } from "./CommitId";
+import type { TreePath } from "./TreePath";
+
+export type WebTarget = { "type": "Commit", id: CommitId, } | { "type": "Branch", name: string, } | { "type": "File", id: CommitId, path: TreePath, line: number | null, };
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevHeader } from "./RevHeader";
+
+export interface WorkspaceInfo { name: string, working_copy: RevHeader, is_current: boolean, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevId } from "./RevId";
+import type { TreePath } from "./TreePath";
+
+export interface WriteResolvedFile { id: RevId, path: TreePath, content: string, }
\ No newline at end of file
@@ -124,7 +124,7 @@ export default class BinaryMutator {
         if (this.#from.type == "Revision") {
             if (this.#to.type == "Revision") {
                 // rebase rev onto single target
-                mutate<MoveRevision>("move_revision", { id: this.#from.header.id, parent_ids: [this.#to.header.id] });
+                mutate<MoveRevision>("move_revision", { id: this.#from.header.id, parent_ids: [this.#to.header.id], skip_empty: null });
                 return;
             } else if (this.#to.type == "Parent") {
                 // rebase between targets 
@@ -133,7 +133,7 @@ export default class BinaryMutator {
             } else if (this.#to.type == "Merge") {
                 // rebase subtree onto additional targets
                 let newParents = [...this.#to.header.parent_ids, this.#from.header.id.commit];
-                mutate<MoveSource>("move_source", { id: this.#to.header.id, parent_ids: newParents });
+                mutate<MoveSource>("move_source", { id: this.#to.header.id, parent_ids: newParents, skip_empty: null });
                 return;
             } else if (this.#to.type == "Repository") {
                 // abandon source
@@ -147,7 +147,7 @@ export default class BinaryMutator {
                 // rebase subtree onto fewer targets 
                 let removeCommit = this.#from.header.id.commit;
                 let newParents = this.#from.child.parent_ids.filter(id => id.hex != removeCommit.hex);
-                mutate<MoveSource>("move_source", { id: this.#from.child.id, parent_ids: newParents });
+                mutate<MoveSource>("move_source", { id: this.#from.child.id, parent_ids: newParents, skip_empty: null });
                 return;
             }
         }
@@ -2,7 +2,8 @@ import type { RevHeader } from "../messages/RevHeader";
 import type { CopyChanges } from "../messages/CopyChanges";
 import type { MoveChanges } from "../messages/MoveChanges";
 import type { TreePath } from "../messages/TreePath";
-import { mutate } from "../ipc";
+import { mutate, trigger } from "../ipc";
+import { logPathFilter } from "../stores";
 
 export default class ChangeMutator {
     #revision: RevHeader;
@@ -25,6 +26,12 @@ export default class ChangeMutator {
             case "restore":
                 this.onRestore();
                 break;
+            case "reveal":
+                this.onReveal();
+                break;
+            case "history":
+                this.onHistory();
+                break;
             default:
                 console.log(`unimplemented mutation '${event}'`, this);
         }
@@ -45,4 +52,12 @@ export default class ChangeMutator {
             paths: [this.#path]
         });
     };
+
+    onReveal = () => {
+        trigger("reveal_in_editor", { path: this.#path, line: null });
+    };
+
+    onHistory = () => {
+        logPathFilter.set([this.#path]);
+    };
 }
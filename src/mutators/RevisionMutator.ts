@@ -6,6 +6,7 @@ import type { CreateRevision } from "../messages/CreateRevision";
 import type { DescribeRevision } from "../messages/DescribeRevision";
 import type { DuplicateRevisions } from "../messages/DuplicateRevisions";
 import type { MoveChanges } from "../messages/MoveChanges";
+import type { SignRevision } from "../messages/SignRevision";
 import { mutate } from "../ipc";
 
 export default class RevisionMutator {
@@ -82,6 +83,13 @@ export default class RevisionMutator {
             id: this.#revision.id,
             new_description,
             reset_author,
+            co_authors: null,
+        });
+    };
+
+    onSign = () => {
+        mutate<SignRevision>("sign_revision", {
+            id: this.#revision.id,
         });
     };
 
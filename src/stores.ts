@@ -1,19 +1,47 @@
 import type { MutationResult } from "./messages/MutationResult";
 import type { RepoConfig } from "./messages/RepoConfig";
+import type { RepoEvent } from "./messages/RepoEvent";
 import type { RepoStatus } from "./messages/RepoStatus";
 import type { RevHeader } from "./messages/RevHeader";
 import type { Operand } from "./messages/Operand";
+import type { LoadStage } from "./messages/LoadStage";
+import type { TreePath } from "./messages/TreePath";
 import { writable } from "svelte/store";
 import { event, type Query } from "./ipc";
 
 export const repoConfigEvent = await event<RepoConfig>("gg://repo/config", { type: "Initial" });
 export const repoStatusEvent = await event<RepoStatus | undefined>("gg://repo/status", undefined);
 export const revisionSelectEvent = await event<RevHeader | undefined>("gg://revision/select", undefined);
+export const repoEvent = await event<RepoEvent | undefined>("gg://repo/event", undefined);
+
+// remotes aren't part of any operation, so changes to them are patched into the current config
+let currentConfig: RepoConfig = { type: "Initial" };
+repoConfigEvent.subscribe((config) => (currentConfig = config));
+repoEvent.subscribe((event) => {
+    if (event?.type == "RemotesChanged" && currentConfig.type == "Workspace") {
+        repoConfigEvent.set({
+            ...currentConfig,
+            git_remotes: event.remotes,
+            capabilities: { ...currentConfig.capabilities, has_remotes: event.remotes.length > 0 },
+        });
+    }
+
+    if (event?.type == "LoadProgress") {
+        loadStage.set(event.stage);
+    }
+
+    // operations from other processes have already been loaded by the worker; a new status reloads the log
+    if (event?.type == "RepoChangedExternally") {
+        repoStatusEvent.set(event.status);
+    }
+});
 
 export const currentMutation = writable<Query<MutationResult> | null>(null);
 export const currentContext = writable<Operand | null>();
 export const currentSource = writable<Operand | null>();
 export const currentTarget = writable<Operand | null>();
+export const logPathFilter = writable<TreePath[]>([]);
+export const loadStage = writable<LoadStage | null>(null);
 
 export function dragOverWidget(event: DragEvent) {
     event.stopPropagation();